@@ -1,43 +1,70 @@
 use clap::{App, Arg, Error};
+use std::ffi::OsString;
 use std::path::PathBuf;
 
 #[derive(Debug, PartialEq)]
 pub enum Args {
-    ExecutableMock { executable_mock_path: PathBuf },
-    Scriptkeeper { script_path: PathBuf, record: bool },
+    ExecutableMock {
+        executable_mock_path: PathBuf,
+    },
+    Scriptkeeper {
+        script_path: PathBuf,
+        record: bool,
+        safe: bool,
+        filter: Option<String>,
+        list: bool,
+    },
 }
 
-pub fn parse_args(args: impl Iterator<Item = String>) -> Args {
+pub fn parse_args(args: impl Iterator<Item = OsString>) -> Args {
     match parse_args_safe(args) {
         Ok(args) => args,
         Err(error) => error.exit(),
     }
 }
 
-fn parse_args_safe(args: impl Iterator<Item = String>) -> Result<Args, Error> {
+fn parse_args_safe(args: impl Iterator<Item = OsString>) -> Result<Args, Error> {
     let args: Vec<_> = args.collect();
-    if args.get(1) == Some(&"--executable-mock".to_string()) {
+    if args.get(1) == Some(&OsString::from("--executable-mock")) {
         Ok(Args::ExecutableMock {
             executable_mock_path: PathBuf::from(
                 args.get(2).expect("missing argument: executable mock file"),
             ),
         })
     } else {
-        let matches = App::new("scriptkeeper")
-            .arg(Arg::with_name("record").short("r").long("record").help(
-                "[EXPERIMENTAL] Runs the script (without mocking out anything), \
+        let matches =
+            App::new("scriptkeeper")
+                .arg(Arg::with_name("record").short("r").long("record").help(
+                    "[EXPERIMENTAL] Runs the script (without mocking out anything), \
                  records a test case and prints it to stdout",
-            ))
-            .arg(
-                Arg::with_name("program")
-                    .help("the program to test")
-                    .required(true)
-                    .index(1),
-            )
-            .get_matches_from_safe(args)?;
+                ))
+                .arg(Arg::with_name("safe").long("safe").help(
+                    "Blocks deleting, renaming, truncating and writing files \
+                 (outside of 'allowedWrites') with EPERM and reports every blocked operation",
+                ))
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .takes_value(true)
+                        .value_name("pattern")
+                        .help("Only runs tests with names matching the given regular expression"),
+                )
+                .arg(Arg::with_name("list").long("list").help(
+                    "Lists the names and numbers of steps of the tests instead of running them",
+                ))
+                .arg(
+                    Arg::with_name("program")
+                        .help("the program to test")
+                        .required(true)
+                        .index(1),
+                )
+                .get_matches_from_safe(args)?;
         Ok(Args::Scriptkeeper {
-            script_path: PathBuf::from(matches.value_of("program").unwrap()),
+            script_path: PathBuf::from(matches.value_of_os("program").unwrap()),
             record: matches.is_present("record"),
+            safe: matches.is_present("safe"),
+            filter: matches.value_of("filter").map(String::from),
+            list: matches.is_present("list"),
         })
     }
 }
@@ -47,24 +74,47 @@ mod parse_args_safe {
     use super::*;
     use crate::R;
     use pretty_assertions::assert_eq;
+    use std::os::unix::ffi::OsStringExt;
 
     #[test]
     fn returns_the_given_script() -> R<()> {
         assert_eq!(
-            parse_args_safe(vec!["program", "file"].into_iter().map(String::from))?,
+            parse_args_safe(vec!["program", "file"].into_iter().map(OsString::from))?,
             Args::Scriptkeeper {
                 script_path: PathBuf::from("file"),
-                record: false
+                record: false,
+                safe: false,
+                filter: None,
+                list: false,
             }
         );
         Ok(())
     }
 
     #[test]
-    fn errors_on_additional_arguments() {
-        assert!(
-            parse_args_safe(vec!["program", "file", "foo"].into_iter().map(String::from)).is_err(),
+    fn allows_script_paths_that_are_not_valid_utf8() -> R<()> {
+        let script = OsString::from_vec(b"\xff.sh".to_vec());
+        assert_eq!(
+            parse_args_safe(vec![OsString::from("program"), script.clone()].into_iter())?,
+            Args::Scriptkeeper {
+                script_path: PathBuf::from(script),
+                record: false,
+                safe: false,
+                filter: None,
+                list: false,
+            }
         );
+        Ok(())
+    }
+
+    #[test]
+    fn errors_on_additional_arguments() {
+        assert!(parse_args_safe(
+            vec!["program", "file", "foo"]
+                .into_iter()
+                .map(OsString::from)
+        )
+        .is_err(),);
     }
 
     #[test]
@@ -73,11 +123,71 @@ mod parse_args_safe {
             parse_args_safe(
                 vec!["program", "--record", "file"]
                     .into_iter()
-                    .map(String::from),
+                    .map(OsString::from),
+            )?,
+            Args::Scriptkeeper {
+                script_path: PathBuf::from("file"),
+                record: true,
+                safe: false,
+                filter: None,
+                list: false,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn respects_the_safe_flag() -> R<()> {
+        assert_eq!(
+            parse_args_safe(
+                vec!["program", "--safe", "file"]
+                    .into_iter()
+                    .map(OsString::from)
+            )?,
+            Args::Scriptkeeper {
+                script_path: PathBuf::from("file"),
+                record: false,
+                safe: true,
+                filter: None,
+                list: false,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn respects_the_filter_option() -> R<()> {
+        assert_eq!(
+            parse_args_safe(
+                vec!["program", "--filter", "foo", "file"]
+                    .into_iter()
+                    .map(OsString::from)
+            )?,
+            Args::Scriptkeeper {
+                script_path: PathBuf::from("file"),
+                record: false,
+                safe: false,
+                filter: Some("foo".to_string()),
+                list: false,
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn respects_the_list_flag() -> R<()> {
+        assert_eq!(
+            parse_args_safe(
+                vec!["program", "--list", "file"]
+                    .into_iter()
+                    .map(OsString::from)
             )?,
             Args::Scriptkeeper {
                 script_path: PathBuf::from("file"),
-                record: true
+                record: false,
+                safe: false,
+                filter: None,
+                list: true,
             }
         );
         Ok(())
@@ -93,7 +203,7 @@ mod parse_args_safe {
                 parse_args_safe(
                     vec!["program", "--executable-mock", "file"]
                         .into_iter()
-                        .map(String::from)
+                        .map(OsString::from)
                 )?,
                 Args::ExecutableMock {
                     executable_mock_path: PathBuf::from("file")
@@ -108,7 +218,7 @@ mod parse_args_safe {
                 parse_args_safe(
                     vec!["program", "--executable-mock", "file", "foo", "bar"]
                         .into_iter()
-                        .map(String::from)
+                        .map(OsString::from)
                 )?,
                 Args::ExecutableMock {
                     executable_mock_path: PathBuf::from("file")
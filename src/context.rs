@@ -42,6 +42,18 @@ impl Context {
         }
     }
 
+    pub fn colors_enabled(&self) -> bool {
+        match self {
+            Context::Context { .. } => {
+                nix::unistd::isatty(1).unwrap_or(false)
+                    && std::env::var_os("NO_COLOR").is_none()
+                    && std::env::var_os("TERM").map(|term| term != "dumb") == Some(true)
+            }
+            #[cfg(feature = "test")]
+            Context::TestContext { .. } => false,
+        }
+    }
+
     pub fn stdout(&self) -> Box<Write> {
         match self {
             Context::Context { .. } => Box::new(std::io::stdout()),
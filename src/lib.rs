@@ -12,9 +12,9 @@
 #![cfg_attr(test, allow(clippy::module_inception))]
 
 #[macro_use]
-extern crate serde_derive;
+extern crate lazy_static;
 #[macro_use]
-extern crate memoffset;
+extern crate serde_derive;
 
 pub mod cli;
 pub mod context;
@@ -26,13 +26,15 @@ pub mod utils;
 
 use crate::context::Context;
 use crate::recorder::{hole_recorder::run_against_tests, Recorder};
-use crate::test_checker::executable_mock;
+use crate::test_checker::{checker_result::TestLabel, executable_mock};
 use crate::test_spec::yaml::write_yaml;
 use crate::test_spec::Tests;
-use crate::tracer::stdio_redirecting::Capture;
+use crate::tracer::sandbox::Sandbox;
+use crate::tracer::stdio_redirecting::Stdio;
 use crate::tracer::Tracer;
+use regex::Regex;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub type R<A> = Result<A, Box<std::error::Error>>;
 
@@ -88,11 +90,22 @@ pub fn run_main(context: &Context, args: &cli::Args) -> R<ExitCode> {
         cli::Args::Scriptkeeper {
             script_path,
             record,
+            safe,
+            filter,
+            list,
         } => {
+            let filter = match filter {
+                Some(filter) => {
+                    Some(Regex::new(filter).map_err(|error| format!("invalid filter: {}", error))?)
+                }
+                None => None,
+            };
             if *record {
-                print_recorded_test(context, script_path)?
+                print_recorded_test(context, script_path, *safe)?
+            } else if *list {
+                list_tests(context, script_path, filter.as_ref())?
             } else {
-                run_scriptkeeper(context, &script_path)?
+                run_selected_tests(context, script_path, *safe, filter.as_ref())?
             }
         }
     })
@@ -126,27 +139,61 @@ mod run_main {
     }
 }
 
-pub fn run_scriptkeeper(context: &Context, script: &Path) -> R<ExitCode> {
+fn load_tests(script: &Path) -> R<(PathBuf, Tests)> {
     if !script.exists() {
         Err(format!(
             "executable file not found: {}",
             script.to_string_lossy()
         ))?
     }
-    let (test_file_path, tests) = Tests::load(script)?;
-    run_against_tests(&context, script, &test_file_path, tests)
+    Tests::load(script)
+}
+
+pub fn run_scriptkeeper(context: &Context, script: &Path, safe: bool) -> R<ExitCode> {
+    run_selected_tests(context, script, safe, None)
+}
+
+fn run_selected_tests(
+    context: &Context,
+    script: &Path,
+    safe: bool,
+    filter: Option<&Regex>,
+) -> R<ExitCode> {
+    let (test_file_path, tests) = load_tests(script)?;
+    run_against_tests(context, script, &test_file_path, safe, filter, tests)
+}
+
+fn list_tests(context: &Context, script: &Path, filter: Option<&Regex>) -> R<ExitCode> {
+    let (_, tests) = load_tests(script)?;
+    let selected = tests.selected(filter);
+    for (index, test) in tests.tests.iter().enumerate() {
+        if test.matches_filter(filter) {
+            let label = TestLabel {
+                number: index + 1,
+                name: test.name.clone(),
+            };
+            let steps = match test.steps.len() {
+                1 => "1 step".to_string(),
+                steps => format!("{} steps", steps),
+            };
+            let skipped = if selected[index] { "" } else { ", skipped" };
+            writeln!(context.stdout(), "{}: {}{}", label.format(), steps, skipped)?;
+        }
+    }
+    Ok(ExitCode(0))
 }
 
-fn print_recorded_test(context: &Context, program: &Path) -> R<ExitCode> {
+fn print_recorded_test(context: &Context, program: &Path, safe: bool) -> R<ExitCode> {
     let test = Tracer::run_against_mock(
         context,
         &None,
         program,
         vec![],
         HashMap::new(),
-        Capture {
-            stdout: false,
-            stderr: false,
+        Stdio::default(),
+        &Sandbox {
+            safe_mode: safe,
+            ..Sandbox::default()
         },
         Recorder::empty(),
     )?;
@@ -8,6 +8,6 @@ use scriptkeeper::{cli::parse_args, context::Context, run_main, wrap_main};
 fn main() {
     wrap_main(
         |exitcode| exitcode.exit(),
-        || run_main(&Context::new()?, &parse_args(std::env::args())),
+        || run_main(&Context::new()?, &parse_args(std::env::args_os())),
     );
 }
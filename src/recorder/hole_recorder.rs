@@ -4,11 +4,13 @@ use crate::recorder::Recorder;
 use crate::test_checker::checker_result::CheckerResult;
 use crate::test_checker::TestChecker;
 use crate::test_spec::{Test, Tests};
+use crate::tracer::sandbox::Sandbox;
 use crate::tracer::stdio_redirecting::Redirector;
 use crate::tracer::SyscallMock;
 use crate::{ExitCode, R};
-use libc::user_regs_struct;
+use libc::{c_int, user_regs_struct};
 use nix::unistd::Pid;
+use regex::Regex;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 
@@ -16,6 +18,7 @@ pub enum HoleRecorder {
     Checker {
         checker: TestChecker,
         original_test: Test,
+        record_output: bool,
     },
     Recorder {
         recorder: Recorder,
@@ -23,10 +26,11 @@ pub enum HoleRecorder {
 }
 
 impl HoleRecorder {
-    pub fn new(context: &Context, unmocked_commands: &[PathBuf], test: Test) -> HoleRecorder {
+    pub fn new(context: &Context, settings: &Tests, test: Test) -> HoleRecorder {
         HoleRecorder::Checker {
-            checker: TestChecker::new(context, test.clone(), unmocked_commands),
+            checker: TestChecker::new(context, test.clone(), settings),
             original_test: test,
+            record_output: settings.record_output,
         }
     }
 }
@@ -40,11 +44,12 @@ impl SyscallMock for HoleRecorder {
         registers: &user_regs_struct,
         executable: PathBuf,
         arguments: Vec<OsString>,
-    ) -> R<()> {
+    ) -> R<Option<c_int>> {
         match self {
             HoleRecorder::Checker {
                 checker,
                 original_test,
+                record_output,
             } => {
                 if !checker.test.steps.is_empty() {
                     checker.handle_execve_enter(pid, registers, executable, arguments)
@@ -58,6 +63,8 @@ impl SyscallMock for HoleRecorder {
                                 recorder: Recorder::new(
                                     original_test.clone(),
                                     &checker.unmocked_commands,
+                                    checker.executable_matching,
+                                    *record_output,
                                 ),
                             };
                             self.handle_execve_enter(pid, registers, executable, arguments)
@@ -83,10 +90,16 @@ impl SyscallMock for HoleRecorder {
             HoleRecorder::Checker {
                 checker,
                 mut original_test,
+                record_output,
             } => match checker.result {
                 CheckerResult::Pass => {
                     original_test.ends_with_hole = false;
-                    let recorder = Recorder::new(original_test, &checker.unmocked_commands);
+                    let recorder = Recorder::new(
+                        original_test,
+                        &checker.unmocked_commands,
+                        checker.executable_matching,
+                        record_output,
+                    );
                     RecorderResult::Recorded(recorder.handle_end(exitcode, redirector)?)
                 }
                 failure @ CheckerResult::Failure(_) => {
@@ -104,13 +117,57 @@ pub fn run_against_tests(
     context: &Context,
     program: &Path,
     test_file: &Path,
-    Tests {
-        tests,
-        unmocked_commands,
-        interpreter,
-    }: Tests,
+    safe: bool,
+    filter: Option<&Regex>,
+    tests: Tests,
 ) -> R<ExitCode> {
-    let results =
-        RecorderResult::collect_results(context, &interpreter, program, tests, &unmocked_commands)?;
-    RecorderResult::handle_results(context, test_file, unmocked_commands, &results)
+    let selected = tests.selected(filter);
+    if !tests.tests.is_empty() && !selected.contains(&true) {
+        Err("no tests selected")?;
+    }
+    let has_holes = tests
+        .tests
+        .iter()
+        .zip(&selected)
+        .any(|(test, selected)| *selected && test.ends_with_hole);
+    if has_holes && !tests.expanded_features.is_empty() {
+        Err(format!(
+            "holes ('_') can't be filled in test files that use {}, \
+             since these would be lost when rewriting the file",
+            tests
+                .expanded_features
+                .iter()
+                .map(|feature| format!("'{}'", feature))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))?;
+    }
+    let sandbox = Sandbox {
+        safe_mode: safe || tests.safe,
+        allowed_writes: tests.allowed_writes.clone(),
+        block_network: true,
+        allowed_destinations: vec![],
+        network_mappings: vec![],
+    };
+    let settings = Tests {
+        tests: vec![],
+        ..tests
+    };
+    let results = RecorderResult::collect_results(
+        context,
+        program,
+        tests.tests,
+        &selected,
+        &settings,
+        &sandbox,
+    )?;
+    RecorderResult::handle_results(
+        context,
+        test_file,
+        Tests {
+            interpreter: None,
+            ..settings
+        },
+        &results,
+    )
 }
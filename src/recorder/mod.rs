@@ -3,11 +3,14 @@ mod result;
 
 use crate::test_spec::command::Command;
 use crate::test_spec::command_matcher::CommandMatcher;
-use crate::test_spec::{compare_executables, Step, Test};
-use crate::tracer::stdio_redirecting::Redirector;
+use crate::test_spec::{
+    search_path, shorten_executable, ExecutableMatching, OutputMatcher, Step, Test,
+};
+use crate::tracer::stdio_redirecting::{Redirect, Redirector};
+use crate::tracer::tracee_memory;
 use crate::tracer::SyscallMock;
 use crate::R;
-use libc::user_regs_struct;
+use libc::{c_int, user_regs_struct};
 use nix::unistd::Pid;
 use std::ffi::OsString;
 use std::path::PathBuf;
@@ -16,6 +19,8 @@ pub struct Recorder {
     test: Test,
     command: Option<Command>,
     unmocked_commands: Vec<PathBuf>,
+    executable_matching: ExecutableMatching,
+    record_output: bool,
 }
 
 impl Recorder {
@@ -24,16 +29,32 @@ impl Recorder {
             test: Test::new(vec![]),
             command: None,
             unmocked_commands: vec![],
+            executable_matching: ExecutableMatching::Path,
+            record_output: false,
         }
     }
 
-    pub fn new(test: Test, unmocked_commands: &[PathBuf]) -> Recorder {
+    pub fn new(
+        test: Test,
+        unmocked_commands: &[PathBuf],
+        executable_matching: ExecutableMatching,
+        record_output: bool,
+    ) -> Recorder {
         Recorder {
             test,
             command: None,
             unmocked_commands: unmocked_commands.to_vec(),
+            executable_matching,
+            record_output,
         }
     }
+
+    fn recorded_output(redirect: &Redirect) -> R<Option<OutputMatcher>> {
+        Ok(match redirect.captured()? {
+            Some(ref output) if !output.is_empty() => Some(OutputMatcher::exact(output.clone())),
+            _ => None,
+        })
+    }
 }
 
 impl SyscallMock for Recorder {
@@ -41,22 +62,24 @@ impl SyscallMock for Recorder {
 
     fn handle_execve_enter(
         &mut self,
-        _pid: Pid,
-        _registers: &user_regs_struct,
+        pid: Pid,
+        registers: &user_regs_struct,
         executable: PathBuf,
         arguments: Vec<OsString>,
-    ) -> R<()> {
-        let is_unmocked_command = self
-            .unmocked_commands
-            .iter()
-            .any(|unmocked_command| compare_executables(unmocked_command, &executable));
+    ) -> R<Option<c_int>> {
+        let search_path = search_path(&tracee_memory::peek_environment(pid, registers.rdx)?);
+        let executable = shorten_executable(&executable, &search_path, self.executable_matching);
+        let is_unmocked_command = self.unmocked_commands.iter().any(|unmocked_command| {
+            shorten_executable(unmocked_command, &search_path, self.executable_matching)
+                == executable
+        });
         if !is_unmocked_command {
             self.command = Some(Command {
                 executable,
                 arguments,
             });
         }
-        Ok(())
+        Ok(None)
     }
 
     fn handle_exited(&mut self, _pid: Pid, exitcode: i32) -> R<()> {
@@ -65,16 +88,27 @@ impl SyscallMock for Recorder {
             self.test.steps.push_back(Step {
                 command_matcher: CommandMatcher::ExactMatch(command),
                 stdout: vec![],
+                stdout_file: None,
                 exitcode,
+                exec_error: None,
             });
         }
         Ok(())
     }
 
-    fn handle_end(mut self, exitcode: i32, _redirector: &Redirector) -> R<Test> {
+    fn handle_end(mut self, exitcode: i32, redirector: &Redirector) -> R<Test> {
+        self.test.ends_with_hole = false;
         if exitcode != 0 {
             self.test.exitcode = Some(exitcode);
         }
+        if self.record_output {
+            if self.test.stdout.is_none() {
+                self.test.stdout = Recorder::recorded_output(&redirector.stdout)?;
+            }
+            if self.test.stderr.is_none() {
+                self.test.stderr = Recorder::recorded_output(&redirector.stderr)?;
+            }
+        }
         Ok(self.test)
     }
 }
@@ -1,11 +1,14 @@
 use super::hole_recorder::HoleRecorder;
 use crate::context::Context;
 use crate::test_checker::{
-    checker_result::{CheckerResult, CheckerResults},
+    checker_result::{CheckerResult, CheckerResults, TestLabel},
     TestChecker,
 };
-use crate::test_spec::{yaml::write_yaml, Test, Tests};
-use crate::tracer::stdio_redirecting::Capture;
+use crate::test_spec::{
+    fixture::write_fixture, yaml::write_yaml, Destination, OutputMatcher, Test, Tests,
+};
+use crate::tracer::sandbox::Sandbox;
+use crate::tracer::stdio_redirecting::{Capture, Stdio};
 use crate::tracer::Tracer;
 use crate::{ExitCode, R};
 use std::fs::OpenOptions;
@@ -15,13 +18,14 @@ use std::path::{Path, PathBuf};
 pub enum RecorderResult {
     Checked(Test, CheckerResult),
     Recorded(Test),
+    Skipped(Test),
 }
 
 impl RecorderResult {
     fn is_recorded(&self) -> bool {
         match self {
             RecorderResult::Recorded(_) => true,
-            RecorderResult::Checked(_, _) => false,
+            RecorderResult::Checked(_, _) | RecorderResult::Skipped(_) => false,
         }
     }
 
@@ -29,32 +33,32 @@ impl RecorderResult {
         match self {
             RecorderResult::Checked(test, _) => test.clone(),
             RecorderResult::Recorded(test) => test.clone(),
+            RecorderResult::Skipped(test) => test.clone(),
         }
     }
 
     fn get_test_result(&self) -> Option<CheckerResult> {
         match self {
             RecorderResult::Checked(_, test_result) => Some(test_result.clone()),
-            RecorderResult::Recorded(_) => None,
+            RecorderResult::Recorded(_) | RecorderResult::Skipped(_) => None,
         }
     }
 
     pub fn collect_results(
         context: &Context,
-        interpreter: &Option<PathBuf>,
         program: &Path,
         tests: Vec<Test>,
-        unmocked_commands: &[PathBuf],
+        selected: &[bool],
+        settings: &Tests,
+        sandbox: &Sandbox,
     ) -> R<Vec<RecorderResult>> {
         let mut results = vec![];
-        for test in tests.into_iter() {
-            results.push(run_against_test(
-                context,
-                &interpreter,
-                program,
-                unmocked_commands,
-                test,
-            )?);
+        for (test, selected) in tests.into_iter().zip(selected) {
+            results.push(if *selected {
+                run_against_test(context, program, settings, sandbox, test)?
+            } else {
+                RecorderResult::Skipped(test)
+            });
         }
         Ok(results)
     }
@@ -62,22 +66,26 @@ impl RecorderResult {
     pub fn handle_results(
         context: &Context,
         test_file: &Path,
-        unmocked_commands: Vec<PathBuf>,
+        settings: Tests,
         results: &[RecorderResult],
     ) -> R<ExitCode> {
-        let checker_results = CheckerResults(
-            results
+        let checker_results = CheckerResults {
+            results: results
                 .iter()
-                .filter_map(|result| result.get_test_result())
+                .enumerate()
+                .filter_map(|(index, result)| {
+                    result.get_test_result().map(|test_result| {
+                        let label = TestLabel {
+                            number: index + 1,
+                            name: result.get_test().name,
+                        };
+                        (label, test_result)
+                    })
+                })
                 .collect(),
-        );
-        RecorderResult::handle_recorded(
-            context,
-            test_file,
-            unmocked_commands,
-            &results,
-            &checker_results,
-        )?;
+            test_count: results.len(),
+        };
+        RecorderResult::handle_recorded(context, test_file, settings, results, &checker_results)?;
         write!(context.stdout(), "{}", checker_results.format())?;
         Ok(checker_results.exitcode())
     }
@@ -85,7 +93,7 @@ impl RecorderResult {
     fn handle_recorded(
         context: &Context,
         test_file: &Path,
-        unmocked_commands: Vec<PathBuf>,
+        settings: Tests,
         results: &[RecorderResult],
         checker_results: &CheckerResults,
     ) -> R<()> {
@@ -94,15 +102,17 @@ impl RecorderResult {
                 .write(true)
                 .truncate(true)
                 .open(test_file)?;
-            write_yaml(
-                &mut file,
-                &Tests {
-                    tests: results.iter().map(|result| result.get_test()).collect(),
-                    unmocked_commands,
-                    interpreter: None,
+            let mut tests = vec![];
+            for (index, result) in results.iter().enumerate() {
+                let mut test = result.get_test();
+                if let (RecorderResult::Recorded(_), Some(lines)) =
+                    (result, settings.output_fixture_lines)
+                {
+                    write_output_fixtures(test_file, index, lines, &mut test)?;
                 }
-                .serialize()?,
-            )?;
+                tests.push(test);
+            }
+            write_yaml(&mut file, &Tests { tests, ..settings }.serialize()?)?;
             writeln!(
                 context.stdout(),
                 "Test holes filled in {}.",
@@ -113,35 +123,102 @@ impl RecorderResult {
     }
 }
 
+fn count_lines(output: &[u8]) -> usize {
+    let newlines = output.iter().filter(|byte| **byte == b'\n').count();
+    match output.last() {
+        Some(b'\n') | None => newlines,
+        Some(_) => newlines + 1,
+    }
+}
+
+fn write_output_fixture(
+    test_file: &Path,
+    index: usize,
+    max_lines: usize,
+    stream: &str,
+    matcher: &mut Option<OutputMatcher>,
+) -> R<()> {
+    if let Some(matcher) = matcher {
+        if let (Some(output), None) = (&matcher.equals, &matcher.file) {
+            if count_lines(output) > max_lines {
+                let name = test_file
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let path = PathBuf::from("fixtures").join(format!(
+                    "{}-{}.{}",
+                    name.trim_end_matches(".test.yaml"),
+                    index + 1,
+                    stream
+                ));
+                write_fixture(
+                    test_file.parent().unwrap_or_else(|| Path::new(".")),
+                    &path,
+                    output,
+                )?;
+                matcher.file = Some(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_output_fixtures(
+    test_file: &Path,
+    index: usize,
+    max_lines: usize,
+    test: &mut Test,
+) -> R<()> {
+    write_output_fixture(test_file, index, max_lines, "stdout", &mut test.stdout)?;
+    write_output_fixture(test_file, index, max_lines, "stderr", &mut test.stderr)?;
+    Ok(())
+}
+
 fn run_against_test(
     context: &Context,
-    interpreter: &Option<PathBuf>,
     program: &Path,
-    unmocked_commands: &[PathBuf],
+    settings: &Tests,
+    sandbox: &Sandbox,
     test: Test,
 ) -> R<RecorderResult> {
+    let sandbox = &Sandbox {
+        allowed_destinations: test
+            .allowed_destinations
+            .iter()
+            .map(Destination::resolve)
+            .collect(),
+        network_mappings: test.network.clone(),
+        ..sandbox.clone()
+    };
+    let record_output = test.ends_with_hole && settings.record_output;
     macro_rules! run_against_mock {
         ($syscall_mock:expr) => {
             Tracer::run_against_mock(
                 context,
-                interpreter,
+                &settings.interpreter,
                 program,
                 test.arguments.clone(),
-                test.env.clone(),
-                Capture {
-                    stdout: test.stdout.is_some(),
-                    stderr: test.stderr.is_some(),
+                settings.effective_env(std::env::vars_os, &test),
+                Stdio {
+                    stdin: test.stdin.clone(),
+                    interaction: test.interaction.clone(),
+                    tty: test.tty.clone(),
+                    capture: Capture {
+                        stdout: test.stdout.is_some() || record_output,
+                        stderr: test.stderr.is_some() || record_output,
+                    },
                 },
+                sandbox,
                 $syscall_mock,
             )
         };
     }
     if test.ends_with_hole {
-        run_against_mock!(HoleRecorder::new(context, unmocked_commands, test))
+        run_against_mock!(HoleRecorder::new(context, settings, test))
     } else {
         Ok(RecorderResult::Checked(
             test.clone(),
-            run_against_mock!(TestChecker::new(context, test, unmocked_commands))?,
+            run_against_mock!(TestChecker::new(context, test, settings))?,
         ))
     }
 }
@@ -15,17 +15,18 @@ impl CheckerResult {
         }
     }
 
-    fn format(&self, number: Option<usize>) -> String {
+    fn format(&self, label: Option<&TestLabel>) -> String {
         match self {
             CheckerResult::Failure(error) => {
-                let header = number.map_or("error".to_string(), |number| {
-                    format!("error in test {}", number)
-                });
+                let header = match label {
+                    None => "error".to_string(),
+                    Some(label) => format!("error in {}", label.format()),
+                };
                 format!("{}:\n{}", header, error)
             }
-            CheckerResult::Pass => match number {
+            CheckerResult::Pass => match label {
                 None => panic!("CheckerResult.format: shouldn't happen"),
-                Some(number) => format!("test {}:\n  Tests passed.\n", number),
+                Some(label) => format!("{}:\n  Tests passed.\n", label.format()),
             },
         }
     }
@@ -45,6 +46,12 @@ impl CheckerResult {
         );
     }
 
+    pub fn append_to_failure(&mut self, message: &str) {
+        if let CheckerResult::Failure(error) = self {
+            error.push_str(message);
+        }
+    }
+
     pub fn register_error(&mut self, message: String) {
         match self {
             CheckerResult::Pass => {
@@ -55,29 +62,47 @@ impl CheckerResult {
     }
 }
 
-pub struct CheckerResults(pub Vec<CheckerResult>);
+#[derive(Debug, PartialEq, Clone)]
+pub struct TestLabel {
+    pub number: usize,
+    pub name: Option<String>,
+}
+
+impl TestLabel {
+    pub fn format(&self) -> String {
+        match &self.name {
+            None => format!("test {}", self.number),
+            Some(name) => format!("test {} ({})", self.number, name),
+        }
+    }
+}
+
+pub struct CheckerResults {
+    pub results: Vec<(TestLabel, CheckerResult)>,
+    pub test_count: usize,
+}
 
 impl CheckerResults {
     pub fn format(&self) -> String {
         if self.is_pass() {
             "All tests passed.\n".to_string()
         } else {
-            let CheckerResults(results) = &self;
-            if results.len() == 1 {
-                results.iter().next().unwrap().format(None)
-            } else {
-                results
-                    .iter()
-                    .enumerate()
-                    .map(|(i, result)| result.format(Some(i + 1)))
-                    .collect::<Vec<String>>()
-                    .join("")
-            }
+            self.results
+                .iter()
+                .map(|(label, result)| {
+                    if self.test_count == 1 {
+                        result.format(None)
+                    } else {
+                        result.format(Some(label))
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("")
         }
     }
 
     pub fn is_pass(&self) -> bool {
-        self.0.iter().all(|result| result.is_pass())
+        self.results.iter().all(|(_, result)| result.is_pass())
     }
 
     pub fn exitcode(&self) -> ExitCode {
@@ -0,0 +1,267 @@
+const CONTEXT_LINES: usize = 3;
+const MAX_RENDERED_LINES: usize = 60;
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+fn split_lines(text: &[u8]) -> Vec<&[u8]> {
+    let mut result = vec![];
+    let mut start = 0;
+    for (i, byte) in text.iter().enumerate() {
+        if *byte == b'\n' {
+            result.push(&text[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < text.len() {
+        result.push(&text[start..]);
+    }
+    result
+}
+
+pub fn is_multi_line(text: &[u8]) -> bool {
+    split_lines(text).len() > 1
+}
+
+fn edits(expected: &[&[u8]], received: &[&[u8]]) -> Vec<Edit> {
+    let prefix = expected
+        .iter()
+        .zip(received)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(received[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let expected_middle = &expected[prefix..expected.len() - suffix];
+    let received_middle = &received[prefix..received.len() - suffix];
+    let mut result = vec![Edit::Equal; prefix];
+    result.extend(middle_edits(expected_middle, received_middle));
+    result.extend(vec![Edit::Equal; suffix]);
+    result
+}
+
+fn middle_edits(expected: &[&[u8]], received: &[&[u8]]) -> Vec<Edit> {
+    let (n, m) = (expected.len(), received.len());
+    if (n + 1) * (m + 1) > MAX_TABLE_SIZE {
+        let mut result = vec![Edit::Delete; n];
+        result.extend(vec![Edit::Insert; m]);
+        return result;
+    }
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if expected[i] == received[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == received[j] {
+            result.push(Edit::Equal);
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || table[i + 1][j] >= table[i][j + 1]) {
+            result.push(Edit::Delete);
+            i += 1;
+        } else {
+            result.push(Edit::Insert);
+            j += 1;
+        }
+    }
+    result
+}
+
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut result: Vec<(usize, usize)> = vec![];
+    for (i, edit) in edits.iter().enumerate() {
+        if *edit != Edit::Equal {
+            let start = i.saturating_sub(CONTEXT_LINES);
+            let end = (i + 1 + CONTEXT_LINES).min(edits.len());
+            match result.last_mut() {
+                Some(last) if last.1 >= start => last.1 = end,
+                _ => result.push((start, end)),
+            }
+        }
+    }
+    result
+}
+
+fn paint(line: String, color: Option<&str>) -> String {
+    match color {
+        Some(color) => format!("\x1b[{}m{}\x1b[0m", color, line),
+        None => line,
+    }
+}
+
+fn render_line(prefix: char, line: &[u8], color: Option<&str>) -> Vec<String> {
+    let (content, has_newline) = match line.last() {
+        Some(b'\n') => (&line[..line.len() - 1], true),
+        _ => (line, false),
+    };
+    let mut result = vec![paint(
+        format!("{}{}", prefix, String::from_utf8_lossy(content)),
+        color,
+    )];
+    if !has_newline {
+        result.push("\\ No newline at end of output".to_string());
+    }
+    result
+}
+
+pub fn render(expected: &[u8], received: &[u8], colored: bool) -> Vec<String> {
+    let color = |color| if colored { Some(color) } else { None };
+    let expected = split_lines(expected);
+    let received = split_lines(received);
+    let edits = edits(&expected, &received);
+    let mut result = vec![];
+    for (start, end) in hunks(&edits) {
+        let count =
+            |range: &[Edit], skipped: Edit| range.iter().filter(|edit| **edit != skipped).count();
+        let (expected_start, received_start) = (
+            count(&edits[..start], Edit::Insert),
+            count(&edits[..start], Edit::Delete),
+        );
+        result.push(paint(
+            format!(
+                "@@ -{},{} +{},{} @@",
+                expected_start + 1,
+                count(&edits[start..end], Edit::Insert),
+                received_start + 1,
+                count(&edits[start..end], Edit::Delete),
+            ),
+            color("36"),
+        ));
+        let (mut i, mut j) = (expected_start, received_start);
+        for edit in &edits[start..end] {
+            match edit {
+                Edit::Equal => {
+                    result.extend(render_line(' ', expected[i], None));
+                    i += 1;
+                    j += 1;
+                }
+                Edit::Delete => {
+                    result.extend(render_line('-', expected[i], color("31")));
+                    i += 1;
+                }
+                Edit::Insert => {
+                    result.extend(render_line('+', received[j], color("32")));
+                    j += 1;
+                }
+            }
+        }
+    }
+    if result.len() > MAX_RENDERED_LINES {
+        let omitted = result.len() - MAX_RENDERED_LINES;
+        result.truncate(MAX_RENDERED_LINES);
+        result.push(format!("... ({} more lines)", omitted));
+    }
+    result
+}
+
+#[cfg(test)]
+mod render {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn test_render(expected: &str, received: &str) -> Vec<String> {
+        render(expected.as_bytes(), received.as_bytes(), false)
+    }
+
+    #[test]
+    fn renders_changed_lines() {
+        assert_eq!(
+            test_render("a\nb\nc\n", "a\nx\nc\n"),
+            vec!["@@ -1,3 +1,3 @@", " a", "-b", "+x", " c"]
+        );
+    }
+
+    #[test]
+    fn renders_added_and_removed_lines() {
+        assert_eq!(
+            test_render("a\nb\n", "b\nc\n"),
+            vec!["@@ -1,2 +1,2 @@", "-a", " b", "+c"]
+        );
+    }
+
+    #[test]
+    fn only_renders_context_around_the_differences() {
+        let expected = (1..=20).map(|i| format!("{}\n", i)).collect::<String>();
+        let received = expected.replace("10\n", "ten\n");
+        assert_eq!(
+            test_render(&expected, &received),
+            vec![
+                "@@ -7,7 +7,7 @@",
+                " 7",
+                " 8",
+                " 9",
+                "-10",
+                "+ten",
+                " 11",
+                " 12",
+                " 13"
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_separate_hunks_for_distant_differences() {
+        let expected = (1..=20).map(|i| format!("{}\n", i)).collect::<String>();
+        let received = expected
+            .replace("\n2\n", "\ntwo\n")
+            .replace("19\n", "nineteen\n");
+        let rendered = test_render(&expected, &received);
+        assert_eq!(
+            rendered
+                .iter()
+                .filter(|line| line.starts_with("@@"))
+                .collect::<Vec<_>>(),
+            vec!["@@ -1,5 +1,5 @@", "@@ -16,5 +16,5 @@"]
+        );
+    }
+
+    #[test]
+    fn marks_missing_trailing_newlines() {
+        assert_eq!(
+            test_render("a\nb\n", "a\nb"),
+            vec![
+                "@@ -1,2 +1,2 @@",
+                " a",
+                "-b",
+                "+b",
+                "\\ No newline at end of output"
+            ]
+        );
+    }
+
+    #[test]
+    fn truncates_long_diffs() {
+        let received = (1..=100).map(|i| format!("{}\n", i)).collect::<String>();
+        let rendered = test_render("", &received);
+        assert_eq!(rendered.len(), MAX_RENDERED_LINES + 1);
+        assert_eq!(rendered.last().unwrap(), "... (41 more lines)");
+    }
+
+    #[test]
+    fn colors_removed_and_added_lines() {
+        assert_eq!(
+            render(b"a\n", b"b\n", true),
+            vec![
+                "\x1b[36m@@ -1,1 +1,1 @@\x1b[0m",
+                "\x1b[31m-a\x1b[0m",
+                "\x1b[32m+b\x1b[0m"
+            ]
+        );
+    }
+}
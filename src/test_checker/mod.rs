@@ -1,39 +1,61 @@
 pub mod checker_result;
+mod diff;
 pub mod executable_mock;
 
 use crate::context::Context;
 use crate::test_spec;
-use crate::test_spec::Test;
+use crate::test_spec::{
+    ExecError, ExecutableMatching, ExpectedFileChange, FileChange, FileType, MockedFile,
+    OutputMatcher, Test, Tests,
+};
 use crate::tracer::stdio_redirecting::{Redirect, Redirector};
+use crate::tracer::syscall::Syscall;
 use crate::tracer::{tracee_memory, SyscallMock};
 use crate::utils::short_temp_files::ShortTempFile;
+use crate::utils::strip_ansi;
 use crate::R;
 use checker_result::CheckerResult;
-use libc::{c_ulonglong, user_regs_struct};
+use libc::{c_int, c_ulonglong, user_regs_struct};
 use nix::sys::ptrace;
-use nix::unistd::Pid;
-use std::ffi::OsString;
+use nix::unistd::{getegid, geteuid, Pid};
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fs;
 use std::os::unix::ffi::OsStrExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use trim_margin::MarginTrimmable;
 
+const MAX_SYMLINK_HOPS: usize = 40;
+
 #[derive(Debug)]
 pub struct TestChecker {
     context: Context,
     pub test: Test,
     pub unmocked_commands: Vec<PathBuf>,
+    pub mocked_executables: Vec<String>,
+    pub executable_matching: ExecutableMatching,
     pub result: CheckerResult,
+    env: HashMap<OsString, OsString>,
     temporary_executables: Vec<ShortTempFile>,
+    file_changes: Vec<FileChange>,
+    written_files: HashMap<PathBuf, PathBuf>,
+    missing_executable_lookup: Option<(PathBuf, Vec<PathBuf>)>,
 }
 
 impl TestChecker {
-    pub fn new(context: &Context, test: Test, unmocked_commands: &[PathBuf]) -> TestChecker {
+    pub fn new(context: &Context, test: Test, settings: &Tests) -> TestChecker {
         TestChecker {
             context: context.clone(),
+            env: settings.shown_env(std::env::vars_os, &test),
             test,
-            unmocked_commands: unmocked_commands.to_vec(),
+            unmocked_commands: settings.unmocked_commands.clone(),
+            mocked_executables: settings.mocked_executables.clone(),
+            executable_matching: settings.executable_matching,
             result: CheckerResult::Pass,
             temporary_executables: vec![],
+            file_changes: vec![],
+            written_files: HashMap::new(),
+            missing_executable_lookup: None,
         }
     }
 
@@ -44,14 +66,22 @@ impl TestChecker {
         }
     }
 
-    fn handle_step(&mut self, received: test_spec::Command) -> R<PathBuf> {
+    fn handle_step(
+        &mut self,
+        received: test_spec::Command,
+        search_path: &OsStr,
+    ) -> R<Result<PathBuf, ExecError>> {
         let mock_config = match self.test.steps.pop_front() {
             Some(next_test_step) => {
-                if !next_test_step.command_matcher.matches(&received) {
-                    self.result.register_step_error(
-                        &next_test_step.command_matcher.format(),
-                        &received.format(),
-                    );
+                let command_matcher = next_test_step
+                    .command_matcher
+                    .shorten(search_path, self.executable_matching);
+                if !command_matcher.matches(&received) {
+                    self.result
+                        .register_step_error(&command_matcher.format(), &received.format());
+                }
+                if let Some(exec_error) = next_test_step.exec_error {
+                    return Ok(Err(exec_error));
                 }
                 executable_mock::Config {
                     stdout: next_test_step.stdout,
@@ -69,26 +99,168 @@ impl TestChecker {
         let temp_executable = ShortTempFile::new(&mock_executable_contents)?;
         let path = temp_executable.path();
         self.temporary_executables.push(temp_executable);
-        Ok(path)
+        Ok(Ok(path))
+    }
+
+    fn find_mocked_file(&self, filename: &Path, follow_symlinks: bool) -> Option<&MockedFile> {
+        let mut filename = filename.to_path_buf();
+        for _ in 0..MAX_SYMLINK_HOPS {
+            let mocked_file = self
+                .test
+                .mocked_files
+                .iter()
+                .find(|mocked_file| mocked_file.path == filename)?;
+            match (&mocked_file.file_type, &mocked_file.target, follow_symlinks) {
+                (FileType::Symlink, Some(target), true) => filename = target.clone(),
+                (FileType::Symlink, None, true) => return None,
+                _ => return Some(mocked_file),
+            }
+        }
+        None
+    }
+
+    fn is_faked_executable(&self, filename: &Path) -> bool {
+        let is_next_not_found_exec = match self.next_not_found_command() {
+            Some(command) => {
+                !command.arguments.is_empty() && self.is_lookup_of(&command.executable, filename)
+            }
+            None => false,
+        };
+        is_next_not_found_exec
+            || test_spec::is_mocked_executable(&self.mocked_executables, filename)
+    }
+
+    fn find_mocked_executable(&self, filename: &Path) -> Option<MockedFile> {
+        if self.is_faked_executable(filename) {
+            let mut mocked_file = MockedFile::new(&filename.to_string_lossy());
+            mocked_file.mode = 0o755;
+            Some(mocked_file)
+        } else {
+            None
+        }
+    }
+
+    fn is_lookup_of(&self, executable: &Path, filename: &Path) -> bool {
+        if self.executable_matching == ExecutableMatching::Basename
+            || executable.parent() == Some(Path::new(""))
+        {
+            filename.is_absolute() && filename.file_name() == executable.file_name()
+        } else {
+            executable == filename
+        }
+    }
+
+    fn next_not_found_command(&self) -> Option<&test_spec::Command> {
+        let next_step = self.test.steps.front()?;
+        match (&next_step.command_matcher, &next_step.exec_error) {
+            (test_spec::CommandMatcher::ExactMatch(command), Some(exec_error))
+                if *exec_error == ExecError::not_found() =>
+            {
+                Some(command)
+            }
+            _ => None,
+        }
+    }
+
+    /// A `notFound` step without arguments matches one lookup of its
+    /// executable, i.e. consecutive `stat` and `access` calls for it in
+    /// different directories. Steps with arguments are matched by `execve`
+    /// instead, so that the arguments can be compared.
+    fn is_missing_executable(&mut self, filename: &Path) -> bool {
+        let continues_lookup = match &self.missing_executable_lookup {
+            Some((executable, looked_up)) => {
+                !looked_up.iter().any(|path| path == filename)
+                    && self.is_lookup_of(executable, filename)
+            }
+            None => false,
+        };
+        if continues_lookup {
+            if let Some((_, looked_up)) = &mut self.missing_executable_lookup {
+                looked_up.push(filename.to_path_buf());
+            }
+            return true;
+        }
+        self.missing_executable_lookup = None;
+        let executable = match self.next_not_found_command() {
+            Some(command)
+                if command.arguments.is_empty()
+                    && self.is_lookup_of(&command.executable, filename) =>
+            {
+                command.executable.clone()
+            }
+            _ => return false,
+        };
+        self.test.steps.pop_front();
+        self.missing_executable_lookup = Some((executable, vec![filename.to_path_buf()]));
+        true
+    }
+
+    fn received_executable(&self, executable: PathBuf, search_path: &OsStr) -> PathBuf {
+        if self.is_faked_executable(&executable) && !executable.exists() {
+            test_spec::basename(&executable)
+        } else {
+            test_spec::shorten_executable(&executable, search_path, self.executable_matching)
+        }
+    }
+
+    fn is_unmocked_command(&self, executable: &Path, search_path: &OsStr) -> bool {
+        self.unmocked_commands.iter().any(|unmocked_command| {
+            test_spec::shorten_executable(unmocked_command, search_path, self.executable_matching)
+                == executable
+        })
+    }
+
+    fn effective_uid(&self) -> u32 {
+        self.test
+            .euid
+            .or(self.test.uid)
+            .unwrap_or_else(|| geteuid().as_raw())
     }
 
-    fn check_expected_output_stream(&mut self, redirect: &Redirect, expected: Vec<u8>) -> R<()> {
+    fn effective_gid(&self) -> u32 {
+        self.test.gid.unwrap_or_else(|| getegid().as_raw())
+    }
+
+    fn check_expected_output_stream(
+        &mut self,
+        redirect: &Redirect,
+        expected: &OutputMatcher,
+    ) -> R<()> {
         match redirect.captured()? {
             None => panic!(
                 "scriptkeeper bug: {} expected, but not captured",
                 redirect.stream_type
             ),
             Some(captured) => {
-                if captured != expected {
+                let captured = if self.test.strip_ansi {
+                    strip_ansi(&captured)
+                } else {
+                    captured
+                };
+                let captured = expected.normalize(&captured);
+                let diff = expected
+                    .equals
+                    .as_ref()
+                    .map(|equals| expected.normalize(equals))
+                    .and_then(|equals| {
+                        self.render_diff(
+                            &format!("output to {}", redirect.stream_type),
+                            &equals,
+                            &captured,
+                        )
+                    });
+                if let Some(diff) = diff {
+                    self.result.register_error(diff);
+                } else if let Some(mismatch) = expected.mismatch(&captured) {
                     self.result.register_error(
                         format!(
                             r"
-                                |  expected output to {}: {:?}
+                                |  expected output to {}{}
                                 |  received output to {}: {:?}
                                 |
                             ",
                             redirect.stream_type,
-                            String::from_utf8_lossy(&expected).as_ref(),
+                            mismatch,
                             redirect.stream_type,
                             String::from_utf8_lossy(&captured).as_ref(),
                         )
@@ -100,6 +272,87 @@ impl TestChecker {
         }
         Ok(())
     }
+
+    fn render_diff(&self, subject: &str, expected: &[u8], received: &[u8]) -> Option<String> {
+        if expected == received || !(diff::is_multi_line(expected) || diff::is_multi_line(received))
+        {
+            return None;
+        }
+        let mut result = format!("  unexpected {} (- expected, + received):\n", subject);
+        for line in diff::render(expected, received, self.context.colors_enabled()) {
+            result.push_str(&format!("    {}\n", line));
+        }
+        Some(result)
+    }
+
+    fn check_written_content(&mut self, path: &Path, expected: &[u8]) {
+        let written_content = self
+            .written_files
+            .get(path)
+            .and_then(|resolved| fs::read(resolved).ok());
+        if let Some(diff) = written_content.as_ref().and_then(|content| {
+            self.render_diff(
+                &format!("content of {}", path.to_string_lossy()),
+                expected,
+                content,
+            )
+        }) {
+            self.result.register_error(diff);
+            return;
+        }
+        let (matches, received) = match written_content {
+            Some(content) => (
+                content == expected,
+                format!("{:?}", String::from_utf8_lossy(&content)),
+            ),
+            None => (false, "<file missing>".to_string()),
+        };
+        if !matches {
+            self.result.register_error(
+                format!(
+                    r"
+                        |  expected content of {}: {:?}
+                        |  received content of {}: {}
+                        |
+                    ",
+                    path.to_string_lossy(),
+                    String::from_utf8_lossy(expected).as_ref(),
+                    path.to_string_lossy(),
+                    received,
+                )
+                .trim_margin()
+                .unwrap(),
+            );
+        }
+    }
+
+    fn check_file_changes(&mut self, expected_changes: &[ExpectedFileChange]) {
+        let mut received_changes = self.file_changes.clone().into_iter();
+        for expected in expected_changes {
+            match received_changes.next() {
+                Some(received) => {
+                    if !expected.change.matches(&received) {
+                        self.result.register_step_error(
+                            &expected.change.to_string(),
+                            &received.to_string(),
+                        );
+                    } else if let Some(content) = &expected.content {
+                        self.check_written_content(received.path(), content);
+                    }
+                }
+                None => {
+                    self.result.register_step_error(
+                        &expected.change.to_string(),
+                        "<no more file changes>",
+                    );
+                }
+            }
+        }
+        if let Some(received) = received_changes.next() {
+            self.result
+                .register_step_error("<no more file changes>", &received.to_string());
+        }
+    }
 }
 
 impl SyscallMock for TestChecker {
@@ -111,23 +364,28 @@ impl SyscallMock for TestChecker {
         registers: &user_regs_struct,
         executable: PathBuf,
         arguments: Vec<OsString>,
-    ) -> R<()> {
-        let is_unmocked_command = self
-            .unmocked_commands
-            .iter()
-            .any(|unmocked_command| test_spec::compare_executables(unmocked_command, &executable));
-        if !is_unmocked_command {
-            let mock_executable_path = self.handle_step(test_spec::Command {
-                executable,
-                arguments,
-            })?;
-            tracee_memory::poke_single_word_string(
-                pid,
-                registers.rdi,
-                &mock_executable_path.as_os_str().as_bytes(),
-            )?;
+    ) -> R<Option<c_int>> {
+        self.missing_executable_lookup = None;
+        let search_path =
+            test_spec::search_path(&tracee_memory::peek_environment(pid, registers.rdx)?);
+        let executable = self.received_executable(executable, &search_path);
+        if !self.is_unmocked_command(&executable, &search_path) {
+            match self.handle_step(
+                test_spec::Command {
+                    executable,
+                    arguments,
+                },
+                &search_path,
+            )? {
+                Ok(mock_executable_path) => tracee_memory::poke_single_word_string(
+                    pid,
+                    registers.rdi,
+                    mock_executable_path.as_os_str().as_bytes(),
+                )?,
+                Err(exec_error) => return Ok(Some(exec_error.errno)),
+            }
         }
-        Ok(())
+        Ok(None)
     }
 
     fn handle_getcwd_exit(&self, pid: Pid, registers: &user_regs_struct) -> R<()> {
@@ -143,20 +401,81 @@ impl SyscallMock for TestChecker {
         Ok(())
     }
 
-    fn handle_stat_exit(&self, pid: Pid, registers: &user_regs_struct, filename: PathBuf) -> R<()> {
-        if self.test.mocked_files.contains(&filename) {
-            let statbuf_ptr = registers.rsi;
-            let mock_mode = if filename.as_os_str().as_bytes().ends_with(b"/") {
-                libc::S_IFDIR
+    fn handle_id_exit(&self, pid: Pid, registers: &user_regs_struct, syscall: &Syscall) -> R<()> {
+        let mocked_id = match syscall {
+            Syscall::Getuid => self.test.uid,
+            Syscall::Geteuid => self.test.euid.or(self.test.uid),
+            Syscall::Getgid | Syscall::Getegid => self.test.gid,
+            _ => None,
+        };
+        if let Some(id) = mocked_id {
+            let mut registers = *registers;
+            registers.rax = c_ulonglong::from(id);
+            ptrace::setregs(pid, registers)?;
+        }
+        Ok(())
+    }
+
+    fn handle_getgroups_exit(&self, pid: Pid, registers: &user_regs_struct) -> R<()> {
+        if let Some(groups) = &self.test.groups {
+            let size = registers.rdi as usize;
+            let mut registers = *registers;
+            registers.rax = if size == 0 {
+                groups.len() as c_ulonglong
+            } else if size < groups.len() {
+                -libc::EINVAL as c_ulonglong
             } else {
-                libc::S_IFREG
+                let bytes: Vec<u8> = groups
+                    .iter()
+                    .flat_map(|group| group.to_ne_bytes().to_vec())
+                    .collect();
+                tracee_memory::poke_bytes(pid, registers.rsi, &bytes)?;
+                groups.len() as c_ulonglong
             };
-            #[allow(clippy::forget_copy)]
-            tracee_memory::poke_four_bytes(
-                pid,
-                statbuf_ptr + (offset_of!(libc::stat, st_mode) as u64),
-                mock_mode as u32,
-            )?;
+            ptrace::setregs(pid, registers)?;
+        }
+        Ok(())
+    }
+
+    fn handle_uname_exit(&self, pid: Pid, registers: &user_regs_struct) -> R<()> {
+        if let Some(uname) = &self.test.uname {
+            if registers.rax == 0 {
+                for (offset, bytes) in uname.patches() {
+                    tracee_memory::poke_bytes(pid, registers.rdi + offset as c_ulonglong, &bytes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_stat_exit(
+        &mut self,
+        pid: Pid,
+        registers: &user_regs_struct,
+        statbuf: c_ulonglong,
+        filename: PathBuf,
+        follow_symlinks: bool,
+    ) -> R<()> {
+        if self.is_missing_executable(&filename) {
+            let mut registers = *registers;
+            registers.rax = -libc::ENOENT as c_ulonglong;
+            ptrace::setregs(pid, registers)?;
+            return Ok(());
+        }
+        let mocked_file = match self.find_mocked_file(&filename, follow_symlinks) {
+            Some(mocked_file) => Some(mocked_file.clone()),
+            None if (registers.rax as i64) < 0 => self.find_mocked_executable(&filename),
+            None => None,
+        };
+        if let Some(mocked_file) = mocked_file {
+            let stat = mocked_file.to_stat();
+            let stat_bytes = unsafe {
+                std::slice::from_raw_parts(
+                    &stat as *const libc::stat as *const u8,
+                    std::mem::size_of::<libc::stat>(),
+                )
+            };
+            tracee_memory::poke_bytes(pid, statbuf, stat_bytes)?;
             let mut registers = *registers;
             registers.rax = 0;
             ptrace::setregs(pid, registers)?;
@@ -164,7 +483,57 @@ impl SyscallMock for TestChecker {
         Ok(())
     }
 
+    fn handle_access_exit(
+        &mut self,
+        pid: Pid,
+        registers: &user_regs_struct,
+        filename: PathBuf,
+        mode: c_int,
+    ) -> R<()> {
+        if self.is_missing_executable(&filename) {
+            let mut registers = *registers;
+            registers.rax = -libc::ENOENT as c_ulonglong;
+            ptrace::setregs(pid, registers)?;
+            return Ok(());
+        }
+        let mocked_file = match self.find_mocked_file(&filename, true) {
+            Some(mocked_file) => Some(mocked_file.clone()),
+            None if (registers.rax as i64) < 0 => self.find_mocked_executable(&filename),
+            None => None,
+        };
+        if let Some(mocked_file) = mocked_file {
+            let mut registers = *registers;
+            registers.rax =
+                if mocked_file.allows_access(mode, self.effective_uid(), self.effective_gid()) {
+                    0
+                } else {
+                    -libc::EACCES as c_ulonglong
+                };
+            ptrace::setregs(pid, registers)?;
+        }
+        Ok(())
+    }
+
+    fn handle_file_change(&mut self, pid: Pid, change: FileChange) -> R<()> {
+        if let FileChange::Write(path) = &change {
+            let resolved = fs::read_link(format!("/proc/{}/cwd", pid))?.join(path);
+            self.written_files.insert(path.clone(), resolved);
+        }
+        if self.file_changes.last() != Some(&change) {
+            self.file_changes.push(change);
+        }
+        Ok(())
+    }
+
+    fn handle_blocked_syscall(&mut self, _pid: Pid, message: String) -> R<()> {
+        self.result.register_error(format!("  {}\n", message));
+        Ok(())
+    }
+
     fn handle_end(mut self, exitcode: i32, redirector: &Redirector) -> R<CheckerResult> {
+        if let Some((expected, received)) = redirector.stdin.interaction_failure()? {
+            self.result.register_step_error(&expected, &received);
+        }
         if let Some(expected_step) = self.test.steps.pop_front() {
             self.result.register_step_error(
                 &expected_step.command_matcher.format(),
@@ -178,11 +547,20 @@ impl SyscallMock for TestChecker {
                 &format!("<exitcode {}>", exitcode),
             );
         }
-        if let Some(expected_stdout) = &self.test.stdout {
-            self.check_expected_output_stream(&redirector.stdout, expected_stdout.clone())?;
+        if let Some(expected_stdout) = self.test.stdout.clone() {
+            self.check_expected_output_stream(&redirector.stdout, &expected_stdout)?;
+        }
+        if let Some(expected_stderr) = self.test.stderr.clone() {
+            self.check_expected_output_stream(&redirector.stderr, &expected_stderr)?;
+        }
+        if let Some(expected_file_changes) = self.test.expected_file_changes.clone() {
+            self.check_file_changes(&expected_file_changes);
         }
-        if let Some(expected_stderr) = &self.test.stderr {
-            self.check_expected_output_stream(&redirector.stderr, expected_stderr.clone())?;
+        if !self.env.is_empty() {
+            self.result.append_to_failure(&format!(
+                "  environment:\n{}",
+                test_spec::environment::format_env(&self.env)
+            ));
         }
         Ok(self.result)
     }
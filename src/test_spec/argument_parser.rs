@@ -1,10 +1,12 @@
 use crate::R;
 use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
 
 #[derive(Debug)]
 pub(super) struct Parser {
     pub original: String,
-    pub input: VecDeque<char>,
+    pub input: VecDeque<u8>,
 }
 
 impl Parser {
@@ -15,7 +17,7 @@ impl Parser {
     fn skip_spaces(&mut self) {
         loop {
             match self.input.pop_front() {
-                Some(' ') => {}
+                Some(b' ') => {}
                 Some(char) => {
                     self.input.push_front(char);
                     break;
@@ -25,33 +27,33 @@ impl Parser {
         }
     }
 
-    fn skip_char(&mut self, expected: char, message: &str) -> R<()> {
-        let next = self.input.pop_front();
-        if next != Some(expected) {
-            self.parse_error(message)?;
+    fn parse_hex_byte(&mut self) -> R<u8> {
+        let digits: Vec<u8> = (0..2).filter_map(|_| self.input.pop_front()).collect();
+        match u8::from_str_radix(&String::from_utf8_lossy(&digits), 16) {
+            Ok(byte) if digits.len() == 2 => Ok(byte),
+            _ => self.parse_error("\\x must be followed by two hexadecimal digits"),
         }
-        Ok(())
     }
 
-    fn peek_chars(&mut self, expected: Vec<Option<char>>, message: &str) -> R<()> {
-        let peeked = self.input.get(0);
-        if !expected.contains(&peeked.cloned()) {
-            self.parse_error(message)?;
-        }
-        Ok(())
+    fn parse_escaped_char(&mut self) -> R<u8> {
+        Ok(match self.input.pop_front() {
+            None => self.parse_error("a backslash must be followed by a character")?,
+            Some(b'n') => b'\n',
+            Some(b't') => b'\t',
+            Some(b'r') => b'\r',
+            Some(b'x') => self.parse_hex_byte()?,
+            Some(char) if b"\"' \\$`".contains(&char) => char,
+            Some(char) => self.parse_error(&format!(
+                "unknown escaped character {}",
+                String::from_utf8_lossy(&[char])
+            ))?,
+        })
     }
 
-    fn parse_char(&mut self, excluded: &[char]) -> R<Option<char>> {
+    fn parse_char(&mut self, excluded: &[u8]) -> R<Option<u8>> {
         Ok(match self.input.pop_front() {
             None => None,
-            Some('\\') => Some(match self.input.pop_front() {
-                None => self.parse_error("a backslash must be followed by a character")?,
-                Some('"') => '"',
-                Some('n') => '\n',
-                Some(' ') => ' ',
-                Some('\\') => '\\',
-                Some(char) => self.parse_error(&format!("unknown escaped character {}", char))?,
-            }),
+            Some(b'\\') => Some(self.parse_escaped_char()?),
             Some(char) => {
                 if excluded.contains(&char) {
                     self.input.push_front(char);
@@ -63,50 +65,112 @@ impl Parser {
         })
     }
 
-    fn collect_chars_until(&mut self, excluded: &[char]) -> R<String> {
-        let mut result = "".to_string();
-        while let Some(char) = self.parse_char(excluded)? {
-            result.push(char);
+    fn collect_double_quoted(&mut self) -> R<Vec<u8>> {
+        let mut result = vec![];
+        loop {
+            match self.input.pop_front() {
+                None => self.parse_error("unmatched quotes")?,
+                Some(b'"') => break,
+                Some(b'\\') => match self.input.front() {
+                    Some(&char) if b"\"\\$`".contains(&char) => {
+                        self.input.pop_front();
+                        result.push(char);
+                    }
+                    Some(b'\n') => {
+                        self.input.pop_front();
+                    }
+                    _ => result.push(b'\\'),
+                },
+                Some(char) => result.push(char),
+            }
         }
         Ok(result)
     }
 
-    fn parse_word(&mut self) -> R<Option<String>> {
-        self.skip_spaces();
-        Ok(match self.input.get(0) {
-            None => None,
-            Some('"') => {
-                self.skip_char('"', "shouldn't happen")?;
-                let word = self.collect_chars_until(&['"'])?;
-                self.skip_char('"', "unmatched quotes")?;
-                self.peek_chars(
-                    vec![Some(' '), None],
-                    "closing quotes must be followed by a space",
-                )?;
-                Some(word)
+    fn collect_single_quoted(&mut self) -> R<Vec<u8>> {
+        let mut result = vec![];
+        loop {
+            match self.input.pop_front() {
+                None => self.parse_error("unmatched single quotes")?,
+                Some(b'\'') => break,
+                Some(char) => result.push(char),
             }
-            Some(_) => {
-                let result = self.collect_chars_until(&[' ', '"'])?;
-                self.peek_chars(
-                    vec![Some(' '), None],
-                    "opening quotes must be preceeded by a space",
-                )?;
-                Some(result)
+        }
+        Ok(result)
+    }
+
+    fn collect_ansi_c_quoted(&mut self) -> R<Vec<u8>> {
+        let mut result = vec![];
+        loop {
+            match self.input.pop_front() {
+                None => self.parse_error("unmatched single quotes")?,
+                Some(b'\'') => break,
+                Some(b'\\') => {
+                    let control_char = match self.input.front() {
+                        Some(b'a') => Some(0x07),
+                        Some(b'b') => Some(0x08),
+                        Some(b'e') => Some(0x1b),
+                        Some(b'f') => Some(0x0c),
+                        Some(b'v') => Some(0x0b),
+                        _ => None,
+                    };
+                    match control_char {
+                        Some(char) => {
+                            self.input.pop_front();
+                            result.push(char);
+                        }
+                        None => result.push(self.parse_escaped_char()?),
+                    }
+                }
+                Some(char) => result.push(char),
             }
-        })
+        }
+        Ok(result)
+    }
+
+    fn parse_word(&mut self) -> R<Option<OsString>> {
+        self.skip_spaces();
+        if self.input.is_empty() {
+            return Ok(None);
+        }
+        let mut word = vec![];
+        loop {
+            match (self.input.front(), self.input.get(1)) {
+                (None, _) | (Some(b' '), _) => break,
+                (Some(b'"'), _) => {
+                    self.input.pop_front();
+                    word.extend(self.collect_double_quoted()?);
+                }
+                (Some(b'\''), _) => {
+                    self.input.pop_front();
+                    word.extend(self.collect_single_quoted()?);
+                }
+                (Some(b'$'), Some(b'\'')) => {
+                    self.input.pop_front();
+                    self.input.pop_front();
+                    word.extend(self.collect_ansi_c_quoted()?);
+                }
+                _ => {
+                    if let Some(char) = self.parse_char(b"")? {
+                        word.push(char);
+                    }
+                }
+            }
+        }
+        Ok(Some(OsString::from_vec(word)))
     }
 
-    pub fn parse_arguments(arguments: &str) -> R<Vec<String>> {
+    pub fn parse_arguments(arguments: &[u8]) -> R<Vec<OsString>> {
         Self {
-            original: arguments.to_owned(),
-            input: arguments.chars().collect(),
+            original: String::from_utf8_lossy(arguments).into_owned(),
+            input: arguments.iter().cloned().collect(),
         }
         .collect()
     }
 }
 
 impl Iterator for Parser {
-    type Item = R<String>;
+    type Item = R<OsString>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.parse_word() {
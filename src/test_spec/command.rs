@@ -1,9 +1,11 @@
 use super::argument_parser::Parser;
-use super::executable_path;
+use super::yaml::YamlExt;
 use crate::R;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
 use std::path::PathBuf;
 use std::str;
+use yaml_rust::Yaml;
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Command {
@@ -12,44 +14,74 @@ pub struct Command {
 }
 
 impl Command {
-    fn add_quotes_if_needed(word: String) -> String {
-        if word.chars().any(|char| char == ' ') {
-            format!("\"{}\"", word)
-        } else {
-            word
+    fn quote(word: &OsStr) -> String {
+        match str::from_utf8(word.as_bytes()) {
+            _ if word.is_empty() => "''".to_string(),
+            Ok(word) if !word.chars().any(|char| char.is_ascii_control()) => {
+                Command::quote_printable(word)
+            }
+            _ => Command::quote_ansi_c(word.as_bytes()),
         }
     }
 
-    pub fn compare(&self, other: &Command) -> bool {
-        executable_path::compare_executables(&self.executable, &other.executable)
-            && self.arguments == other.arguments
+    fn quote_printable(word: &str) -> String {
+        let escaped: String = word
+            .chars()
+            .map(|char| match char {
+                '"' | '\\' | '$' | '`' => format!("\\{}", char),
+                _ => char.to_string(),
+            })
+            .collect();
+        if word.chars().any(|char| " '!#&()*;<>?[]{}|~".contains(char)) {
+            format!("\"{}\"", escaped)
+        } else {
+            escaped
+        }
     }
 
-    fn escape(word: String) -> String {
+    fn quote_ansi_c(mut bytes: &[u8]) -> String {
         fn escape_char(char: char) -> String {
             match char {
-                '"' => "\\\"".to_string(),
                 '\n' => "\\n".to_string(),
+                '\t' => "\\t".to_string(),
+                '\r' => "\\r".to_string(),
                 '\\' => "\\\\".to_string(),
+                '\'' => "\\'".to_string(),
+                _ if char.is_ascii_control() => format!("\\x{:02x}", char as u8),
                 _ => char.to_string(),
             }
         }
-        word.chars().map(escape_char).collect::<Vec<_>>().join("")
+        let mut result = "$'".to_string();
+        while !bytes.is_empty() {
+            let (valid, invalid) = match str::from_utf8(bytes) {
+                Ok(valid) => (valid, 0),
+                Err(error) => (
+                    str::from_utf8(&bytes[..error.valid_up_to()]).unwrap(),
+                    error
+                        .error_len()
+                        .unwrap_or_else(|| bytes.len() - error.valid_up_to()),
+                ),
+            };
+            result.extend(valid.chars().map(escape_char));
+            for byte in &bytes[valid.len()..valid.len() + invalid] {
+                result.push_str(&format!("\\x{:02x}", byte));
+            }
+            bytes = &bytes[valid.len() + invalid..];
+        }
+        result.push('\'');
+        result
     }
 
     pub fn format_arguments(arguments: Vec<OsString>) -> String {
         arguments
-            .into_iter()
-            .map(|argument| Command::escape(argument.to_string_lossy().into_owned()))
-            .map(Command::add_quotes_if_needed)
+            .iter()
+            .map(|argument| Command::quote(argument))
             .collect::<Vec<String>>()
             .join(" ")
     }
 
     pub fn format(&self) -> String {
-        let executable = executable_path::canonicalize(&self.executable)
-            .to_string_lossy()
-            .into_owned();
+        let executable = Command::quote(self.executable.as_os_str());
         if self.arguments.is_empty() {
             executable
         } else {
@@ -61,16 +93,46 @@ impl Command {
         }
     }
 
+    pub fn serialize_arguments(arguments: &[OsString]) -> Yaml {
+        Yaml::String(Command::format_arguments(arguments.to_vec()))
+    }
+
+    pub fn parse_words(words: &[Yaml]) -> R<Vec<OsString>> {
+        words
+            .iter()
+            .map(|word| Ok(OsString::from_vec(word.expect_bytes()?)))
+            .collect()
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        Yaml::String(self.format())
+    }
+
+    pub fn from_words(words: Vec<OsString>) -> R<Command> {
+        let mut words = words.into_iter();
+        match words.next() {
+            Some(executable) => Ok(Command {
+                executable: PathBuf::from(executable),
+                arguments: words.collect(),
+            }),
+            None => Err("expected: non-empty list of executable and arguments")?,
+        }
+    }
+
     pub fn new(command: &str) -> R<Command> {
+        Command::from_bytes(command.as_bytes())
+    }
+
+    pub fn from_bytes(command: &[u8]) -> R<Command> {
         let mut words = Parser::parse_arguments(command)?.into_iter();
         match words.next() {
             Some(executable) => Ok(Command {
                 executable: PathBuf::from(executable),
-                arguments: words.map(OsString::from).collect(),
+                arguments: words.collect(),
             }),
             None => Err(format!(
                 "expected: space-separated command and arguments ({:?})",
-                command.to_string()
+                String::from_utf8_lossy(command)
             ))?,
         }
     }
@@ -79,6 +141,7 @@ impl Command {
 #[cfg(test)]
 mod command {
     use super::*;
+    use std::os::unix::ffi::OsStringExt;
 
     mod new {
         use super::Command;
@@ -141,19 +204,62 @@ mod command {
         }
 
         #[test]
-        fn quotes_next_to_letters_1() -> R<()> {
-            assert_error!(
-                Command::new(r#"foo"bar""#),
-                r#"opening quotes must be preceeded by a space ("foo\"bar\"")"#
+        fn concatenates_quotes_next_to_letters() -> R<()> {
+            assert_eq!(
+                Command::new(r#"foo"bar" "baz"qux"#)?,
+                Command {
+                    executable: PathBuf::from("foobar"),
+                    arguments: vec![OsString::from("bazqux")]
+                }
             );
             Ok(())
         }
 
         #[test]
-        fn quotes_next_to_letters_2() -> R<()> {
+        fn single_quotes() -> R<()> {
+            assert_eq!(
+                Command::new(r#"foo 'bar "baz" \n' a'b c'"#)?,
+                Command {
+                    executable: PathBuf::from("foo"),
+                    arguments: vec![r#"bar "baz" \n"#, "ab c"].map(OsString::from)
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn ansi_c_quotes() -> R<()> {
+            assert_eq!(
+                Command::new(r"foo $'bar\tbaz\'s' $'\x41\e' $bar")?,
+                Command {
+                    executable: PathBuf::from("foo"),
+                    arguments: vec!["bar\tbaz's", "A\x1b", "$bar"].map(OsString::from)
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn empty_arguments() -> R<()> {
+            assert_eq!(
+                Command::new(r#"foo '' "" bar"#)?,
+                Command {
+                    executable: PathBuf::from("foo"),
+                    arguments: vec!["", "", "bar"].map(OsString::from)
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn nonmatching_single_quotes() -> R<()> {
             assert_error!(
-                Command::new(r#""foo"bar"#),
-                r#"closing quotes must be followed by a space ("\"foo\"bar")"#
+                Command::new("foo 'bar"),
+                r#"unmatched single quotes ("foo 'bar")"#
+            );
+            assert_error!(
+                Command::new(r"foo $'bar\'"),
+                r#"unmatched single quotes ("foo $'bar\\'")"#
             );
             Ok(())
         }
@@ -209,7 +315,7 @@ mod command {
             #[test]
             fn newlines() -> R<()> {
                 assert_eq!(
-                    Command::new(r#"foo "bar\nbaz""#)?,
+                    Command::new(r"foo bar\nbaz")?,
                     Command {
                         executable: PathBuf::from("foo"),
                         arguments: vec![OsString::from("bar\nbaz")]
@@ -218,6 +324,42 @@ mod command {
                 Ok(())
             }
 
+            #[test]
+            fn keeps_other_backslashes_inside_double_quotes() -> R<()> {
+                assert_eq!(
+                    Command::new(r#"foo "bar\nbaz" "\t\x41\'""#)?,
+                    Command {
+                        executable: PathBuf::from("foo"),
+                        arguments: vec![OsString::from(r"bar\nbaz"), OsString::from(r"\t\x41\'")]
+                    }
+                );
+                Ok(())
+            }
+
+            #[test]
+            fn escapes_quotes_backslashes_dollars_and_backticks_inside_double_quotes() -> R<()> {
+                assert_eq!(
+                    Command::new(r#"foo "\"\\\$\`""#)?,
+                    Command {
+                        executable: PathBuf::from("foo"),
+                        arguments: vec![OsString::from(r#""\$`"#)]
+                    }
+                );
+                Ok(())
+            }
+
+            #[test]
+            fn removes_escaped_newlines_inside_double_quotes() -> R<()> {
+                assert_eq!(
+                    Command::new("foo \"bar\\\nbaz\"")?,
+                    Command {
+                        executable: PathBuf::from("foo"),
+                        arguments: vec![OsString::from("barbaz")]
+                    }
+                );
+                Ok(())
+            }
+
             #[test]
             fn escaping_spaces() -> R<()> {
                 assert_eq!(
@@ -230,6 +372,29 @@ mod command {
                 Ok(())
             }
 
+            #[test]
+            fn hexadecimal_bytes() -> R<()> {
+                assert_eq!(
+                    Command::new(r"foo caf\xe9 \x41")?,
+                    Command {
+                        executable: PathBuf::from("foo"),
+                        arguments: vec![
+                            OsString::from_vec(b"caf\xe9".to_vec()),
+                            OsString::from("A")
+                        ]
+                    }
+                );
+                Ok(())
+            }
+
+            #[test]
+            fn invalid_hexadecimal_bytes() {
+                assert_error!(
+                    Command::new(r"foo \xz1"),
+                    r#"\x must be followed by two hexadecimal digits ("foo \\xz1")"#
+                );
+            }
+
             #[test]
             fn escaping_backslashes() -> R<()> {
                 assert_eq!(
@@ -285,8 +450,60 @@ mod command {
             r#"foo "bar baz""#
         );
 
-        roundtrip!(escaped_newlines, r"foo bar\nbaz");
+        normalizing_roundtrip!(escaped_newlines, r"foo bar\nbaz", r"foo $'bar\nbaz'");
 
         roundtrip!(backslash, r"foo bar\\baz");
+
+        normalizing_roundtrip!(
+            invalid_utf8,
+            r"foo caf\xe9 \xff\xfe",
+            r"foo $'caf\xe9' $'\xff\xfe'"
+        );
+
+        roundtrip!(empty_arguments, "foo '' bar");
+
+        roundtrip!(tabs, r"foo $'bar\tbaz'");
+
+        roundtrip!(dollar_signs, r"foo \$bar");
+
+        roundtrip!(single_quotes, r#"foo "it's""#);
+
+        roundtrip!(shell_metacharacters, r#"foo "a;b" "*" "~" "a|b""#);
+
+        #[test]
+        fn escapes_invalid_utf8_losslessly() {
+            let command = Command {
+                executable: PathBuf::from(OsString::from_vec(b"b\xe4r".to_vec())),
+                arguments: vec![OsString::from_vec(b"\xc3\xa4 \xc3".to_vec())],
+            };
+            assert_eq!(command.format(), r"$'b\xe4r' $'ä \xc3'");
+        }
+
+        #[test]
+        fn parses_back_to_the_identical_command() -> R<()> {
+            let words: Vec<&[u8]> = vec![
+                b"",
+                b" ",
+                b"'",
+                b"\"",
+                b"\\",
+                b"$'a'",
+                b"${HOME}",
+                b"`ls`",
+                b"a\tb",
+                b"a\nb'c",
+                b"\x07\x7f",
+                b"\xff'",
+                b"\xc3\xa4 $x",
+            ];
+            for word in words {
+                let command = Command {
+                    executable: PathBuf::from("foo"),
+                    arguments: vec![OsString::from_vec(word.to_vec())],
+                };
+                assert_eq!(Command::new(&command.format())?, command);
+            }
+            Ok(())
+        }
     }
 }
@@ -1,7 +1,10 @@
 use super::command::Command;
+use super::executable_path::{shorten_executable, ExecutableMatching};
 use crate::R;
 use regex::Regex;
+use std::ffi::OsStr;
 use std::str;
+use yaml_rust::Yaml;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum CommandMatcher {
@@ -12,11 +15,28 @@ pub enum CommandMatcher {
 impl CommandMatcher {
     pub fn matches(&self, other: &Command) -> bool {
         match self {
-            CommandMatcher::ExactMatch(command) => command.compare(other),
+            CommandMatcher::ExactMatch(command) => command == other,
             CommandMatcher::RegexMatch(regex) => regex.is_match(&other.format()),
         }
     }
 
+    pub fn shorten(self, search_path: &OsStr, matching: ExecutableMatching) -> CommandMatcher {
+        match self {
+            CommandMatcher::ExactMatch(command) => CommandMatcher::ExactMatch(Command {
+                executable: shorten_executable(&command.executable, search_path, matching),
+                arguments: command.arguments,
+            }),
+            regex_match @ CommandMatcher::RegexMatch(_) => regex_match,
+        }
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        match self {
+            CommandMatcher::ExactMatch(command) => command.serialize(),
+            CommandMatcher::RegexMatch(_) => Yaml::String(self.format()),
+        }
+    }
+
     pub fn format(&self) -> String {
         match self {
             CommandMatcher::ExactMatch(command) => command.format(),
@@ -154,4 +174,46 @@ mod command_matcher {
             }
         }
     }
+
+    mod shorten {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn shortens_executables_against_the_given_search_path() -> R<()> {
+            assert_eq!(
+                CommandMatcher::ExactMatch(Command::new("/bin/sh -c true")?)
+                    .shorten(OsStr::new("/bin"), ExecutableMatching::Path),
+                CommandMatcher::ExactMatch(Command::new("sh -c true")?)
+            );
+            assert_eq!(
+                CommandMatcher::ExactMatch(Command::new("/bin/sh -c true")?)
+                    .shorten(OsStr::new("/nonexistent"), ExecutableMatching::Path),
+                CommandMatcher::ExactMatch(Command::new("/bin/sh -c true")?)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn shortens_executables_to_their_basenames_when_matching_by_basename() -> R<()> {
+            assert_eq!(
+                CommandMatcher::ExactMatch(Command::new("/opt/bin/git status")?)
+                    .shorten(OsStr::new(""), ExecutableMatching::Basename),
+                CommandMatcher::ExactMatch(Command::new("git status")?)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn leaves_regexes_unchanged() -> R<()> {
+            let regex = CommandMatcher::RegexMatch(AnchoredRegex::new("/opt/bin/git .*")?);
+            assert_eq!(
+                regex
+                    .clone()
+                    .shorten(OsStr::new(""), ExecutableMatching::Basename),
+                regex
+            );
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,274 @@
+use crate::R;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Destination {
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl Destination {
+    fn parse_port(string: &str, port: &str) -> R<u16> {
+        Ok(port
+            .parse()
+            .map_err(|_| format!("invalid port in destination: {:?}", string))?)
+    }
+
+    pub fn parse(string: &str) -> R<Destination> {
+        let (host, port) = if string.starts_with('[') {
+            let end = string
+                .find(']')
+                .ok_or_else(|| format!("missing ']' in destination: {:?}", string))?;
+            let port = match &string[end + 1..] {
+                "" => None,
+                rest if rest.starts_with(':') => Some(Destination::parse_port(string, &rest[1..])?),
+                _ => Err(format!("invalid destination: {:?}", string))?,
+            };
+            (&string[1..end], port)
+        } else {
+            match string.rfind(':') {
+                Some(index) if string.matches(':').count() == 1 => (
+                    &string[..index],
+                    Some(Destination::parse_port(string, &string[index + 1..])?),
+                ),
+                _ => (string, None),
+            }
+        };
+        if host.is_empty() {
+            Err(format!("missing host in destination: {:?}", string))?;
+        }
+        Ok(Destination {
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    fn addresses(&self) -> Vec<IpAddr> {
+        match self.host.parse::<IpAddr>() {
+            Ok(address) => vec![address],
+            Err(_) => match (self.host.as_str(), 0).to_socket_addrs() {
+                Ok(addresses) => addresses.map(|address| address.ip()).collect(),
+                Err(_) => vec![],
+            },
+        }
+    }
+
+    pub fn resolve(&self) -> ResolvedDestination {
+        ResolvedDestination {
+            addresses: self.addresses(),
+            port: self.port,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ResolvedDestination {
+    addresses: Vec<IpAddr>,
+    port: Option<u16>,
+}
+
+impl ResolvedDestination {
+    pub fn matches(&self, address: &SocketAddr) -> bool {
+        let port_matches = match self.port {
+            Some(port) => port == address.port(),
+            None => true,
+        };
+        port_matches && self.addresses.contains(&address.ip())
+    }
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        match self.port {
+            Some(port) => write!(f, "{}:{}", host, port),
+            None => write!(f, "{}", host),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct NetworkMapping {
+    pub from: Destination,
+    pub to: Destination,
+}
+
+impl NetworkMapping {
+    pub fn parse(string: &str) -> R<NetworkMapping> {
+        let mut parts = string.split("->");
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(from), Some(to), None) => {
+                let to = Destination::parse(to.trim())?;
+                if to.host.parse::<IpAddr>().is_err() {
+                    Err(format!(
+                        "network mapping targets have to be ip addresses, got: {:?}",
+                        to.host
+                    ))?;
+                }
+                Ok(NetworkMapping {
+                    from: Destination::parse(from.trim())?,
+                    to,
+                })
+            }
+            _ => Err(format!(
+                "expected network mapping of the form 'host:port -> ip:port', got: {:?}",
+                string
+            ))?,
+        }
+    }
+
+    pub fn target_ip(&self) -> R<IpAddr> {
+        Ok(self.to.host.parse().map_err(|_| {
+            format!(
+                "network mapping targets have to be ip addresses, got: {:?}",
+                self.to.host
+            )
+        })?)
+    }
+}
+
+impl fmt::Display for NetworkMapping {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} -> {}", self.from, self.to)
+    }
+}
+
+#[cfg(test)]
+mod destination {
+    use super::*;
+    use test_utils::assert_error;
+
+    mod parse {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn parses_hosts_with_ports() -> R<()> {
+            assert_eq!(
+                Destination::parse("127.0.0.1:8080")?,
+                Destination {
+                    host: "127.0.0.1".to_string(),
+                    port: Some(8080),
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn parses_hosts_without_ports() -> R<()> {
+            assert_eq!(
+                Destination::parse("localhost")?,
+                Destination {
+                    host: "localhost".to_string(),
+                    port: None,
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn parses_ipv6_addresses() -> R<()> {
+            assert_eq!(
+                Destination::parse("[::1]:8080")?,
+                Destination {
+                    host: "::1".to_string(),
+                    port: Some(8080),
+                }
+            );
+            assert_eq!(Destination::parse("::1")?.port, None);
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_invalid_ports() {
+            assert_error!(
+                Destination::parse("localhost:http"),
+                "invalid port in destination: \"localhost:http\""
+            );
+        }
+
+        #[test]
+        fn roundtrips_through_display() -> R<()> {
+            for string in &["127.0.0.1:8080", "localhost", "[::1]:80"] {
+                assert_eq!(Destination::parse(string)?.to_string(), *string);
+            }
+            Ok(())
+        }
+    }
+
+    mod matches {
+        use super::*;
+
+        #[test]
+        fn matches_ip_addresses_and_ports() -> R<()> {
+            let destination = Destination::parse("127.0.0.1:8080")?.resolve();
+            assert!(destination.matches(&"127.0.0.1:8080".parse()?));
+            assert!(!destination.matches(&"127.0.0.1:8081".parse()?));
+            assert!(!destination.matches(&"127.0.0.2:8080".parse()?));
+            Ok(())
+        }
+
+        #[test]
+        fn matches_all_ports_when_none_is_given() -> R<()> {
+            assert!(Destination::parse("127.0.0.1")?
+                .resolve()
+                .matches(&"127.0.0.1:1234".parse()?));
+            Ok(())
+        }
+
+        #[test]
+        fn resolves_host_names() -> R<()> {
+            assert!(Destination::parse("localhost:80")?
+                .resolve()
+                .matches(&"127.0.0.1:80".parse()?));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod network_mapping {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+
+    #[test]
+    fn parses_mappings_from_host_names_to_ip_addresses() -> R<()> {
+        assert_eq!(
+            NetworkMapping::parse("api.example.com:443 -> 127.0.0.1:8080")?,
+            NetworkMapping {
+                from: Destination {
+                    host: "api.example.com".to_string(),
+                    port: Some(443),
+                },
+                to: Destination {
+                    host: "127.0.0.1".to_string(),
+                    port: Some(8080),
+                },
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_host_names_as_targets() {
+        assert_error!(
+            NetworkMapping::parse("api.example.com -> localhost"),
+            "network mapping targets have to be ip addresses, got: \"localhost\""
+        );
+    }
+
+    #[test]
+    fn disallows_strings_without_arrows() {
+        assert_error!(
+            NetworkMapping::parse("api.example.com"),
+            "expected network mapping of the form 'host:port -> ip:port', \
+             got: \"api.example.com\""
+        );
+    }
+}
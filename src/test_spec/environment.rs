@@ -0,0 +1,227 @@
+use super::yaml::*;
+use crate::R;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use yaml_rust::Yaml;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InheritEnv {
+    All,
+    Variables(Vec<String>),
+}
+
+impl InheritEnv {
+    pub fn parse(yaml: &Yaml) -> R<InheritEnv> {
+        Ok(match yaml {
+            Yaml::Boolean(true) => InheritEnv::All,
+            Yaml::Boolean(false) => InheritEnv::Variables(vec![]),
+            Yaml::Array(_) => InheritEnv::Variables(parse_variable_names(yaml)?),
+            _ => Err(format!(
+                "expected: boolean or array of strings, got: {:?}",
+                yaml
+            ))?,
+        })
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        match self {
+            InheritEnv::All => Yaml::Boolean(true),
+            InheritEnv::Variables(variables) => serialize_variable_names(variables),
+        }
+    }
+
+    fn inherits(&self, variable: &OsStr) -> bool {
+        match self {
+            InheritEnv::All => true,
+            InheritEnv::Variables(variables) => {
+                variables.iter().any(|name| OsStr::new(name) == variable)
+            }
+        }
+    }
+}
+
+pub fn parse_variable_names(yaml: &Yaml) -> R<Vec<String>> {
+    yaml.expect_array()?
+        .iter()
+        .map(|name| Ok(name.expect_str()?.to_string()))
+        .collect()
+}
+
+pub fn serialize_variable_names(names: &[String]) -> Yaml {
+    Yaml::Array(names.iter().map(|name| Yaml::from_str(name)).collect())
+}
+
+/// `parent_env` is only called if variables are inherited, so that
+/// scriptkeeper's own environment isn't read otherwise.
+pub fn effective_env<I: Iterator<Item = (OsString, OsString)>>(
+    parent_env: impl FnOnce() -> I,
+    inherit_env: Option<&InheritEnv>,
+    env: &HashMap<String, String>,
+    unset_env: &[String],
+) -> HashMap<OsString, OsString> {
+    let mut result = HashMap::new();
+    if let Some(inherit_env) = inherit_env {
+        for (key, value) in parent_env() {
+            if inherit_env.inherits(&key) {
+                result.insert(key, value);
+            }
+        }
+    }
+    for (key, value) in env {
+        result.insert(OsString::from(key), OsString::from(value));
+    }
+    for key in unset_env {
+        result.remove(OsStr::new(key));
+    }
+    result
+}
+
+pub fn shown_env(
+    effective_env: HashMap<OsString, OsString>,
+    inherit_env: Option<&InheritEnv>,
+    env: &HashMap<String, String>,
+) -> HashMap<OsString, OsString> {
+    effective_env
+        .into_iter()
+        .filter(|(key, _)| {
+            env.keys().any(|name| OsStr::new(name) == key)
+                || match inherit_env {
+                    Some(inherit_env @ InheritEnv::Variables(_)) => inherit_env.inherits(key),
+                    _ => false,
+                }
+        })
+        .collect()
+}
+
+pub fn format_env(env: &HashMap<OsString, OsString>) -> String {
+    let mut variables: Vec<(&OsString, &OsString)> = env.iter().collect();
+    variables.sort();
+    variables
+        .into_iter()
+        .map(|(key, value)| {
+            format!(
+                "    {}={}\n",
+                key.to_string_lossy(),
+                value.to_string_lossy()
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod effective_env {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::os::unix::ffi::OsStringExt;
+
+    fn parent_env() -> impl Iterator<Item = (OsString, OsString)> {
+        vec![
+            (OsString::from("HOME"), OsString::from("/home/user")),
+            (OsString::from("LANG"), OsString::from("C.UTF-8")),
+        ]
+        .into_iter()
+    }
+
+    fn env(variables: &[(&str, &str)]) -> HashMap<String, String> {
+        variables
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn os_env(variables: &[(&str, &str)]) -> HashMap<OsString, OsString> {
+        variables
+            .iter()
+            .map(|(key, value)| (OsString::from(key), OsString::from(value)))
+            .collect()
+    }
+
+    #[test]
+    fn does_not_inherit_anything_by_default() {
+        assert_eq!(
+            effective_env(parent_env, None, &env(&[("FOO", "bar")]), &[]),
+            os_env(&[("FOO", "bar")])
+        );
+    }
+
+    #[test]
+    fn does_not_read_the_parent_environment_if_nothing_is_inherited() {
+        effective_env(
+            || -> std::vec::IntoIter<(OsString, OsString)> {
+                panic!("parent environment was read")
+            },
+            None,
+            &HashMap::new(),
+            &[],
+        );
+    }
+
+    #[test]
+    fn inherits_variables_that_are_not_valid_utf8() {
+        let variable = (OsString::from("FOO"), OsString::from_vec(b"\xff".to_vec()));
+        assert_eq!(
+            effective_env(
+                || vec![variable.clone()].into_iter(),
+                Some(&InheritEnv::All),
+                &HashMap::new(),
+                &[]
+            ),
+            vec![variable.clone()].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn inherits_all_variables() {
+        assert_eq!(
+            effective_env(parent_env, Some(&InheritEnv::All), &HashMap::new(), &[]),
+            os_env(&[("HOME", "/home/user"), ("LANG", "C.UTF-8")])
+        );
+    }
+
+    #[test]
+    fn inherits_selected_variables() {
+        assert_eq!(
+            effective_env(
+                parent_env,
+                Some(&InheritEnv::Variables(vec!["HOME".to_string()])),
+                &HashMap::new(),
+                &[]
+            ),
+            os_env(&[("HOME", "/home/user")])
+        );
+    }
+
+    #[test]
+    fn overrides_inherited_variables_with_the_test_environment() {
+        assert_eq!(
+            effective_env(
+                parent_env,
+                Some(&InheritEnv::All),
+                &env(&[("HOME", "/tmp")]),
+                &[]
+            ),
+            os_env(&[("HOME", "/tmp"), ("LANG", "C.UTF-8")])
+        );
+    }
+
+    #[test]
+    fn removes_unset_variables() {
+        assert_eq!(
+            effective_env(
+                parent_env,
+                Some(&InheritEnv::All),
+                &HashMap::new(),
+                &["LANG".to_string()]
+            ),
+            os_env(&[("HOME", "/home/user")])
+        );
+    }
+
+    #[test]
+    fn formats_variables_sorted_by_name() {
+        assert_eq!(
+            format_env(&os_env(&[("B", "2"), ("A", "1")])),
+            "    A=1\n    B=2\n"
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::R;
+use libc::c_int;
+
+const ERRNOS: &[(&str, c_int)] = &[
+    ("E2BIG", libc::E2BIG),
+    ("EACCES", libc::EACCES),
+    ("EINVAL", libc::EINVAL),
+    ("EIO", libc::EIO),
+    ("EISDIR", libc::EISDIR),
+    ("ELIBBAD", libc::ELIBBAD),
+    ("ELOOP", libc::ELOOP),
+    ("EMFILE", libc::EMFILE),
+    ("ENAMETOOLONG", libc::ENAMETOOLONG),
+    ("ENFILE", libc::ENFILE),
+    ("ENOENT", libc::ENOENT),
+    ("ENOEXEC", libc::ENOEXEC),
+    ("ENOMEM", libc::ENOMEM),
+    ("ENOTDIR", libc::ENOTDIR),
+    ("EPERM", libc::EPERM),
+    ("ETXTBSY", libc::ETXTBSY),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ExecError {
+    pub errno: c_int,
+}
+
+impl ExecError {
+    pub fn not_found() -> ExecError {
+        ExecError {
+            errno: libc::ENOENT,
+        }
+    }
+
+    pub fn parse(name: &str) -> R<ExecError> {
+        match ERRNOS.iter().find(|(errno_name, _)| *errno_name == name) {
+            Some((_, errno)) => Ok(ExecError { errno: *errno }),
+            None => Err(format!(
+                "unknown execve error: {}, expected one of: {}",
+                name,
+                ERRNOS
+                    .iter()
+                    .map(|(errno_name, _)| *errno_name)
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            ))?,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        ERRNOS
+            .iter()
+            .find(|(_, errno)| *errno == self.errno)
+            .map(|(name, _)| *name)
+            .unwrap_or("<unknown errno>")
+    }
+}
+
+#[cfg(test)]
+mod exec_error {
+    use super::*;
+    use test_utils::assert_error;
+
+    #[test]
+    fn parses_errno_names() -> R<()> {
+        assert_eq!(ExecError::parse("EACCES")?.errno, libc::EACCES);
+        assert_eq!(ExecError::parse("ENOENT")?, ExecError::not_found());
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrips_errno_names() -> R<()> {
+        assert_eq!(ExecError::parse("ENOEXEC")?.name(), "ENOEXEC");
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_unknown_errno_names() {
+        assert_error!(
+            ExecError::parse("EFOO"),
+            "unknown execve error: EFOO, expected one of: E2BIG, EACCES, EINVAL, EIO, EISDIR, \
+             ELIBBAD, ELOOP, EMFILE, ENAMETOOLONG, ENFILE, ENOENT, ENOEXEC, ENOMEM, ENOTDIR, \
+             EPERM, ETXTBSY"
+        );
+    }
+}
@@ -1,101 +1,182 @@
-use quale::which;
+use crate::R;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use yaml_rust::Yaml;
 
-pub fn compare_executables(a: &Path, b: &Path) -> bool {
-    canonicalize(a) == canonicalize(b)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutableMatching {
+    Path,
+    Basename,
+}
+
+impl ExecutableMatching {
+    pub fn parse(yaml: &Yaml) -> R<ExecutableMatching> {
+        Ok(match yaml.as_str() {
+            Some("path") => ExecutableMatching::Path,
+            Some("basename") => ExecutableMatching::Basename,
+            _ => Err(format!(
+                "expected one of 'path', 'basename', got: {:?}",
+                yaml
+            ))?,
+        })
+    }
+
+    pub fn format(self) -> &'static str {
+        match self {
+            ExecutableMatching::Path => "path",
+            ExecutableMatching::Basename => "basename",
+        }
+    }
+}
+
+pub fn is_mocked_executable(mocked_executables: &[String], path: &Path) -> bool {
+    match path.file_name() {
+        Some(file_name) if path.is_absolute() => mocked_executables
+            .iter()
+            .any(|mocked_executable| OsStr::new(mocked_executable) == file_name),
+        _ => false,
+    }
 }
 
 #[cfg(test)]
-mod compare_executables {
+mod is_mocked_executable {
     use super::*;
-    use crate::R;
 
     #[test]
-    fn returns_true_if_executables_are_identical() -> R<()> {
-        let executable = Path::new("./bin/myexec");
-        assert!(compare_executables(executable, executable));
-        Ok(())
+    fn matches_absolute_paths_by_file_name() {
+        let mocked_executables = vec!["kubectl".to_string()];
+        assert!(is_mocked_executable(
+            &mocked_executables,
+            Path::new("/usr/local/bin/kubectl")
+        ));
+        assert!(!is_mocked_executable(
+            &mocked_executables,
+            Path::new("/usr/local/bin/helm")
+        ));
     }
 
     #[test]
-    fn returns_false_if_executables_are_distinct() -> R<()> {
-        let a = Path::new("./bin/myexec");
-        let b = Path::new("./bin/myotherexec");
-        assert!(!compare_executables(a, b));
-        Ok(())
+    fn does_not_match_relative_paths() {
+        let mocked_executables = vec!["kubectl".to_string()];
+        assert!(!is_mocked_executable(
+            &mocked_executables,
+            Path::new("kubectl")
+        ));
     }
+}
 
-    #[test]
-    fn returns_true_if_executables_match_after_lookup_in_path() -> R<()> {
-        let path = which("cp").unwrap();
-        let cp_long = path;
-        let cp_short = Path::new("cp");
-        assert!(compare_executables(&cp_long, cp_short));
-        Ok(())
+/// The search path that bash uses when `PATH` is unset.
+const DEFAULT_SEARCH_PATH: &str = "/usr/local/bin:/usr/local/sbin:/usr/bin:/usr/sbin:/bin:/sbin:.";
+
+pub fn search_path(environment: &[Vec<u8>]) -> OsString {
+    environment
+        .iter()
+        .find(|variable| variable.starts_with(b"PATH="))
+        .map(|variable| OsString::from_vec(variable[5..].to_vec()))
+        .unwrap_or_else(|| OsString::from(DEFAULT_SEARCH_PATH))
+}
+
+fn lookup(file_name: &OsStr, search_path: &OsStr) -> Option<PathBuf> {
+    std::env::split_paths(search_path)
+        .map(|directory| directory.join(file_name))
+        .find(|candidate| match candidate.metadata() {
+            Ok(metadata) => metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+            Err(_) => false,
+        })
+}
+
+pub fn basename(executable: &Path) -> PathBuf {
+    match executable.file_name() {
+        Some(file_name) => PathBuf::from(file_name),
+        None => executable.into(),
     }
 }
 
-pub fn canonicalize(executable: &Path) -> PathBuf {
+pub fn shorten_executable(
+    executable: &Path,
+    search_path: &OsStr,
+    matching: ExecutableMatching,
+) -> PathBuf {
     let file_name = match executable.file_name() {
         None => return executable.into(),
-        Some(f) => f,
+        Some(file_name) => file_name,
     };
-    match which(file_name) {
-        Some(resolved) => {
-            if resolved == executable {
-                PathBuf::from(file_name)
-            } else {
-                executable.into()
-            }
-        }
-        None => executable.into(),
+    let is_found_in_path = lookup(file_name, search_path) == Some(executable.to_path_buf());
+    if matching == ExecutableMatching::Basename || is_found_in_path {
+        PathBuf::from(file_name)
+    } else {
+        executable.into()
     }
 }
 
 #[cfg(test)]
-mod canonicalize {
+mod shorten_executable {
     use super::*;
-    use crate::R;
     use pretty_assertions::assert_eq;
+    use quale::which;
+    use test_utils::TempFile;
+
+    fn executable_in_temp_dir(tempfile: &TempFile) -> R<(PathBuf, OsString)> {
+        let directory = tempfile.path().parent().unwrap().join("bin");
+        std::fs::create_dir_all(&directory)?;
+        let executable = directory.join("git");
+        std::fs::write(&executable, "")?;
+        std::fs::set_permissions(&executable, std::fs::Permissions::from_mode(0o755))?;
+        Ok((executable, directory.into_os_string()))
+    }
 
     #[test]
-    fn shortens_absolute_executable_paths_if_found_in_path() -> R<()> {
-        let executable = "cp";
-        let resolved = which(executable).unwrap();
-        let file_name = canonicalize(&resolved);
-        assert_eq!(file_name, PathBuf::from("cp"));
+    fn shortens_executables_found_in_the_given_search_path() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let (executable, search_path) = executable_in_temp_dir(&tempfile)?;
+        assert_eq!(
+            shorten_executable(&executable, &search_path, ExecutableMatching::Path),
+            PathBuf::from("git")
+        );
         Ok(())
     }
 
     #[test]
-    fn does_not_shorten_executable_that_is_not_in_path() -> R<()> {
-        let executable = Path::new("/foo/doesnotexist");
-        let file_name = canonicalize(executable);
-        assert_eq!(file_name, PathBuf::from("/foo/doesnotexist"));
+    fn does_not_shorten_executables_that_are_shadowed_in_the_search_path() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let (executable, search_path) = executable_in_temp_dir(&tempfile)?;
+        let mut shadowing_path = OsString::from(which("git").unwrap().parent().unwrap());
+        shadowing_path.push(":");
+        shadowing_path.push(search_path);
+        assert_eq!(
+            shorten_executable(&executable, &shadowing_path, ExecutableMatching::Path),
+            executable
+        );
         Ok(())
     }
 
     #[test]
-    fn does_not_shorten_executable_that_is_not_in_path_but_has_same_name_as_one_that_is() -> R<()> {
-        let executable = Path::new("/not/in/path/ls");
-        let file_name = canonicalize(executable);
-        assert_eq!(file_name, PathBuf::from("/not/in/path/ls"));
-        Ok(())
+    fn shortens_all_executables_to_their_basenames_when_matching_by_basename() {
+        assert_eq!(
+            shorten_executable(
+                Path::new("/opt/tools/bin/git"),
+                OsStr::new(""),
+                ExecutableMatching::Basename
+            ),
+            PathBuf::from("git")
+        );
     }
 
     #[test]
-    fn does_not_shorten_relative_path() -> R<()> {
-        let executable = Path::new("./foo");
-        let file_name = canonicalize(executable);
-        assert_eq!(file_name, PathBuf::from("./foo"));
-        Ok(())
+    fn takes_the_search_path_from_the_environment() {
+        assert_eq!(
+            search_path(&[b"FOO=bar".to_vec(), b"PATH=/foo:/bar".to_vec()]),
+            OsString::from("/foo:/bar")
+        );
     }
 
     #[test]
-    fn does_not_modify_short_forms_if_found_in_path() -> R<()> {
-        let executable = Path::new("ls");
-        let file_name = canonicalize(executable);
-        assert_eq!(file_name, PathBuf::from("ls"));
-        Ok(())
+    fn falls_back_to_the_default_search_path_of_bash_when_path_is_unset() {
+        assert_eq!(
+            search_path(&[b"FOO=bar".to_vec()]),
+            OsString::from("/usr/local/bin:/usr/local/sbin:/usr/bin:/usr/sbin:/bin:/sbin:.")
+        );
     }
 }
@@ -0,0 +1,242 @@
+use super::yaml::*;
+use super::MockedFile;
+use crate::R;
+use libc::mode_t;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use yaml_rust::{yaml::Hash, Yaml};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum FileChange {
+    Write(PathBuf),
+    Delete(PathBuf),
+    Rename(PathBuf, PathBuf),
+    Mkdir(PathBuf),
+    Rmdir(PathBuf),
+    Chmod(PathBuf, Option<mode_t>),
+    Truncate(PathBuf),
+}
+
+impl FileChange {
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            FileChange::Write(path)
+            | FileChange::Delete(path)
+            | FileChange::Rename(path, _)
+            | FileChange::Mkdir(path)
+            | FileChange::Rmdir(path)
+            | FileChange::Chmod(path, _)
+            | FileChange::Truncate(path) => path,
+        }
+    }
+
+    pub fn matches(&self, received: &FileChange) -> bool {
+        match (self, received) {
+            (FileChange::Chmod(expected_path, None), FileChange::Chmod(received_path, _)) => {
+                expected_path == received_path
+            }
+            (expected, received) => expected == received,
+        }
+    }
+}
+
+impl fmt::Display for FileChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileChange::Write(path) => write!(f, "write {}", path.to_string_lossy()),
+            FileChange::Delete(path) => write!(f, "delete {}", path.to_string_lossy()),
+            FileChange::Rename(from, to) => write!(
+                f,
+                "rename {} -> {}",
+                from.to_string_lossy(),
+                to.to_string_lossy()
+            ),
+            FileChange::Mkdir(path) => write!(f, "mkdir {}", path.to_string_lossy()),
+            FileChange::Rmdir(path) => write!(f, "rmdir {}", path.to_string_lossy()),
+            FileChange::Chmod(path, None) => write!(f, "chmod {}", path.to_string_lossy()),
+            FileChange::Chmod(path, Some(mode)) => {
+                write!(f, "chmod {} {:o}", path.to_string_lossy(), mode)
+            }
+            FileChange::Truncate(path) => write!(f, "truncate {}", path.to_string_lossy()),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ExpectedFileChange {
+    pub change: FileChange,
+    pub content: Option<Vec<u8>>,
+}
+
+impl ExpectedFileChange {
+    fn from_object(object: &Hash) -> R<ExpectedFileChange> {
+        check_keys(&["path", "operation", "to", "mode", "content"], object)?;
+        let path = PathBuf::from(object.expect_field("path")?.expect_str()?);
+        let operation = object.expect_field("operation")?.expect_str()?;
+        let change = match operation {
+            "write" => FileChange::Write(path),
+            "delete" => FileChange::Delete(path),
+            "rename" => FileChange::Rename(
+                path,
+                PathBuf::from(object.expect_field("to")?.expect_str()?),
+            ),
+            "mkdir" => FileChange::Mkdir(path),
+            "rmdir" => FileChange::Rmdir(path),
+            "chmod" => FileChange::Chmod(
+                path,
+                match object.expect_field("mode") {
+                    Ok(mode) => Some(MockedFile::parse_mode(mode)?),
+                    Err(_) => None,
+                },
+            ),
+            "truncate" => FileChange::Truncate(path),
+            other => Err(format!(
+                "expected one of 'write', 'delete', 'rename', 'mkdir', 'rmdir', 'chmod', \
+                 'truncate', got: {:?}",
+                other
+            ))?,
+        };
+        if object.expect_field("to").is_ok() && operation != "rename" {
+            Err("'to' is only allowed for file changes with operation 'rename'")?;
+        }
+        if object.expect_field("mode").is_ok() && operation != "chmod" {
+            Err("'mode' is only allowed for file changes with operation 'chmod'")?;
+        }
+        let content = match object.expect_field("content") {
+            Ok(content) => {
+                if operation != "write" {
+                    Err("'content' is only allowed for file changes with operation 'write'")?;
+                }
+                Some(content.expect_bytes()?)
+            }
+            Err(_) => None,
+        };
+        Ok(ExpectedFileChange { change, content })
+    }
+
+    pub fn parse(yaml: &Yaml) -> R<ExpectedFileChange> {
+        ExpectedFileChange::from_object(yaml.expect_object()?)
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        fn path(path: &Path) -> Yaml {
+            Yaml::String(path.to_string_lossy().into_owned())
+        }
+        let mut object = Hash::new();
+        object.insert(Yaml::from_str("path"), path(self.change.path()));
+        let operation = match &self.change {
+            FileChange::Write(_) => "write",
+            FileChange::Delete(_) => "delete",
+            FileChange::Rename(_, to) => {
+                object.insert(Yaml::from_str("to"), path(to));
+                "rename"
+            }
+            FileChange::Mkdir(_) => "mkdir",
+            FileChange::Rmdir(_) => "rmdir",
+            FileChange::Chmod(_, mode) => {
+                if let Some(mode) = mode {
+                    object.insert(Yaml::from_str("mode"), MockedFile::serialize_mode(*mode));
+                }
+                "chmod"
+            }
+            FileChange::Truncate(_) => "truncate",
+        };
+        object.insert(Yaml::from_str("operation"), Yaml::from_str(operation));
+        if let Some(content) = &self.content {
+            object.insert(Yaml::from_str("content"), serialize_bytes(content));
+        }
+        Yaml::Hash(object)
+    }
+}
+
+#[cfg(test)]
+mod parse {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+    use yaml_rust::YamlLoader;
+
+    fn test_parse(yaml: &str) -> R<ExpectedFileChange> {
+        ExpectedFileChange::parse(&YamlLoader::load_from_str(yaml)?[0])
+    }
+
+    #[test]
+    fn parses_writes_with_content() -> R<()> {
+        assert_eq!(
+            test_parse(r#"{path: VERSION, operation: write, content: "1.2.3\n"}"#)?,
+            ExpectedFileChange {
+                change: FileChange::Write(PathBuf::from("VERSION")),
+                content: Some(b"1.2.3\n".to_vec()),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parses_renames() -> R<()> {
+        assert_eq!(
+            test_parse("{path: a, operation: rename, to: b}")?.change,
+            FileChange::Rename(PathBuf::from("a"), PathBuf::from("b"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parses_chmods_with_and_without_modes() -> R<()> {
+        assert_eq!(
+            test_parse("{path: a, operation: chmod, mode: '755'}")?.change,
+            FileChange::Chmod(PathBuf::from("a"), Some(0o755))
+        );
+        assert_eq!(
+            test_parse(r#"{path: a, operation: chmod, mode: "0755"}"#)?.change,
+            FileChange::Chmod(PathBuf::from("a"), Some(0o755))
+        );
+        assert_eq!(
+            test_parse("{path: a, operation: chmod}")?.change,
+            FileChange::Chmod(PathBuf::from("a"), None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_invalid_chmod_modes() {
+        assert_error!(
+            test_parse("{path: a, operation: chmod, mode: '9'}"),
+            "expected: octal file mode, got: String(\"9\")"
+        );
+    }
+
+    #[test]
+    fn disallows_unknown_operations() {
+        assert_error!(
+            test_parse("{path: a, operation: touch}"),
+            "expected one of 'write', 'delete', 'rename', 'mkdir', 'rmdir', 'chmod', \
+             'truncate', got: \"touch\""
+        );
+    }
+
+    #[test]
+    fn disallows_content_for_operations_other_than_write() {
+        assert_error!(
+            test_parse("{path: a, operation: delete, content: foo}"),
+            "'content' is only allowed for file changes with operation 'write'"
+        );
+    }
+}
+
+#[cfg(test)]
+mod matches {
+    use super::*;
+
+    #[test]
+    fn chmods_without_modes_match_any_mode() {
+        assert!(FileChange::Chmod(PathBuf::from("a"), None)
+            .matches(&FileChange::Chmod(PathBuf::from("a"), Some(0o755))));
+    }
+
+    #[test]
+    fn chmods_with_modes_only_match_the_same_mode() {
+        assert!(!FileChange::Chmod(PathBuf::from("a"), Some(0o700))
+            .matches(&FileChange::Chmod(PathBuf::from("a"), Some(0o755))));
+    }
+}
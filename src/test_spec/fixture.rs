@@ -0,0 +1,81 @@
+use super::yaml::*;
+use crate::R;
+use std::fs;
+use std::path::{Path, PathBuf};
+use yaml_rust::{yaml::Hash, Yaml};
+
+pub fn parse_file_field(object: &Hash) -> R<Option<PathBuf>> {
+    Ok(match object.expect_field("file") {
+        Ok(file) => Some(PathBuf::from(file.expect_str()?)),
+        Err(_) => None,
+    })
+}
+
+pub fn read_fixture(test_directory: &Path, path: &Path) -> R<Vec<u8>> {
+    let path = test_directory.join(path);
+    Ok(fs::read(&path).map_err(|error| {
+        format!(
+            "cannot read fixture file {}: {}",
+            path.to_string_lossy(),
+            error
+        )
+    })?)
+}
+
+pub fn write_fixture(test_directory: &Path, path: &Path, content: &[u8]) -> R<()> {
+    let path = test_directory.join(path);
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    fs::write(&path, content).map_err(|error| {
+        format!(
+            "cannot write fixture file {}: {}",
+            path.to_string_lossy(),
+            error
+        )
+    })?;
+    Ok(())
+}
+
+pub fn serialize_file_field(path: &Path) -> Yaml {
+    let mut object = Hash::new();
+    object.insert(
+        Yaml::from_str("file"),
+        Yaml::String(path.to_string_lossy().to_string()),
+    );
+    Yaml::Hash(object)
+}
+
+#[cfg(test)]
+mod fixture {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::{assert_error, TempFile};
+
+    #[test]
+    fn reads_fixtures_relative_to_the_test_directory() -> R<()> {
+        let tempfile = TempFile::new()?;
+        fs::write(tempfile.path(), "foo")?;
+        let directory = tempfile.path().parent().unwrap().to_path_buf();
+        assert_eq!(read_fixture(&directory, Path::new("file"))?, b"foo");
+        Ok(())
+    }
+
+    #[test]
+    fn writes_fixtures_into_new_directories() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let directory = tempfile.path().parent().unwrap().to_path_buf();
+        let path = Path::new("fixtures/output");
+        write_fixture(&directory, path, b"foo")?;
+        assert_eq!(read_fixture(&directory, path)?, b"foo");
+        Ok(())
+    }
+
+    #[test]
+    fn gives_nice_errors_for_missing_fixtures() {
+        assert_error!(
+            read_fixture(Path::new("/does-not-exist"), Path::new("output")),
+            "cannot read fixture file /does-not-exist/output: No such file or directory (os error 2)"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use super::yaml::*;
+use super::Step;
+use crate::R;
+use std::fs;
+use std::path::{Path, PathBuf};
+use yaml_rust::Yaml;
+
+struct Include {
+    name: String,
+    canonical_path: PathBuf,
+}
+
+fn read_included_steps(path: &Path) -> R<Vec<Yaml>> {
+    let contents = fs::read(path).map_err(|error| {
+        format!(
+            "cannot read included file {}: {}",
+            path.to_string_lossy(),
+            error
+        )
+    })?;
+    let documents = load_yaml(&String::from_utf8(contents)?)
+        .map_err(|error| format!("invalid YAML: {}", error))?;
+    if documents.len() != 1 {
+        Err("expected exactly one YAML document")?;
+    }
+    let steps = documents[0].expect_array()?.clone();
+    for step in &steps {
+        match step {
+            Yaml::String(step) if step == "_" => {
+                Err("holes ('_') are not allowed in included files")?;
+            }
+            step if is_include(step) => {}
+            step => {
+                Step::parse(step)?;
+            }
+        }
+    }
+    Ok(steps)
+}
+
+pub fn is_include(step: &Yaml) -> bool {
+    match step {
+        Yaml::Hash(object) => object.contains_key(&Yaml::from_str("include")),
+        _ => false,
+    }
+}
+
+fn include_steps(name: &str, path: &Path, chain: &mut Vec<Include>) -> R<Vec<Yaml>> {
+    let canonical_path = fs::canonicalize(path).map_err(|error| {
+        format!(
+            "cannot read included file {}: {}",
+            path.to_string_lossy(),
+            error
+        )
+    })?;
+    if chain
+        .iter()
+        .any(|include| include.canonical_path == canonical_path)
+    {
+        let mut names: Vec<&str> = chain.iter().map(|include| include.name.as_str()).collect();
+        names.push(name);
+        Err(format!("include cycle: {}", names.join(" -> ")))?;
+    }
+    let steps = read_included_steps(path)?;
+    chain.push(Include {
+        name: name.to_string(),
+        canonical_path,
+    });
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    let result = expand_steps(&steps, directory, chain)?;
+    chain.pop();
+    Ok(result)
+}
+
+fn expand_steps(steps: &[Yaml], directory: &Path, chain: &mut Vec<Include>) -> R<Vec<Yaml>> {
+    let mut result = vec![];
+    for step in steps {
+        match step {
+            Yaml::Hash(object) if is_include(step) => {
+                check_keys(&["include"], object)?;
+                let name = object.expect_field("include")?.expect_str()?;
+                result.extend(
+                    include_steps(name, &directory.join(name), chain)
+                        .map_err(|error| format!("in included file {}: {}", name, error))?,
+                );
+            }
+            step => result.push(step.clone()),
+        }
+    }
+    Ok(result)
+}
+
+fn resolve_test_includes(test: &mut Yaml, test_directory: &Path) -> R<()> {
+    if let Yaml::Hash(object) = test {
+        if let Some(Yaml::Array(steps)) = object.get_mut(&Yaml::from_str("steps")) {
+            *steps = expand_steps(steps, test_directory, &mut vec![])?;
+        }
+    }
+    Ok(())
+}
+
+pub fn resolve_includes(yaml: &mut Yaml, test_directory: &Path) -> R<()> {
+    match yaml {
+        Yaml::Array(tests) => {
+            for test in tests.iter_mut() {
+                resolve_test_includes(test, test_directory)?;
+            }
+        }
+        Yaml::Hash(object) => {
+            if let Some(Yaml::Array(tests)) = object.get_mut(&Yaml::from_str("tests")) {
+                for test in tests.iter_mut() {
+                    resolve_test_includes(test, test_directory)?;
+                }
+            }
+            if let Some(defaults) = object.get_mut(&Yaml::from_str("defaults")) {
+                resolve_test_includes(defaults, test_directory)?;
+            }
+        }
+        _ => {}
+    }
+    resolve_test_includes(yaml, test_directory)
+}
+
+#[cfg(test)]
+mod resolve_includes {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::{assert_error, trim_margin, TempFile};
+    use yaml_rust::YamlLoader;
+
+    fn test_resolve(tempfile: &TempFile, files: &[(&str, &str)], yaml: &str) -> R<Yaml> {
+        let directory = tempfile.path().parent().unwrap().to_path_buf();
+        for (path, contents) in files {
+            let path = directory.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, contents)?;
+        }
+        let mut yaml = YamlLoader::load_from_str(&trim_margin(yaml)?)?[0].clone();
+        resolve_includes(&mut yaml, &directory)?;
+        Ok(yaml)
+    }
+
+    fn steps(yaml: &str) -> R<Yaml> {
+        Ok(YamlLoader::load_from_str(yaml)?[0].clone())
+    }
+
+    #[test]
+    fn replaces_includes_with_the_included_steps() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let yaml = test_resolve(
+            &tempfile,
+            &[("common/preamble.yaml", "[git fetch, git status]")],
+            r"
+                |tests:
+                |  - steps:
+                |      - include: common/preamble.yaml
+                |      - git push
+            ",
+        )?;
+        assert_eq!(
+            yaml["tests"][0]["steps"],
+            steps("[git fetch, git status, git push]")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resolves_includes_in_defaults_and_single_tests() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let files = [("preamble.yaml", "[git fetch]")];
+        let yaml = test_resolve(
+            &tempfile,
+            &files,
+            r"
+                |defaults:
+                |  steps:
+                |    - include: preamble.yaml
+                |tests: []
+            ",
+        )?;
+        assert_eq!(yaml["defaults"]["steps"], steps("[git fetch]")?);
+        let yaml = test_resolve(
+            &tempfile,
+            &files,
+            r"
+                |steps:
+                |  - include: preamble.yaml
+            ",
+        )?;
+        assert_eq!(yaml["steps"], steps("[git fetch]")?);
+        Ok(())
+    }
+
+    #[test]
+    fn resolves_nested_includes_relative_to_the_including_file() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let yaml = test_resolve(
+            &tempfile,
+            &[
+                ("common/preamble.yaml", "[{include: aws.yaml}, git fetch]"),
+                ("common/aws.yaml", "[aws sts get-caller-identity]"),
+            ],
+            r"
+                |- steps:
+                |    - include: common/preamble.yaml
+            ",
+        )?;
+        assert_eq!(
+            yaml[0]["steps"],
+            steps("[aws sts get-caller-identity, git fetch]")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn detects_include_cycles() -> R<()> {
+        let tempfile = TempFile::new()?;
+        assert_error!(
+            test_resolve(
+                &tempfile,
+                &[
+                    ("a.yaml", "[{include: b.yaml}]"),
+                    ("b.yaml", "[{include: a.yaml}]"),
+                ],
+                r"
+                    |steps:
+                    |  - include: a.yaml
+                "
+            ),
+            "in included file a.yaml: in included file b.yaml: \
+             in included file a.yaml: include cycle: a.yaml -> b.yaml -> a.yaml"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_include_paths_for_invalid_steps() -> R<()> {
+        let tempfile = TempFile::new()?;
+        assert_error!(
+            test_resolve(
+                &tempfile,
+                &[("a.yaml", "[{include: b.yaml}]"), ("b.yaml", "[42]")],
+                r"
+                    |steps:
+                    |  - include: a.yaml
+                "
+            ),
+            "in included file a.yaml: in included file b.yaml: \
+             expected: string or array, got: Integer(42)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_holes_in_included_files() -> R<()> {
+        let tempfile = TempFile::new()?;
+        assert_error!(
+            test_resolve(
+                &tempfile,
+                &[("a.yaml", "[_]")],
+                r"
+                    |steps:
+                    |  - include: a.yaml
+                "
+            ),
+            "in included file a.yaml: holes ('_') are not allowed in included files"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn gives_nice_errors_for_missing_files() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let directory = tempfile.path().parent().unwrap().to_path_buf();
+        assert_error!(
+            test_resolve(
+                &tempfile,
+                &[],
+                r"
+                    |steps:
+                    |  - include: missing.yaml
+                "
+            ),
+            format!(
+                "in included file missing.yaml: cannot read included file {}: \
+                 No such file or directory (os error 2)",
+                directory.join("missing.yaml").to_string_lossy()
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use super::yaml::*;
+use crate::R;
+use std::collections::HashMap;
+use yaml_rust::{yaml::Hash, Yaml};
+
+const LIST_FIELDS: &[&str] = &[
+    "steps",
+    "mockedFiles",
+    "allowedDestinations",
+    "network",
+    "unsetEnv",
+];
+
+const MAP_FIELDS: &[&str] = &["env", "uname"];
+
+const NOT_INHERITED_FIELDS: &[&str] = &["name", "extends", "skip", "only"];
+
+fn merge_field(field: &str, base: &Yaml, test: &Yaml) -> R<Yaml> {
+    Ok(match base {
+        Yaml::Array(base) if LIST_FIELDS.contains(&field) => {
+            Yaml::Array(base.iter().chain(test.expect_array()?).cloned().collect())
+        }
+        Yaml::Hash(base) if MAP_FIELDS.contains(&field) => {
+            let mut result = base.clone();
+            for (key, value) in test.expect_object()? {
+                result.insert(key.clone(), value.clone());
+            }
+            Yaml::Hash(result)
+        }
+        _ => test.clone(),
+    })
+}
+
+pub fn merge(base: &Hash, test: &Hash) -> R<Hash> {
+    let mut result = Hash::new();
+    for (key, value) in base {
+        let field = key.expect_str()?;
+        if !NOT_INHERITED_FIELDS.contains(&field) {
+            let value = match test.get(key) {
+                Some(test_value) => merge_field(field, value, test_value)?,
+                None => value.clone(),
+            };
+            result.insert(key.clone(), value);
+        }
+    }
+    for (key, value) in test {
+        if !result.contains_key(key) {
+            result.insert(key.clone(), value.clone());
+        }
+    }
+    Ok(result)
+}
+
+pub fn check_defaults(defaults: &Hash) -> R<()> {
+    for field in NOT_INHERITED_FIELDS {
+        if defaults.contains_key(&Yaml::from_str(field)) {
+            Err(format!("'{}' is not allowed in 'defaults'", field))?;
+        }
+    }
+    Ok(())
+}
+
+struct Resolver<'a> {
+    tests: Vec<&'a Hash>,
+    names: HashMap<String, usize>,
+    defaults: Option<&'a Hash>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(tests: &'a [Yaml], defaults: Option<&'a Hash>) -> R<Resolver<'a>> {
+        let tests = tests
+            .iter()
+            .map(|test| test.expect_object())
+            .collect::<R<Vec<&Hash>>>()?;
+        let mut names = HashMap::new();
+        for (index, test) in tests.iter().enumerate() {
+            if let Ok(name) = test.expect_field("name") {
+                let name = name.expect_str()?;
+                if names.insert(name.to_string(), index).is_some() {
+                    Err(format!("duplicate test name: {}", name))?;
+                }
+            }
+        }
+        Ok(Resolver {
+            tests,
+            names,
+            defaults,
+        })
+    }
+
+    fn resolve(&self, index: usize, chain: &mut Vec<String>) -> R<Hash> {
+        let test = self.tests[index];
+        let base = match test.expect_field("extends") {
+            Ok(extends) => {
+                let extends = extends.expect_str()?;
+                let is_cyclic = chain.iter().any(|name| name == extends);
+                chain.push(extends.to_string());
+                if is_cyclic {
+                    Err(format!("cyclic 'extends': {}", chain.join(" -> ")))?;
+                }
+                let base_index = *self
+                    .names
+                    .get(extends)
+                    .ok_or_else(|| format!("'extends' refers to unknown test: {}", extends))?;
+                let base = self.resolve(base_index, chain)?;
+                chain.pop();
+                base
+            }
+            Err(_) => self.defaults.cloned().unwrap_or_else(Hash::new),
+        };
+        merge(&base, test)
+    }
+}
+
+pub fn resolve_tests(tests: &[Yaml], defaults: Option<&Hash>) -> R<Vec<Hash>> {
+    let resolver = Resolver::new(tests, defaults)?;
+    (0..tests.len())
+        .map(|index| {
+            let mut chain = match resolver.tests[index].expect_field("name") {
+                Ok(name) => vec![name.expect_str()?.to_string()],
+                Err(_) => vec![],
+            };
+            resolver.resolve(index, &mut chain)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod resolve_tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::{assert_error, trim_margin};
+    use yaml_rust::YamlLoader;
+
+    fn test_resolve(yaml: &str) -> R<Vec<Yaml>> {
+        let yaml = &YamlLoader::load_from_str(&trim_margin(yaml)?)?[0];
+        Ok(
+            resolve_tests(yaml["tests"].expect_array()?, yaml["defaults"].as_hash())?
+                .into_iter()
+                .map(Yaml::Hash)
+                .collect(),
+        )
+    }
+
+    fn yaml(yaml: &str) -> R<Vec<Yaml>> {
+        Ok(YamlLoader::load_from_str(&trim_margin(yaml)?)?[0]
+            .expect_array()?
+            .clone())
+    }
+
+    #[test]
+    fn merges_defaults_into_all_tests() -> R<()> {
+        assert_eq!(
+            test_resolve(
+                r"
+                    |defaults:
+                    |  cwd: /foo
+                    |  env: {A: a, B: b}
+                    |  steps: [setup]
+                    |  mockedFiles: [/a]
+                    |tests:
+                    |  - env: {B: c}
+                    |    steps: [foo]
+                    |    mockedFiles: [/b]
+                    |  - cwd: /bar
+                "
+            )?,
+            yaml(
+                r"
+                    |- cwd: /foo
+                    |  env: {A: a, B: c}
+                    |  steps: [setup, foo]
+                    |  mockedFiles: [/a, /b]
+                    |- cwd: /bar
+                    |  env: {A: a, B: b}
+                    |  steps: [setup]
+                    |  mockedFiles: [/a]
+                "
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn inherits_from_named_tests() -> R<()> {
+        assert_eq!(
+            test_resolve(
+                r"
+                    |defaults:
+                    |  steps: [setup]
+                    |tests:
+                    |  - name: base
+                    |    steps: [foo]
+                    |    exitcode: 1
+                    |  - name: derived
+                    |    extends: base
+                    |    steps: [bar]
+                "
+            )?,
+            yaml(
+                r"
+                    |- steps: [setup, foo]
+                    |  name: base
+                    |  exitcode: 1
+                    |- steps: [setup, foo, bar]
+                    |  exitcode: 1
+                    |  name: derived
+                    |  extends: base
+                "
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allows_chains_of_extends() -> R<()> {
+        assert_eq!(
+            test_resolve(
+                r"
+                    |tests:
+                    |  - name: c
+                    |    extends: b
+                    |    steps: [c]
+                    |  - name: b
+                    |    extends: a
+                    |    steps: [b]
+                    |  - name: a
+                    |    steps: [a]
+                "
+            )?[0]["steps"],
+            Yaml::Array(vec![
+                Yaml::from_str("a"),
+                Yaml::from_str("b"),
+                Yaml::from_str("c")
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_unknown_tests_in_extends() {
+        assert_error!(
+            test_resolve(
+                r"
+                    |tests:
+                    |  - extends: foo
+                    |    steps: []
+                "
+            ),
+            "'extends' refers to unknown test: foo"
+        );
+    }
+
+    #[test]
+    fn disallows_cyclic_extends() {
+        assert_error!(
+            test_resolve(
+                r"
+                    |tests:
+                    |  - name: a
+                    |    extends: b
+                    |    steps: []
+                    |  - name: b
+                    |    extends: a
+                    |    steps: []
+                "
+            ),
+            "cyclic 'extends': a -> b -> a"
+        );
+    }
+
+    #[test]
+    fn disallows_duplicate_names() {
+        assert_error!(
+            test_resolve(
+                r"
+                    |tests:
+                    |  - name: a
+                    |    steps: []
+                    |  - name: a
+                    |    steps: []
+                "
+            ),
+            "duplicate test name: a"
+        );
+    }
+}
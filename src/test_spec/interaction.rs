@@ -0,0 +1,105 @@
+use super::yaml::*;
+use crate::R;
+use std::time::Duration;
+use yaml_rust::{yaml::Hash, Yaml};
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 5;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Interaction {
+    pub expect: Vec<u8>,
+    pub send: Vec<u8>,
+    pub timeout: Duration,
+}
+
+impl Interaction {
+    pub fn parse(yaml: &Yaml) -> R<Interaction> {
+        let object = yaml.expect_object()?;
+        check_keys(&["expect", "send", "timeout"], object)?;
+        let timeout = match object.expect_field("timeout") {
+            Ok(timeout) => {
+                let timeout = timeout.expect_integer()?;
+                if timeout <= 0 {
+                    Err(format!(
+                        "interaction timeouts have to be positive, got: {}",
+                        timeout
+                    ))?;
+                }
+                timeout as u64
+            }
+            Err(_) => DEFAULT_TIMEOUT_SECONDS,
+        };
+        let expect = object.expect_field("expect")?.expect_bytes()?;
+        if expect.is_empty() {
+            Err("interaction prompts ('expect') can't be empty")?;
+        }
+        Ok(Interaction {
+            expect,
+            send: object.expect_field("send")?.expect_bytes()?,
+            timeout: Duration::from_secs(timeout),
+        })
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        let mut object = Hash::new();
+        object.insert(Yaml::from_str("expect"), serialize_bytes(&self.expect));
+        object.insert(Yaml::from_str("send"), serialize_bytes(&self.send));
+        if self.timeout != Duration::from_secs(DEFAULT_TIMEOUT_SECONDS) {
+            object.insert(
+                Yaml::from_str("timeout"),
+                Yaml::Integer(self.timeout.as_secs() as i64),
+            );
+        }
+        Yaml::Hash(object)
+    }
+}
+
+#[cfg(test)]
+mod parse {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+    use yaml_rust::YamlLoader;
+
+    fn test_parse(yaml: &str) -> R<Interaction> {
+        Interaction::parse(&YamlLoader::load_from_str(yaml)?[0])
+    }
+
+    #[test]
+    fn parses_prompts_and_answers() -> R<()> {
+        assert_eq!(
+            test_parse(r#"{expect: "Deploy to prod? ", send: "y\n"}"#)?,
+            Interaction {
+                expect: b"Deploy to prod? ".to_vec(),
+                send: b"y\n".to_vec(),
+                timeout: Duration::from_secs(5),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_specify_timeouts_in_seconds() -> R<()> {
+        assert_eq!(
+            test_parse("{expect: foo, send: bar, timeout: 30}")?.timeout,
+            Duration::from_secs(30)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_non_positive_timeouts() {
+        assert_error!(
+            test_parse("{expect: foo, send: bar, timeout: 0}"),
+            "interaction timeouts have to be positive, got: 0"
+        );
+    }
+
+    #[test]
+    fn disallows_empty_prompts() {
+        assert_error!(
+            test_parse(r#"{expect: "", send: "y\n"}"#),
+            "interaction prompts ('expect') can't be empty"
+        );
+    }
+}
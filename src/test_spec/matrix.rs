@@ -0,0 +1,262 @@
+use super::yaml::*;
+use crate::R;
+use yaml_rust::{yaml::Hash, Yaml};
+
+type Parameters = Vec<(String, String)>;
+
+fn parse_value(value: &Yaml) -> R<String> {
+    Ok(match value {
+        Yaml::String(string) => string.clone(),
+        Yaml::Integer(integer) => integer.to_string(),
+        Yaml::Real(real) => real.clone(),
+        Yaml::Boolean(boolean) => boolean.to_string(),
+        _ => Err(format!(
+            "expected: string, number or boolean as matrix value, got: {:?}",
+            value
+        ))?,
+    })
+}
+
+fn parse_matrix(matrix: &Yaml) -> R<Vec<(String, Vec<String>)>> {
+    let mut result = vec![];
+    for (name, values) in matrix.expect_object()? {
+        let name = name.expect_str()?;
+        let values = values
+            .expect_array()?
+            .iter()
+            .map(parse_value)
+            .collect::<R<Vec<String>>>()?;
+        if values.is_empty() {
+            Err(format!("matrix parameter '{}' has no values", name))?;
+        }
+        result.push((name.to_string(), values));
+    }
+    Ok(result)
+}
+
+fn combinations(matrix: &[(String, Vec<String>)]) -> Vec<Parameters> {
+    let mut result: Vec<Parameters> = vec![vec![]];
+    for (name, values) in matrix {
+        result = result
+            .into_iter()
+            .flat_map(|parameters| {
+                values.iter().map(move |value| {
+                    let mut parameters = parameters.clone();
+                    parameters.push((name.clone(), value.clone()));
+                    parameters
+                })
+            })
+            .collect();
+    }
+    result
+}
+
+fn substitute_string(string: &str, parameters: &[(String, String)]) -> String {
+    let mut result = String::new();
+    let mut rest = string;
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+        if rest.starts_with("$$") {
+            result.push_str("$$");
+            rest = &rest[2..];
+            continue;
+        }
+        let parameter = parameters
+            .iter()
+            .find(|(name, _)| rest.starts_with(&format!("${{{}}}", name)));
+        match parameter {
+            Some((name, value)) => {
+                result.push_str(value);
+                rest = &rest[name.len() + 3..];
+            }
+            None => {
+                result.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn substitute(yaml: &Yaml, parameters: &[(String, String)]) -> Yaml {
+    match yaml {
+        Yaml::String(string) => Yaml::String(substitute_string(string, parameters)),
+        Yaml::Array(array) => Yaml::Array(
+            array
+                .iter()
+                .map(|element| substitute(element, parameters))
+                .collect(),
+        ),
+        Yaml::Hash(object) => Yaml::Hash(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), substitute(value, parameters)))
+                .collect(),
+        ),
+        yaml => yaml.clone(),
+    }
+}
+
+fn generate_name(name: Option<&Yaml>, parameters: &[(String, String)]) -> R<String> {
+    let parameters = parameters
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<String>>()
+        .join(", ");
+    Ok(match name {
+        Some(name) => format!("{} [{}]", name.expect_str()?, parameters),
+        None => parameters,
+    })
+}
+
+pub fn expand_matrix(test: Hash) -> R<Vec<Hash>> {
+    let matrix_key = Yaml::from_str("matrix");
+    let matrix = match test.get(&matrix_key) {
+        Some(matrix) => parse_matrix(matrix)?,
+        None => return Ok(vec![test]),
+    };
+    let name_key = Yaml::from_str("name");
+    let mut result = vec![];
+    for parameters in combinations(&matrix) {
+        let mut expanded = Hash::new();
+        for (key, value) in &test {
+            if *key != matrix_key && *key != name_key {
+                expanded.insert(key.clone(), substitute(value, &parameters));
+            }
+        }
+        let name = generate_name(test.get(&name_key), &parameters)?;
+        expanded.insert(name_key.clone(), Yaml::String(name));
+        result.push(expanded);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod expand_matrix {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::{assert_error, trim_margin};
+    use yaml_rust::YamlLoader;
+
+    fn test_expand(yaml: &str) -> R<Vec<Yaml>> {
+        let yaml = YamlLoader::load_from_str(&trim_margin(yaml)?)?[0].clone();
+        Ok(expand_matrix(yaml.expect_object()?.clone())?
+            .into_iter()
+            .map(Yaml::Hash)
+            .collect())
+    }
+
+    fn yaml(yaml: &str) -> R<Vec<Yaml>> {
+        Ok(YamlLoader::load_from_str(&trim_margin(yaml)?)?[0]
+            .expect_array()?
+            .clone())
+    }
+
+    #[test]
+    fn leaves_tests_without_matrix_unchanged() -> R<()> {
+        assert_eq!(
+            test_expand(
+                r"
+                    |steps:
+                    |  - cp ${FOO}
+                "
+            )?,
+            yaml(
+                r"
+                    |- steps:
+                    |    - cp ${FOO}
+                "
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn substitutes_parameters_into_all_fields() -> R<()> {
+        assert_eq!(
+            test_expand(
+                r"
+                    |matrix:
+                    |  ENV: [staging, prod]
+                    |arguments: ${ENV}
+                    |env:
+                    |  TARGET: ${ENV}-cluster
+                    |steps:
+                    |  - deploy --env ${ENV}
+                    |stdout: deployed to ${ENV}
+                "
+            )?,
+            yaml(
+                r"
+                    |- arguments: staging
+                    |  env:
+                    |    TARGET: staging-cluster
+                    |  steps:
+                    |    - deploy --env staging
+                    |  stdout: deployed to staging
+                    |  name: ENV=staging
+                    |- arguments: prod
+                    |  env:
+                    |    TARGET: prod-cluster
+                    |  steps:
+                    |    - deploy --env prod
+                    |  stdout: deployed to prod
+                    |  name: ENV=prod
+                "
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn expands_into_all_combinations() -> R<()> {
+        assert_eq!(
+            test_expand(
+                r"
+                    |name: deploy
+                    |matrix:
+                    |  ENV: [staging, prod]
+                    |  REPLICAS: [1, 2]
+                    |steps: []
+                "
+            )?
+            .iter()
+            .map(|test| test["name"].clone())
+            .collect::<Vec<_>>(),
+            yaml(
+                r"
+                    |- deploy [ENV=staging, REPLICAS=1]
+                    |- deploy [ENV=staging, REPLICAS=2]
+                    |- deploy [ENV=prod, REPLICAS=1]
+                    |- deploy [ENV=prod, REPLICAS=2]
+                "
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_substitute_unknown_or_escaped_parameters() {
+        let parameters = vec![("FOO".to_string(), "foo".to_string())];
+        assert_eq!(
+            substitute_string("$FOO ${FOO} $${FOO} ${BAR} $", &parameters),
+            "$FOO foo $${FOO} ${BAR} $"
+        );
+    }
+
+    #[test]
+    fn disallows_empty_parameters() {
+        assert_error!(
+            test_expand(
+                r"
+                    |matrix:
+                    |  ENV: []
+                    |steps: []
+                "
+            ),
+            "matrix parameter 'ENV' has no values"
+        );
+    }
+}
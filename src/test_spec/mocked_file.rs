@@ -0,0 +1,415 @@
+use super::yaml::*;
+use crate::R;
+use libc::{c_int, mode_t};
+use nix::unistd::{getgid, getuid};
+use std::path::PathBuf;
+use yaml_rust::{yaml::Hash, Yaml};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum FileType {
+    File,
+    Directory,
+    Symlink,
+    Fifo,
+}
+
+impl FileType {
+    fn parse(yaml: &Yaml) -> R<FileType> {
+        Ok(match yaml.expect_str()? {
+            "file" => FileType::File,
+            "directory" => FileType::Directory,
+            "symlink" => FileType::Symlink,
+            "fifo" => FileType::Fifo,
+            other => Err(format!(
+                "expected one of 'file', 'directory', 'symlink', 'fifo', got: {:?}",
+                other
+            ))?,
+        })
+    }
+
+    fn format(self) -> &'static str {
+        match self {
+            FileType::File => "file",
+            FileType::Directory => "directory",
+            FileType::Symlink => "symlink",
+            FileType::Fifo => "fifo",
+        }
+    }
+
+    fn format_bits(self) -> mode_t {
+        match self {
+            FileType::File => libc::S_IFREG,
+            FileType::Directory => libc::S_IFDIR,
+            FileType::Symlink => libc::S_IFLNK,
+            FileType::Fifo => libc::S_IFIFO,
+        }
+    }
+
+    fn default_mode(self) -> mode_t {
+        match self {
+            FileType::File | FileType::Fifo => 0o644,
+            FileType::Directory => 0o755,
+            FileType::Symlink => 0o777,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MockedFile {
+    pub path: PathBuf,
+    pub file_type: FileType,
+    pub mode: mode_t,
+    pub size: i64,
+    pub uid: u32,
+    pub gid: u32,
+    pub mtime: i64,
+    pub target: Option<PathBuf>,
+}
+
+impl MockedFile {
+    pub fn new(path: &str) -> MockedFile {
+        let file_type = if path.ends_with('/') {
+            FileType::Directory
+        } else {
+            FileType::File
+        };
+        MockedFile::with_type(PathBuf::from(path), file_type)
+    }
+
+    fn with_type(path: PathBuf, file_type: FileType) -> MockedFile {
+        MockedFile {
+            path,
+            file_type,
+            mode: file_type.default_mode(),
+            size: 0,
+            uid: getuid().as_raw(),
+            gid: getgid().as_raw(),
+            mtime: 0,
+            target: None,
+        }
+    }
+
+    pub fn parse_mode(yaml: &Yaml) -> R<mode_t> {
+        let digits = match yaml {
+            Yaml::String(string) if string.starts_with("0o") => &string[2..],
+            Yaml::String(string) => string.as_str(),
+            // Unquoted modes like 755 or 0o644 are loaded as integers, which
+            // don't tell whether they were meant to be octal.
+            Yaml::Integer(_) => Err(format!(
+                "expected: quoted octal file mode (e.g. \"755\"), got: {:?}",
+                yaml
+            ))?,
+            _ => Err(format!("expected: octal file mode, got: {:?}", yaml))?,
+        };
+        let mode = mode_t::from_str_radix(digits, 8)
+            .map_err(|_| format!("expected: octal file mode, got: {:?}", yaml))?;
+        if mode > 0o7777 {
+            Err(format!("file mode out of range: {:o}", mode))?;
+        }
+        Ok(mode)
+    }
+
+    fn from_object(object: &Hash) -> R<MockedFile> {
+        check_keys(
+            &[
+                "path", "type", "mode", "size", "uid", "gid", "mtime", "target",
+            ],
+            object,
+        )?;
+        let path = object.expect_field("path")?.expect_str()?;
+        let mut result = match object.expect_field("type") {
+            Ok(file_type) => {
+                MockedFile::with_type(PathBuf::from(path), FileType::parse(file_type)?)
+            }
+            Err(_) => MockedFile::new(path),
+        };
+        if let Ok(mode) = object.expect_field("mode") {
+            result.mode = MockedFile::parse_mode(mode)?;
+        }
+        if let Ok(size) = object.expect_field("size") {
+            result.size = i64::from(size.expect_integer()?);
+        }
+        if let Ok(uid) = object.expect_field("uid") {
+            result.uid = uid.expect_integer()? as u32;
+        }
+        if let Ok(gid) = object.expect_field("gid") {
+            result.gid = gid.expect_integer()? as u32;
+        }
+        if let Ok(mtime) = object.expect_field("mtime") {
+            result.mtime = mtime
+                .as_i64()
+                .ok_or_else(|| format!("expected: integer, got: {:?}", mtime))?;
+        }
+        if let Ok(target) = object.expect_field("target") {
+            if result.file_type != FileType::Symlink {
+                Err("'target' is only allowed for mocked files of type 'symlink'")?;
+            }
+            result.target = Some(PathBuf::from(target.expect_str()?));
+        }
+        Ok(result)
+    }
+
+    pub fn parse(yaml: &Yaml) -> R<MockedFile> {
+        match yaml {
+            Yaml::String(path) => Ok(MockedFile::new(path)),
+            Yaml::Hash(object) => MockedFile::from_object(object),
+            _ => Err(format!("expected: string or object, got: {:?}", yaml))?,
+        }
+    }
+
+    pub fn serialize_mode(mode: mode_t) -> Yaml {
+        Yaml::String(format!("{:o}", mode))
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        let path = self.path.to_string_lossy().into_owned();
+        let default = MockedFile::with_type(self.path.clone(), self.file_type);
+        let default_type = MockedFile::new(&path).file_type;
+        if *self == default && self.file_type == default_type {
+            return Yaml::String(path);
+        }
+        let mut object = Hash::new();
+        object.insert(Yaml::from_str("path"), Yaml::String(path));
+        if self.file_type != default_type {
+            object.insert(
+                Yaml::from_str("type"),
+                Yaml::from_str(self.file_type.format()),
+            );
+        }
+        if self.mode != default.mode {
+            object.insert(
+                Yaml::from_str("mode"),
+                MockedFile::serialize_mode(self.mode),
+            );
+        }
+        if self.size != default.size {
+            object.insert(Yaml::from_str("size"), Yaml::Integer(self.size));
+        }
+        if self.uid != default.uid {
+            object.insert(Yaml::from_str("uid"), Yaml::Integer(i64::from(self.uid)));
+        }
+        if self.gid != default.gid {
+            object.insert(Yaml::from_str("gid"), Yaml::Integer(i64::from(self.gid)));
+        }
+        if self.mtime != default.mtime {
+            object.insert(Yaml::from_str("mtime"), Yaml::Integer(self.mtime));
+        }
+        if let Some(target) = &self.target {
+            object.insert(
+                Yaml::from_str("target"),
+                Yaml::String(target.to_string_lossy().into_owned()),
+            );
+        }
+        Yaml::Hash(object)
+    }
+
+    pub fn to_stat(&self) -> libc::stat {
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        stat.st_mode = self.file_type.format_bits() | self.mode;
+        stat.st_nlink = if self.file_type == FileType::Directory {
+            2
+        } else {
+            1
+        };
+        stat.st_uid = self.uid;
+        stat.st_gid = self.gid;
+        stat.st_size = self.size;
+        stat.st_blksize = 4096;
+        stat.st_blocks = (self.size + 511) / 512;
+        stat.st_atime = self.mtime;
+        stat.st_mtime = self.mtime;
+        stat.st_ctime = self.mtime;
+        stat
+    }
+
+    pub fn allows_access(&self, mode: c_int, uid: u32, gid: u32) -> bool {
+        let requested = (mode & (libc::R_OK | libc::W_OK | libc::X_OK)) as mode_t;
+        if uid == 0 {
+            requested & (libc::X_OK as mode_t) == 0
+                || self.mode & 0o111 != 0
+                || self.file_type == FileType::Directory
+        } else {
+            let granted = if uid == self.uid {
+                self.mode >> 6
+            } else if gid == self.gid {
+                self.mode >> 3
+            } else {
+                self.mode
+            } & 0o7;
+            requested & !granted == 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod mocked_file {
+    use super::*;
+    use test_utils::assert_error;
+    use yaml_rust::YamlLoader;
+
+    fn test_parse(yaml: &str) -> R<MockedFile> {
+        MockedFile::parse(&YamlLoader::load_from_str(yaml)?[0])
+    }
+
+    mod parse {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn parses_strings_to_regular_files() -> R<()> {
+            let file = test_parse("/foo")?;
+            assert_eq!(file.path, PathBuf::from("/foo"));
+            assert_eq!(file.file_type, FileType::File);
+            assert_eq!(file.mode, 0o644);
+            Ok(())
+        }
+
+        #[test]
+        fn parses_strings_with_trailing_slashes_to_directories() -> R<()> {
+            assert_eq!(test_parse("/foo/")?.file_type, FileType::Directory);
+            Ok(())
+        }
+
+        #[test]
+        fn allows_to_specify_all_metadata() -> R<()> {
+            assert_eq!(
+                test_parse(
+                    "{path: /foo, type: symlink, mode: '700', size: 42, \
+                     uid: 1, gid: 2, mtime: 1560000000, target: /bar}"
+                )?,
+                MockedFile {
+                    path: PathBuf::from("/foo"),
+                    file_type: FileType::Symlink,
+                    mode: 0o700,
+                    size: 42,
+                    uid: 1,
+                    gid: 2,
+                    mtime: 1_560_000_000,
+                    target: Some(PathBuf::from("/bar")),
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn parses_string_modes_as_octal() -> R<()> {
+            assert_eq!(test_parse(r#"{path: /foo, mode: "755"}"#)?.mode, 0o755);
+            Ok(())
+        }
+
+        #[test]
+        fn allows_an_octal_prefix_in_string_modes() -> R<()> {
+            assert_eq!(test_parse(r#"{path: /foo, mode: "0o644"}"#)?.mode, 0o644);
+            assert_eq!(test_parse(r#"{path: /foo, mode: "0644"}"#)?.mode, 0o644);
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_integer_modes() {
+            assert_error!(
+                test_parse("{path: /foo, mode: 0o644}"),
+                "expected: quoted octal file mode (e.g. \"755\"), got: Integer(420)"
+            );
+            assert_error!(
+                test_parse("{path: /foo, mode: 755}"),
+                "expected: quoted octal file mode (e.g. \"755\"), got: Integer(755)"
+            );
+        }
+
+        #[test]
+        fn disallows_invalid_modes() {
+            assert_error!(
+                test_parse(r#"{path: /foo, mode: "789"}"#),
+                "expected: octal file mode, got: String(\"789\")"
+            );
+            assert_error!(
+                test_parse(r#"{path: /foo, mode: "-1"}"#),
+                "expected: octal file mode, got: String(\"-1\")"
+            );
+            assert_error!(
+                test_parse(r#"{path: /foo, mode: "17777"}"#),
+                "file mode out of range: 17777"
+            );
+        }
+
+        #[test]
+        fn disallows_unknown_file_types() {
+            assert_error!(
+                test_parse("{path: /foo, type: socket}"),
+                "expected one of 'file', 'directory', 'symlink', 'fifo', got: \"socket\""
+            );
+        }
+
+        #[test]
+        fn disallows_targets_for_non_symlinks() {
+            assert_error!(
+                test_parse("{path: /foo, target: /bar}"),
+                "'target' is only allowed for mocked files of type 'symlink'"
+            );
+        }
+    }
+
+    mod serialize {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn serializes_files_without_metadata_as_paths() -> R<()> {
+            assert_eq!(MockedFile::new("/foo").serialize(), Yaml::from_str("/foo"));
+            assert_eq!(
+                MockedFile::new("/foo/").serialize(),
+                Yaml::from_str("/foo/")
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn roundtrips_metadata() -> R<()> {
+            let file = test_parse(
+                "{path: /foo, type: symlink, mode: '700', size: 42, \
+                 uid: 1, gid: 2, mtime: 1560000000, target: /bar}",
+            )?;
+            assert_eq!(MockedFile::parse(&file.serialize())?, file);
+            let directory = test_parse("{path: /foo, type: directory, mode: '0o700'}")?;
+            assert_eq!(MockedFile::parse(&directory.serialize())?, directory);
+            Ok(())
+        }
+    }
+
+    mod allows_access {
+        use super::*;
+
+        fn file_with_mode(mode: mode_t) -> MockedFile {
+            let mut file = MockedFile::new("/foo");
+            file.mode = mode;
+            file.uid = 1000;
+            file.gid = 1000;
+            file
+        }
+
+        #[test]
+        fn uses_the_owner_bits_for_the_owner() {
+            assert!(file_with_mode(0o700).allows_access(libc::X_OK, 1000, 2000));
+            assert!(!file_with_mode(0o077).allows_access(libc::R_OK, 1000, 1000));
+        }
+
+        #[test]
+        fn uses_the_group_bits_for_group_members() {
+            assert!(file_with_mode(0o050).allows_access(libc::R_OK | libc::X_OK, 2000, 1000));
+            assert!(!file_with_mode(0o050).allows_access(libc::W_OK, 2000, 1000));
+        }
+
+        #[test]
+        fn uses_the_other_bits_for_everyone_else() {
+            assert!(file_with_mode(0o004).allows_access(libc::R_OK, 2000, 2000));
+            assert!(!file_with_mode(0o770).allows_access(libc::R_OK, 2000, 2000));
+        }
+
+        #[test]
+        fn grants_everything_but_execution_to_root() {
+            assert!(file_with_mode(0o000).allows_access(libc::R_OK | libc::W_OK, 0, 0));
+            assert!(!file_with_mode(0o644).allows_access(libc::X_OK, 0, 0));
+            assert!(file_with_mode(0o744).allows_access(libc::X_OK, 0, 0));
+        }
+    }
+}
@@ -3,28 +3,59 @@ extern crate yaml_rust;
 mod argument_parser;
 pub mod command;
 pub mod command_matcher;
+pub mod destination;
+pub mod environment;
+pub mod exec_error;
 mod executable_path;
+pub mod file_change;
+pub mod fixture;
+mod include;
+mod inheritance;
+pub mod interaction;
+mod matrix;
+pub mod mocked_file;
+pub mod output_matcher;
+pub mod stdin;
+pub mod tty;
+pub mod uname;
+mod variables;
 pub mod yaml;
 
 use self::argument_parser::Parser;
-pub use self::executable_path::compare_executables;
+pub use self::executable_path::{
+    basename, is_mocked_executable, search_path, shorten_executable, ExecutableMatching,
+};
+use self::variables::Variables;
 use crate::test_spec::yaml::*;
 use crate::utils::{path_to_string, with_has_more};
 use crate::R;
 pub use command::Command;
 pub use command_matcher::{AnchoredRegex, CommandMatcher};
+pub use destination::{Destination, NetworkMapping, ResolvedDestination};
+pub use environment::InheritEnv;
+pub use exec_error::ExecError;
+pub use file_change::{ExpectedFileChange, FileChange};
+pub use interaction::Interaction;
 use linked_hash_map::LinkedHashMap;
+pub use mocked_file::{FileType, MockedFile};
+pub use output_matcher::OutputMatcher;
+use regex::Regex;
 use std::collections::{HashMap, VecDeque};
 use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
-use yaml_rust::{yaml::Hash, Yaml, YamlLoader};
+pub use stdin::Stdin;
+pub use tty::Tty;
+pub use uname::Uname;
+use yaml_rust::{yaml::Hash, Yaml};
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Step {
     pub command_matcher: CommandMatcher,
     pub stdout: Vec<u8>,
+    pub stdout_file: Option<PathBuf>,
     pub exitcode: i32,
+    pub exec_error: Option<ExecError>,
 }
 
 impl Step {
@@ -32,7 +63,9 @@ impl Step {
         Step {
             command_matcher,
             stdout: vec![],
+            stdout_file: None,
             exitcode: 0,
+            exec_error: None,
         }
     }
 
@@ -49,7 +82,38 @@ impl Step {
 
     fn add_stdout(&mut self, object: &Hash) -> R<()> {
         if let Ok(stdout) = object.expect_field("stdout") {
-            self.stdout = stdout.expect_bytes()?;
+            match stdout {
+                Yaml::Hash(object) if !stdout.is_binary() => {
+                    check_keys(&["file"], object)?;
+                    self.stdout_file = fixture::parse_file_field(object)?;
+                }
+                _ => self.stdout = stdout.expect_bytes()?,
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_fixtures(&mut self, test_directory: &Path) -> R<()> {
+        if let Some(stdout_file) = &self.stdout_file {
+            self.stdout = fixture::read_fixture(test_directory, stdout_file)?;
+        }
+        Ok(())
+    }
+
+    fn add_exec_error(&mut self, object: &Hash) -> R<()> {
+        let not_found = match object.expect_field("notFound") {
+            Ok(not_found) => not_found
+                .as_bool()
+                .ok_or_else(|| format!("expected: boolean, got: {:?}", not_found))?,
+            Err(_) => false,
+        };
+        match (not_found, object.expect_field("execError")) {
+            (true, Ok(_)) => Err("'notFound' and 'execError' can't be used together")?,
+            (true, Err(_)) => self.exec_error = Some(ExecError::not_found()),
+            (false, Ok(exec_error)) => {
+                self.exec_error = Some(ExecError::parse(exec_error.expect_str()?)?)
+            }
+            (false, Err(_)) => {}
         }
         Ok(())
     }
@@ -57,11 +121,29 @@ impl Step {
     fn parse(yaml: &Yaml) -> R<Step> {
         match yaml {
             Yaml::String(string) => Step::from_string(string),
+            yaml if yaml.is_binary() => Ok(Step::new(CommandMatcher::ExactMatch(
+                Command::from_bytes(&yaml.expect_bytes()?)?,
+            ))),
             Yaml::Hash(object) => {
-                check_keys(&["command", "stdout", "exitcode", "regex"], object)?;
+                check_keys(
+                    &[
+                        "command",
+                        "stdout",
+                        "exitcode",
+                        "regex",
+                        "notFound",
+                        "execError",
+                    ],
+                    object,
+                )?;
                 let mut step = match (object.expect_field("command"), object.expect_field("regex"))
                 {
-                    (Ok(command_field), Err(_)) => Step::from_string(command_field.expect_str()?)?,
+                    (Ok(Yaml::Array(words)), Err(_)) => Step::new(CommandMatcher::ExactMatch(
+                        Command::from_words(Command::parse_words(words)?)?,
+                    )),
+                    (Ok(command_field), Err(_)) => Step::new(CommandMatcher::ExactMatch(
+                        Command::from_bytes(&command_field.expect_bytes()?)?,
+                    )),
                     (Err(_), Ok(regex_field)) => Step::new(CommandMatcher::RegexMatch(
                         AnchoredRegex::new(regex_field.expect_str()?)?,
                     )),
@@ -69,6 +151,7 @@ impl Step {
                 };
                 step.add_stdout(object)?;
                 step.add_exitcode(object)?;
+                step.add_exec_error(object)?;
                 Ok(step)
             }
             _ => Err(format!("expected: string or array, got: {:?}", yaml))?,
@@ -76,16 +159,47 @@ impl Step {
     }
 
     fn serialize(&self) -> Yaml {
-        let command = Yaml::String(self.command_matcher.format());
-        if self.exitcode == 0 {
+        let command = self.command_matcher.serialize();
+        if command.as_str().is_some()
+            && self.stdout.is_empty()
+            && self.stdout_file.is_none()
+            && self.exitcode == 0
+            && self.exec_error.is_none()
+        {
             command
         } else {
             let mut step = LinkedHashMap::new();
             step.insert(Yaml::from_str("command"), command);
-            step.insert(
-                Yaml::from_str("exitcode"),
-                Yaml::Integer(i64::from(self.exitcode)),
-            );
+            match &self.stdout_file {
+                Some(stdout_file) => {
+                    step.insert(
+                        Yaml::from_str("stdout"),
+                        fixture::serialize_file_field(stdout_file),
+                    );
+                }
+                None if !self.stdout.is_empty() => {
+                    step.insert(Yaml::from_str("stdout"), serialize_bytes(&self.stdout));
+                }
+                None => {}
+            }
+            if self.exitcode != 0 {
+                step.insert(
+                    Yaml::from_str("exitcode"),
+                    Yaml::Integer(i64::from(self.exitcode)),
+                );
+            }
+            match self.exec_error {
+                Some(exec_error) if exec_error == ExecError::not_found() => {
+                    step.insert(Yaml::from_str("notFound"), Yaml::Boolean(true));
+                }
+                Some(exec_error) => {
+                    step.insert(
+                        Yaml::from_str("execError"),
+                        Yaml::from_str(exec_error.name()),
+                    );
+                }
+                None => {}
+            }
             Yaml::Hash(step)
         }
     }
@@ -94,8 +208,10 @@ impl Step {
 #[cfg(test)]
 mod parse_step {
     use super::*;
+    use std::os::unix::ffi::OsStringExt;
     use test_utils::assert_error;
     use yaml_rust::Yaml;
+    use yaml_rust::YamlLoader;
 
     fn test_parse_step(yaml: &str) -> R<Step> {
         let yaml = YamlLoader::load_from_str(yaml)?;
@@ -152,6 +268,26 @@ mod parse_step {
         Ok(())
     }
 
+    #[test]
+    fn allows_lists_in_the_command_field() -> R<()> {
+        assert_eq!(
+            test_parse_step(r#"{command: [foo, "bar baz", ""]}"#)?.command_matcher,
+            CommandMatcher::ExactMatch(Command {
+                executable: PathBuf::from("foo"),
+                arguments: vec![OsString::from("bar baz"), OsString::from("")],
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_empty_lists_in_the_command_field() {
+        assert_error!(
+            test_parse_step("{command: []}"),
+            "expected: non-empty list of executable and arguments"
+        );
+    }
+
     #[test]
     fn gives_nice_parse_errors() {
         assert_error!(
@@ -169,6 +305,71 @@ mod parse_step {
         Ok(())
     }
 
+    #[test]
+    fn allows_to_specify_binary_stdout() -> R<()> {
+        assert_eq!(
+            test_parse_step(r#"{command: "foo", stdout: {base64: //4A}}"#)?.stdout,
+            b"\xff\xfe\x00".to_vec(),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_specify_binary_commands() -> R<()> {
+        assert_eq!(
+            test_parse_step(r#"{command: {base64: Zm9vIOk=}}"#)?.command_matcher,
+            CommandMatcher::ExactMatch(Command {
+                executable: PathBuf::from("foo"),
+                arguments: vec![OsString::from_vec(b"\xe9".to_vec())],
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_specify_stdout_fixture_files() -> R<()> {
+        let step = test_parse_step(r#"{command: "foo", stdout: {file: output.json}}"#)?;
+        assert_eq!(step.stdout_file, Some(PathBuf::from("output.json")));
+        assert_eq!(step.stdout, b"".to_vec());
+        Ok(())
+    }
+
+    mod exec_error {
+        use super::*;
+
+        #[test]
+        fn allows_to_mock_commands_that_are_not_found() -> R<()> {
+            assert_eq!(
+                test_parse_step(r#"{command: "foo", notFound: true}"#)?.exec_error,
+                Some(ExecError::not_found())
+            );
+            assert_eq!(
+                test_parse_step(r#"{command: "foo", notFound: false}"#)?.exec_error,
+                None
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn allows_to_specify_execve_errors() -> R<()> {
+            assert_eq!(
+                test_parse_step(r#"{command: "foo", execError: EACCES}"#)?.exec_error,
+                Some(ExecError {
+                    errno: libc::EACCES
+                })
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_not_found_together_with_exec_errors() {
+            assert_error!(
+                test_parse_step(r#"{command: "foo", notFound: true, execError: EACCES}"#),
+                "'notFound' and 'execError' can't be used together"
+            );
+        }
+    }
+
     mod exitcode {
         use super::*;
 
@@ -191,15 +392,32 @@ mod parse_step {
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Test {
+    pub name: Option<String>,
+    pub skip: bool,
+    pub only: bool,
     pub steps: VecDeque<Step>,
     pub ends_with_hole: bool,
-    pub arguments: Vec<String>,
+    pub arguments: Vec<OsString>,
     pub env: HashMap<String, String>,
+    pub inherit_env: Option<InheritEnv>,
+    pub unset_env: Vec<String>,
     pub cwd: Option<PathBuf>,
-    pub stdout: Option<Vec<u8>>,
-    pub stderr: Option<Vec<u8>>,
+    pub stdin: Option<Stdin>,
+    pub interaction: Vec<Interaction>,
+    pub tty: Option<Tty>,
+    pub stdout: Option<OutputMatcher>,
+    pub stderr: Option<OutputMatcher>,
+    pub strip_ansi: bool,
     pub exitcode: Option<i32>,
-    pub mocked_files: Vec<PathBuf>,
+    pub mocked_files: Vec<MockedFile>,
+    pub expected_file_changes: Option<Vec<ExpectedFileChange>>,
+    pub allowed_destinations: Vec<Destination>,
+    pub network: Vec<NetworkMapping>,
+    pub uid: Option<u32>,
+    pub euid: Option<u32>,
+    pub gid: Option<u32>,
+    pub groups: Option<Vec<u32>>,
+    pub uname: Option<Uname>,
 }
 
 impl Test {
@@ -210,15 +428,32 @@ impl Test {
 
     pub fn new(steps: Vec<Step>) -> Test {
         Test {
+            name: None,
+            skip: false,
+            only: false,
             steps: steps.into(),
             ends_with_hole: false,
             arguments: vec![],
             env: HashMap::new(),
+            inherit_env: None,
+            unset_env: vec![],
             cwd: None,
+            stdin: None,
+            interaction: vec![],
+            tty: None,
             stdout: None,
             stderr: None,
+            strip_ansi: false,
             exitcode: None,
             mocked_files: vec![],
+            expected_file_changes: None,
+            allowed_destinations: vec![],
+            network: vec![],
+            uid: None,
+            euid: None,
+            gid: None,
+            groups: None,
+            uname: None,
         }
     }
 
@@ -250,9 +485,45 @@ impl Test {
         Ok(test)
     }
 
+    fn add_name(&mut self, object: &Hash) -> R<()> {
+        if let Ok(name) = object.expect_field("name") {
+            self.name = Some(name.expect_str()?.to_string());
+        }
+        Ok(())
+    }
+
+    fn add_skip_and_only(&mut self, object: &Hash) -> R<()> {
+        fn parse_bool(yaml: &Yaml) -> R<bool> {
+            Ok(yaml
+                .as_bool()
+                .ok_or_else(|| format!("expected: boolean, got: {:?}", yaml))?)
+        }
+        if let Ok(skip) = object.expect_field("skip") {
+            self.skip = parse_bool(skip)?;
+        }
+        if let Ok(only) = object.expect_field("only") {
+            self.only = parse_bool(only)?;
+        }
+        if self.skip && self.only {
+            Err("'skip' and 'only' can't be used together")?;
+        }
+        Ok(())
+    }
+
+    pub fn matches_filter(&self, filter: Option<&Regex>) -> bool {
+        match (filter, &self.name) {
+            (None, _) => true,
+            (Some(filter), Some(name)) => filter.is_match(name),
+            (Some(_), None) => false,
+        }
+    }
+
     fn add_arguments(&mut self, object: &Hash) -> R<()> {
         if let Ok(arguments) = object.expect_field("arguments") {
-            self.arguments = Parser::parse_arguments(arguments.expect_str()?)?;
+            self.arguments = match arguments {
+                Yaml::Array(words) => Command::parse_words(words)?,
+                _ => Parser::parse_arguments(&arguments.expect_bytes()?)?,
+            };
         }
         Ok(())
     }
@@ -269,6 +540,16 @@ impl Test {
         Ok(())
     }
 
+    fn add_inherit_env(&mut self, object: &Hash) -> R<()> {
+        if let Ok(inherit_env) = object.expect_field("inheritEnv") {
+            self.inherit_env = Some(InheritEnv::parse(inherit_env)?);
+        }
+        if let Ok(unset_env) = object.expect_field("unsetEnv") {
+            self.unset_env = environment::parse_variable_names(unset_env)?;
+        }
+        Ok(())
+    }
+
     fn add_cwd(&mut self, object: &Hash) -> R<()> {
         if let Ok(cwd) = object.expect_field("cwd") {
             let cwd = cwd.expect_str()?;
@@ -283,16 +564,54 @@ impl Test {
         Ok(())
     }
 
+    fn add_stdin(&mut self, object: &Hash) -> R<()> {
+        if let Ok(stdin) = object.expect_field("stdin") {
+            self.stdin = Some(Stdin::parse(stdin)?);
+        }
+        Ok(())
+    }
+
+    fn add_interaction(&mut self, object: &Hash) -> R<()> {
+        if let Ok(interaction) = object.expect_field("interaction") {
+            if self.stdin.is_some() {
+                Err("'stdin' and 'interaction' can't be used together")?;
+            }
+            for entry in interaction.expect_array()? {
+                self.interaction.push(Interaction::parse(entry)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn add_tty(&mut self, object: &Hash) -> R<()> {
+        if let Ok(tty) = object.expect_field("tty") {
+            self.tty = Tty::parse(tty)?;
+        }
+        Ok(())
+    }
+
     fn add_stdout(&mut self, object: &Hash) -> R<()> {
         if let Ok(stdout) = object.expect_field("stdout") {
-            self.stdout = Some(stdout.expect_bytes()?);
+            self.stdout = Some(OutputMatcher::parse(stdout)?);
         }
         Ok(())
     }
 
     fn add_stderr(&mut self, object: &Hash) -> R<()> {
         if let Ok(stderr) = object.expect_field("stderr") {
-            self.stderr = Some(stderr.expect_bytes()?);
+            if self.tty.is_some() {
+                Err("'tty' and 'stderr' can't be used together")?;
+            }
+            self.stderr = Some(OutputMatcher::parse(stderr)?);
+        }
+        Ok(())
+    }
+
+    fn add_strip_ansi(&mut self, object: &Hash) -> R<()> {
+        if let Ok(strip_ansi) = object.expect_field("stripAnsi") {
+            self.strip_ansi = strip_ansi
+                .as_bool()
+                .ok_or_else(|| format!("expected: boolean, got: {:?}", strip_ansi))?;
         }
         Ok(())
     }
@@ -306,9 +625,75 @@ impl Test {
 
     fn add_mocked_files(&mut self, object: &Hash) -> R<()> {
         if let Ok(paths) = object.expect_field("mockedFiles") {
-            for path in paths.expect_array()?.iter() {
-                self.mocked_files.push(PathBuf::from(path.expect_str()?));
+            for mocked_file in paths.expect_array()?.iter() {
+                self.mocked_files.push(MockedFile::parse(mocked_file)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn add_expected_file_changes(&mut self, object: &Hash) -> R<()> {
+        if let Ok(changes) = object.expect_field("expectedFileChanges") {
+            let mut expected_file_changes = vec![];
+            for change in changes.expect_array()?.iter() {
+                expected_file_changes.push(ExpectedFileChange::parse(change)?);
+            }
+            self.expected_file_changes = Some(expected_file_changes);
+        }
+        Ok(())
+    }
+
+    fn add_allowed_destinations(&mut self, object: &Hash) -> R<()> {
+        if let Ok(destinations) = object.expect_field("allowedDestinations") {
+            for destination in destinations.expect_array()?.iter() {
+                self.allowed_destinations
+                    .push(Destination::parse(destination.expect_str()?)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn add_network(&mut self, object: &Hash) -> R<()> {
+        if let Ok(mappings) = object.expect_field("network") {
+            for mapping in mappings.expect_array()?.iter() {
+                self.network
+                    .push(NetworkMapping::parse(mapping.expect_str()?)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_id(yaml: &Yaml) -> R<u32> {
+        let id = yaml.expect_integer()?;
+        if id < 0 {
+            Err(format!("expected: non-negative integer, got: {}", id))?;
+        }
+        Ok(id as u32)
+    }
+
+    fn add_ids(&mut self, object: &Hash) -> R<()> {
+        if let Ok(uid) = object.expect_field("uid") {
+            self.uid = Some(Test::parse_id(uid)?);
+        }
+        if let Ok(euid) = object.expect_field("euid") {
+            self.euid = Some(Test::parse_id(euid)?);
+        }
+        if let Ok(gid) = object.expect_field("gid") {
+            self.gid = Some(Test::parse_id(gid)?);
+        }
+        if let Ok(groups) = object.expect_field("groups") {
+            let mut result = vec![];
+            for group in groups.expect_array()? {
+                result.push(Test::parse_id(group)?);
             }
+            self.groups = Some(result);
+        }
+        Ok(())
+    }
+
+    fn add_uname(&mut self, object: &Hash) -> R<()> {
+        if let Ok(uname) = object.expect_field("uname") {
+            self.uname = Some(Uname::parse(uname)?);
         }
         Ok(())
     }
@@ -316,28 +701,73 @@ impl Test {
     fn from_object(object: &Hash) -> R<Test> {
         check_keys(
             &[
+                "name",
+                "extends",
+                "skip",
+                "only",
+                "matrix",
                 "steps",
                 "mockedFiles",
                 "arguments",
                 "env",
+                "inheritEnv",
+                "unsetEnv",
                 "exitcode",
+                "stdin",
+                "interaction",
+                "tty",
                 "stdout",
                 "stderr",
+                "stripAnsi",
                 "cwd",
+                "expectedFileChanges",
+                "allowedDestinations",
+                "network",
+                "uid",
+                "euid",
+                "gid",
+                "groups",
+                "uname",
             ],
             object,
         )?;
         let mut test = Test::from_array(object.expect_field("steps")?.expect_array()?)?;
+        test.add_name(object)?;
+        test.add_skip_and_only(object)?;
         test.add_arguments(&object)?;
         test.add_env(&object)?;
+        test.add_inherit_env(object)?;
         test.add_cwd(&object)?;
+        test.add_stdin(object)?;
+        test.add_interaction(object)?;
+        test.add_tty(object)?;
         test.add_stdout(&object)?;
         test.add_stderr(&object)?;
+        test.add_strip_ansi(object)?;
         test.add_exitcode(&object)?;
         test.add_mocked_files(&object)?;
+        test.add_expected_file_changes(object)?;
+        test.add_allowed_destinations(object)?;
+        test.add_network(object)?;
+        test.add_ids(object)?;
+        test.add_uname(object)?;
         Ok(test)
     }
 
+    fn resolve_fixtures(&mut self, test_directory: &Path) -> R<()> {
+        self.stdin = self.stdin.take().map(|stdin| stdin.resolve(test_directory));
+        for step in self.steps.iter_mut() {
+            step.resolve_fixtures(test_directory)?;
+        }
+        if let Some(stdout) = &mut self.stdout {
+            stdout.resolve(test_directory)?;
+        }
+        if let Some(stderr) = &mut self.stderr {
+            stderr.resolve(test_directory)?;
+        }
+        Ok(())
+    }
+
     fn serialize_env(&self, object: &mut Hash) {
         if !self.env.is_empty() {
             let mut env = LinkedHashMap::new();
@@ -346,59 +776,259 @@ impl Test {
             }
             object.insert(Yaml::from_str("env"), Yaml::Hash(env));
         }
+        if let Some(inherit_env) = &self.inherit_env {
+            object.insert(Yaml::from_str("inheritEnv"), inherit_env.serialize());
+        }
+        if !self.unset_env.is_empty() {
+            object.insert(
+                Yaml::from_str("unsetEnv"),
+                environment::serialize_variable_names(&self.unset_env),
+            );
+        }
     }
 
-    fn serialize(&self) -> Yaml {
+    fn serialize_ids(&self, object: &mut Hash) {
+        let ids = [("uid", self.uid), ("euid", self.euid), ("gid", self.gid)];
+        for (key, id) in ids.iter() {
+            if let Some(id) = id {
+                object.insert(Yaml::from_str(key), Yaml::Integer(i64::from(*id)));
+            }
+        }
+        if let Some(groups) = &self.groups {
+            object.insert(
+                Yaml::from_str("groups"),
+                Yaml::Array(
+                    groups
+                        .iter()
+                        .map(|group| Yaml::Integer(i64::from(*group)))
+                        .collect(),
+                ),
+            );
+        }
+    }
+
+    fn serialize(&self) -> R<Yaml> {
         let mut test = LinkedHashMap::new();
+        if let Some(name) = &self.name {
+            test.insert(Yaml::from_str("name"), Yaml::String(name.clone()));
+        }
+        if self.skip {
+            test.insert(Yaml::from_str("skip"), Yaml::Boolean(true));
+        }
+        if self.only {
+            test.insert(Yaml::from_str("only"), Yaml::Boolean(true));
+        }
         if !self.arguments.is_empty() {
-            let arguments = self.arguments.iter().map(OsString::from).collect();
             test.insert(
                 Yaml::from_str("arguments"),
-                Yaml::String(Command::format_arguments(arguments)),
+                Command::serialize_arguments(&self.arguments),
             );
         }
         self.serialize_env(&mut test);
+        if let Some(cwd) = &self.cwd {
+            test.insert(
+                Yaml::from_str("cwd"),
+                Yaml::String(cwd.to_string_lossy().into_owned()),
+            );
+        }
         {
             let mut steps = vec![];
             for step in &self.steps {
                 steps.push(step.serialize());
             }
+            if self.ends_with_hole {
+                steps.push(Yaml::from_str("_"));
+            }
             test.insert(Yaml::from_str("steps"), Yaml::Array(steps));
         }
+        if let Some(stdin) = &self.stdin {
+            test.insert(Yaml::from_str("stdin"), stdin.serialize());
+        }
+        if !self.interaction.is_empty() {
+            test.insert(
+                Yaml::from_str("interaction"),
+                Yaml::Array(
+                    self.interaction
+                        .iter()
+                        .map(Interaction::serialize)
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(tty) = &self.tty {
+            test.insert(Yaml::from_str("tty"), tty.serialize());
+        }
+        if let Some(stdout) = &self.stdout {
+            test.insert(Yaml::from_str("stdout"), stdout.serialize());
+        }
+        if let Some(stderr) = &self.stderr {
+            test.insert(Yaml::from_str("stderr"), stderr.serialize());
+        }
+        if self.strip_ansi {
+            test.insert(Yaml::from_str("stripAnsi"), Yaml::Boolean(true));
+        }
         if let Some(exitcode) = self.exitcode {
             test.insert(
                 Yaml::from_str("exitcode"),
                 Yaml::Integer(i64::from(exitcode)),
             );
         }
-        Yaml::Hash(test)
+        if !self.mocked_files.is_empty() {
+            test.insert(
+                Yaml::from_str("mockedFiles"),
+                Yaml::Array(
+                    self.mocked_files
+                        .iter()
+                        .map(MockedFile::serialize)
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(expected_file_changes) = &self.expected_file_changes {
+            test.insert(
+                Yaml::from_str("expectedFileChanges"),
+                Yaml::Array(
+                    expected_file_changes
+                        .iter()
+                        .map(ExpectedFileChange::serialize)
+                        .collect(),
+                ),
+            );
+        }
+        if !self.allowed_destinations.is_empty() {
+            test.insert(
+                Yaml::from_str("allowedDestinations"),
+                Yaml::Array(
+                    self.allowed_destinations
+                        .iter()
+                        .map(|destination| Yaml::String(destination.to_string()))
+                        .collect(),
+                ),
+            );
+        }
+        if !self.network.is_empty() {
+            test.insert(
+                Yaml::from_str("network"),
+                Yaml::Array(
+                    self.network
+                        .iter()
+                        .map(|mapping| Yaml::String(mapping.to_string()))
+                        .collect(),
+                ),
+            );
+        }
+        self.serialize_ids(&mut test);
+        if let Some(uname) = &self.uname {
+            test.insert(Yaml::from_str("uname"), uname.serialize());
+        }
+        variables::escape_test(&mut test)?;
+        Ok(Yaml::Hash(test))
+    }
+}
+
+// Features that are expanded when loading a test file and that would therefore
+// get lost when rewriting it.
+fn expanded_features(yaml: &Yaml) -> Vec<&'static str> {
+    let tests: &[Yaml] = match yaml {
+        Yaml::Array(tests) => tests,
+        Yaml::Hash(object) => match object.get(&Yaml::from_str("tests")) {
+            Some(Yaml::Array(tests)) => tests,
+            _ => std::slice::from_ref(yaml),
+        },
+        _ => &[],
+    };
+    let tests: Vec<&Hash> = tests.iter().filter_map(Yaml::as_hash).collect();
+    let uses = |field: &str| {
+        tests
+            .iter()
+            .any(|test| test.contains_key(&Yaml::from_str(field)))
+    };
+    let mut result = vec![];
+    if let Yaml::Hash(object) = yaml {
+        if object.contains_key(&Yaml::from_str("defaults")) {
+            result.push("defaults");
+        }
+    }
+    if uses("extends") {
+        result.push("extends");
+    }
+    if uses("matrix") {
+        result.push("matrix");
+    }
+    let uses_includes = tests
+        .iter()
+        .any(|test| match test.get(&Yaml::from_str("steps")) {
+            Some(Yaml::Array(steps)) => steps.iter().any(include::is_include),
+            _ => false,
+        });
+    if uses_includes {
+        result.push("include");
     }
+    if tests
+        .iter()
+        .any(|test| variables::references_variables(test))
+    {
+        result.push("${...}");
+    }
+    result
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Tests {
     pub tests: Vec<Test>,
+    pub expanded_features: Vec<&'static str>,
     pub unmocked_commands: Vec<PathBuf>,
+    pub mocked_executables: Vec<String>,
+    pub executable_matching: ExecutableMatching,
+    pub inherit_env: Option<InheritEnv>,
+    pub unset_env: Vec<String>,
+    pub record_output: bool,
+    pub output_fixture_lines: Option<usize>,
     pub interpreter: Option<PathBuf>,
+    pub safe: bool,
+    pub allowed_writes: Vec<PathBuf>,
 }
 
 impl Tests {
     pub fn new(tests: Vec<Test>) -> Tests {
         Tests {
             tests,
+            expanded_features: vec![],
             unmocked_commands: vec![],
+            mocked_executables: vec![],
+            executable_matching: ExecutableMatching::Path,
+            inherit_env: None,
+            unset_env: vec![],
+            record_output: false,
+            output_fixture_lines: None,
             interpreter: None,
+            safe: false,
+            allowed_writes: vec![],
         }
     }
 
-    fn from_array(array: &[Yaml]) -> R<Tests> {
+    fn from_array(array: &[Yaml], defaults: Option<&Hash>, variables: &Variables) -> R<Tests> {
         let mut result = vec![];
-        for element in array.iter() {
-            result.push(Test::from_object(element.expect_object()?)?);
+        for object in inheritance::resolve_tests(array, defaults)? {
+            for mut object in matrix::expand_matrix(object)? {
+                variables.expand_test(&mut object)?;
+                result.push(Test::from_object(&object)?);
+            }
         }
         Ok(Tests::new(result))
     }
 
+    fn parse_defaults(object: &Hash) -> R<Option<&Hash>> {
+        Ok(match object.expect_field("defaults") {
+            Ok(defaults) => {
+                let defaults = defaults.expect_object()?;
+                inheritance::check_defaults(defaults)?;
+                Some(defaults)
+            }
+            Err(_) => None,
+        })
+    }
+
     fn add_interpreter(&mut self, object: &Hash) -> R<()> {
         if let Ok(interpreter) = object.expect_field("interpreter") {
             self.interpreter = Some(PathBuf::from(interpreter.expect_str()?));
@@ -416,51 +1046,175 @@ impl Tests {
         Ok(())
     }
 
-    fn parse(yaml: Yaml) -> R<Tests> {
-        Ok(match &yaml {
-            Yaml::Array(array) => Tests::from_array(&array)?,
-            Yaml::Hash(object) => {
-                match (object.expect_field("tests"), object.expect_field("steps")) {
-                    (Ok(tests), _) => {
-                        check_keys(&["tests", "interpreter", "unmockedCommands"], object)?;
-                        let mut tests = Tests::from_array(tests.expect_array()?)?;
-                        tests.add_unmocked_commands(object)?;
-                        tests.add_interpreter(object)?;
-                        tests
-                    }
-                    (Err(_), Ok(_)) => Tests::new(vec![Test::from_object(&object)?]),
-                    (Err(_), Err(_)) => Err(format!(
-                        "expected top-level field \"steps\" or \"tests\", got: {:?}",
-                        &yaml
-                    ))?,
+    fn add_mocked_executables(&mut self, object: &Hash) -> R<()> {
+        if let Ok(mocked_executables) = object.expect_field("mockedExecutables") {
+            for mocked_executable in mocked_executables.expect_array()? {
+                let mocked_executable = mocked_executable.expect_str()?;
+                if mocked_executable.is_empty() || mocked_executable.contains('/') {
+                    Err(format!(
+                        "mockedExecutables have to be executable names without slashes, got: {:?}",
+                        mocked_executable
+                    ))?;
                 }
+                self.mocked_executables.push(mocked_executable.to_string());
             }
-            _ => Err(format!("expected: array or object, got: {:?}", &yaml))?,
-        })
+        }
+        Ok(())
     }
 
-    pub fn load(executable_path: &Path) -> R<(PathBuf, Tests)> {
-        let test_file = find_test_file(executable_path);
-        let file_contents = read_test_file(&test_file)?;
-        let yaml: Vec<Yaml> = YamlLoader::load_from_str(&file_contents).map_err(|error| {
-            format!("invalid YAML in {}: {}", test_file.to_string_lossy(), error)
-        })?;
-        let yaml: Yaml = {
-            if yaml.len() > 1 {
-                Err(format!(
-                    "multiple YAML documents not allowed (in {})",
-                    test_file.to_string_lossy()
-                ))?;
-            }
-            yaml.into_iter()
-                .next()
+    fn add_executable_matching(&mut self, object: &Hash) -> R<()> {
+        if let Ok(executable_matching) = object.expect_field("executableMatching") {
+            self.executable_matching = ExecutableMatching::parse(executable_matching)?;
+        }
+        Ok(())
+    }
+
+    fn add_inherit_env(&mut self, object: &Hash) -> R<()> {
+        if let Ok(inherit_env) = object.expect_field("inheritEnv") {
+            self.inherit_env = Some(InheritEnv::parse(inherit_env)?);
+        }
+        if let Ok(unset_env) = object.expect_field("unsetEnv") {
+            self.unset_env = environment::parse_variable_names(unset_env)?;
+        }
+        Ok(())
+    }
+
+    fn add_record_output(&mut self, object: &Hash) -> R<()> {
+        if let Ok(record_output) = object.expect_field("recordOutput") {
+            self.record_output = record_output
+                .as_bool()
+                .ok_or_else(|| format!("expected: boolean, got: {:?}", record_output))?;
+        }
+        if let Ok(lines) = object.expect_field("outputFixtureLines") {
+            let lines = lines.expect_integer()?;
+            if lines < 0 {
+                Err(format!("expected: non-negative integer, got: {}", lines))?;
+            }
+            self.output_fixture_lines = Some(lines as usize);
+        }
+        Ok(())
+    }
+
+    fn add_safe(&mut self, object: &Hash) -> R<()> {
+        if let Ok(safe) = object.expect_field("safe") {
+            self.safe = safe
+                .as_bool()
+                .ok_or_else(|| format!("expected: boolean, got: {:?}", safe))?;
+        }
+        Ok(())
+    }
+
+    fn add_allowed_writes(&mut self, object: &Hash) -> R<()> {
+        if let Ok(allowed_writes) = object.expect_field("allowedWrites") {
+            for allowed_write in allowed_writes.expect_array()? {
+                let allowed_write = allowed_write.expect_str()?;
+                if !allowed_write.starts_with('/') {
+                    Err(format!(
+                        "allowedWrites have to be absolute paths starting with \"/\", got: {:?}",
+                        allowed_write
+                    ))?;
+                }
+                self.allowed_writes.push(PathBuf::from(allowed_write));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn parse(yaml: Yaml) -> R<Tests> {
+        Tests::parse_with_variables(yaml, &Variables::from_env())
+    }
+
+    fn parse_with_variables(yaml: Yaml, variables: &Variables) -> R<Tests> {
+        Ok(match &yaml {
+            Yaml::Array(array) => Tests::from_array(array, None, variables)?,
+            Yaml::Hash(object) => {
+                match (object.expect_field("tests"), object.expect_field("steps")) {
+                    (Ok(tests), _) => {
+                        check_keys(
+                            &[
+                                "tests",
+                                "defaults",
+                                "interpreter",
+                                "unmockedCommands",
+                                "mockedExecutables",
+                                "executableMatching",
+                                "inheritEnv",
+                                "unsetEnv",
+                                "recordOutput",
+                                "outputFixtureLines",
+                                "safe",
+                                "allowedWrites",
+                            ],
+                            object,
+                        )?;
+                        let mut tests = Tests::from_array(
+                            tests.expect_array()?,
+                            Tests::parse_defaults(object)?,
+                            variables,
+                        )?;
+                        tests.add_unmocked_commands(object)?;
+                        tests.add_mocked_executables(object)?;
+                        tests.add_executable_matching(object)?;
+                        tests.add_inherit_env(object)?;
+                        tests.add_record_output(object)?;
+                        tests.add_interpreter(object)?;
+                        tests.add_safe(object)?;
+                        tests.add_allowed_writes(object)?;
+                        tests
+                    }
+                    (Err(_), Ok(_)) => {
+                        Tests::from_array(std::slice::from_ref(&yaml), None, variables)?
+                    }
+                    (Err(_), Err(_)) => Err(format!(
+                        "expected top-level field \"steps\" or \"tests\", got: {:?}",
+                        &yaml
+                    ))?,
+                }
+            }
+            _ => Err(format!("expected: array or object, got: {:?}", &yaml))?,
+        })
+    }
+
+    pub fn load(executable_path: &Path) -> R<(PathBuf, Tests)> {
+        let test_file = find_test_file(executable_path);
+        let file_contents = read_test_file(&test_file)?;
+        let yaml: Vec<Yaml> = load_yaml(&file_contents).map_err(|error| {
+            format!("invalid YAML in {}: {}", test_file.to_string_lossy(), error)
+        })?;
+        let mut yaml: Yaml = {
+            if yaml.len() > 1 {
+                Err(format!(
+                    "multiple YAML documents not allowed (in {})",
+                    test_file.to_string_lossy()
+                ))?;
+            }
+            yaml.into_iter()
+                .next()
                 .ok_or_else(|| format!("no YAML documents (in {})", test_file.to_string_lossy()))?
         };
-        Ok((
-            test_file.clone(),
-            Tests::parse(yaml)
-                .map_err(|error| format!("error in {}: {}", test_file.to_string_lossy(), error))?,
-        ))
+        let expanded_features = expanded_features(&yaml);
+        if let Some(test_directory) = test_file.parent() {
+            include::resolve_includes(&mut yaml, test_directory)
+                .map_err(|error| format!("error in {}: {}", test_file.to_string_lossy(), error))?;
+        }
+        let variables = Variables::new(executable_path, &test_file)?;
+        let mut tests = Tests::parse_with_variables(yaml, &variables)
+            .map_err(|error| format!("error in {}: {}", test_file.to_string_lossy(), error))?;
+        tests.expanded_features = expanded_features;
+        if let Some(test_directory) = test_file.parent() {
+            tests
+                .resolve_fixtures(test_directory)
+                .map_err(|error| format!("error in {}: {}", test_file.to_string_lossy(), error))?;
+        }
+        Ok((test_file.clone(), tests))
+    }
+
+    fn resolve_fixtures(&mut self, test_directory: &Path) -> R<()> {
+        for test in self.tests.iter_mut() {
+            test.resolve_fixtures(test_directory)?;
+        }
+        Ok(())
     }
 
     fn serialize_unmocked_commands(&self, object: &mut Hash) -> R<()> {
@@ -480,13 +1234,117 @@ impl Tests {
         Ok(())
     }
 
+    fn serialize_mocked_executables(&self, object: &mut Hash) {
+        if !self.mocked_executables.is_empty() {
+            object.insert(
+                Yaml::from_str("mockedExecutables"),
+                Yaml::Array(
+                    self.mocked_executables
+                        .iter()
+                        .map(|mocked_executable| Yaml::from_str(mocked_executable))
+                        .collect(),
+                ),
+            );
+        }
+    }
+
+    fn serialize_inherit_env(&self, object: &mut Hash) {
+        if let Some(inherit_env) = &self.inherit_env {
+            object.insert(Yaml::from_str("inheritEnv"), inherit_env.serialize());
+        }
+        if !self.unset_env.is_empty() {
+            object.insert(
+                Yaml::from_str("unsetEnv"),
+                environment::serialize_variable_names(&self.unset_env),
+            );
+        }
+    }
+
+    fn serialize_record_output(&self, object: &mut Hash) {
+        if self.record_output {
+            object.insert(Yaml::from_str("recordOutput"), Yaml::Boolean(true));
+        }
+        if let Some(lines) = self.output_fixture_lines {
+            object.insert(
+                Yaml::from_str("outputFixtureLines"),
+                Yaml::Integer(lines as i64),
+            );
+        }
+    }
+
+    fn inherit_env<'a>(&'a self, test: &'a Test) -> Option<&'a InheritEnv> {
+        test.inherit_env.as_ref().or(self.inherit_env.as_ref())
+    }
+
+    pub fn effective_env<I: Iterator<Item = (OsString, OsString)>>(
+        &self,
+        parent_env: impl FnOnce() -> I,
+        test: &Test,
+    ) -> HashMap<OsString, OsString> {
+        let unset_env: Vec<String> = self
+            .unset_env
+            .iter()
+            .chain(test.unset_env.iter())
+            .cloned()
+            .collect();
+        environment::effective_env(parent_env, self.inherit_env(test), &test.env, &unset_env)
+    }
+
+    pub fn shown_env<I: Iterator<Item = (OsString, OsString)>>(
+        &self,
+        parent_env: impl FnOnce() -> I,
+        test: &Test,
+    ) -> HashMap<OsString, OsString> {
+        environment::shown_env(
+            self.effective_env(parent_env, test),
+            self.inherit_env(test),
+            &test.env,
+        )
+    }
+
+    pub fn selected(&self, filter: Option<&Regex>) -> Vec<bool> {
+        let has_only = self.tests.iter().any(|test| test.only);
+        self.tests
+            .iter()
+            .map(|test| !test.skip && (test.only || !has_only) && test.matches_filter(filter))
+            .collect()
+    }
+
+    fn serialize_sandbox(&self, object: &mut Hash) -> R<()> {
+        if self.safe {
+            object.insert(Yaml::from_str("safe"), Yaml::Boolean(true));
+        }
+        if !self.allowed_writes.is_empty() {
+            object.insert(
+                Yaml::from_str("allowedWrites"),
+                Yaml::Array(
+                    self.allowed_writes
+                        .iter()
+                        .map(|path| Ok(Yaml::String(path_to_string(path)?.to_string())))
+                        .collect::<R<Vec<Yaml>>>()?,
+                ),
+            );
+        }
+        Ok(())
+    }
+
     pub fn serialize(&self) -> R<Yaml> {
         let mut object = LinkedHashMap::new();
         self.serialize_unmocked_commands(&mut object)?;
+        self.serialize_mocked_executables(&mut object);
+        self.serialize_inherit_env(&mut object);
+        self.serialize_record_output(&mut object);
+        if self.executable_matching != ExecutableMatching::Path {
+            object.insert(
+                Yaml::from_str("executableMatching"),
+                Yaml::from_str(self.executable_matching.format()),
+            );
+        }
+        self.serialize_sandbox(&mut object)?;
         {
             let mut tests = vec![];
             for test in self.tests.iter() {
-                tests.push(test.serialize());
+                tests.push(test.serialize()?);
             }
             object.insert(Yaml::from_str("tests"), Yaml::Array(tests));
         }
@@ -499,9 +1357,11 @@ mod load {
     use super::*;
     use crate::R;
     use pretty_assertions::assert_eq;
+    use std::os::unix::ffi::OsStringExt;
     use std::path::PathBuf;
     use std::*;
     use test_utils::{assert_error, trim_margin, Mappable, TempFile};
+    use yaml_rust::YamlLoader;
 
     fn test_parse(tempfile: &TempFile, tests_string: &str) -> R<Tests> {
         let test_file = tempfile.path().with_extension("test.yaml");
@@ -541,6 +1401,98 @@ mod load {
         );
     }
 
+    #[test]
+    fn resolves_stdin_fixtures_relative_to_the_test_file() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let tests = test_parse(
+            &tempfile,
+            r"
+                |steps: []
+                |stdin:
+                |  file: input.json
+            ",
+        )?;
+        assert_eq!(
+            tests.tests[0].stdin,
+            Some(Stdin::File {
+                file: PathBuf::from("input.json"),
+                path: tempfile.path().parent().unwrap().join("input.json"),
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reads_binary_values() -> R<()> {
+        let test = test_parse_one(
+            r"
+                |arguments: !!binary LXYg/w==
+                |steps:
+                |  - command: foo
+                |    stdout: !!binary H4sIAA==
+                |stdin: !!binary //4A
+            ",
+        )?;
+        assert_eq!(
+            test.arguments,
+            vec![OsString::from("-v"), OsString::from_vec(b"\xff".to_vec())]
+        );
+        assert_eq!(test.steps[0].stdout, b"\x1f\x8b\x08\x00".to_vec());
+        assert_eq!(test.stdin, Some(Stdin::Bytes(b"\xff\xfe\x00".to_vec())));
+        Ok(())
+    }
+
+    #[test]
+    fn reads_output_fixtures_relative_to_the_test_file() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let directory = tempfile.path().parent().unwrap().to_path_buf();
+        fs::create_dir(directory.join("fixtures"))?;
+        fs::write(directory.join("fixtures/mocked"), "mocked output")?;
+        fs::write(directory.join("fixtures/expected"), "expected output")?;
+        let tests = test_parse(
+            &tempfile,
+            r"
+                |steps:
+                |  - command: foo
+                |    stdout:
+                |      file: fixtures/mocked
+                |stdout:
+                |  file: fixtures/expected
+                |stderr:
+                |  file: fixtures/expected
+            ",
+        )?;
+        let test = &tests.tests[0];
+        assert_eq!(test.steps[0].stdout, b"mocked output".to_vec());
+        assert_eq!(
+            test.stdout.as_ref().unwrap().equals,
+            Some(b"expected output".to_vec())
+        );
+        assert_eq!(
+            test.stderr.as_ref().unwrap().equals,
+            Some(b"expected output".to_vec())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_enable_output_recording() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let tests = test_parse(
+            &tempfile,
+            r"
+                |tests: []
+                |recordOutput: true
+                |outputFixtureLines: 20
+            ",
+        )?;
+        assert_eq!(
+            (tests.record_output, tests.output_fixture_lines),
+            (true, Some(20))
+        );
+        Ok(())
+    }
+
     mod invalid_fields {
         use super::*;
         use pretty_assertions::assert_eq;
@@ -561,7 +1513,9 @@ mod load {
                 format!(
                     "error in {}.test.yaml: \
                      unexpected field 'foo', \
-                     possible values: 'tests', 'interpreter', 'unmockedCommands'",
+                     possible values: 'tests', 'defaults', 'interpreter', 'unmockedCommands', \
+                     'mockedExecutables', 'executableMatching', 'inheritEnv', 'unsetEnv', \
+                     'recordOutput', 'outputFixtureLines', 'safe', 'allowedWrites'",
                     path_to_string(&tempfile.path())?
                 )
             );
@@ -585,8 +1539,10 @@ mod load {
                     "error in {}.test.yaml: \
                      unexpected field 'foo', \
                      possible values: \
-                     'steps', 'mockedFiles', 'arguments', 'env', \
-                     'exitcode', 'stdout', 'stderr', 'cwd'",
+                     'name', 'extends', 'skip', 'only', 'matrix', 'steps', 'mockedFiles', \
+                     'arguments', 'env', 'inheritEnv', 'unsetEnv', 'exitcode', 'stdin', 'interaction', 'tty', 'stdout', 'stderr', \
+                     'stripAnsi', 'cwd', 'expectedFileChanges', 'allowedDestinations', \
+                     'network', 'uid', 'euid', 'gid', 'groups', 'uname'",
                     path_to_string(&tempfile.path())?
                 )
             );
@@ -609,7 +1565,8 @@ mod load {
                 format!(
                     "error in {}.test.yaml: \
                      unexpected field 'foo', \
-                     possible values: 'command', 'stdout', 'exitcode', 'regex'",
+                     possible values: 'command', 'stdout', 'exitcode', 'regex', \
+                     'notFound', 'execError'",
                     path_to_string(&tempfile.path())?
                 )
             );
@@ -696,129 +1653,764 @@ mod load {
         Ok(())
     }
 
-    #[test]
-    fn allows_to_specify_multiple_tests() -> R<()> {
-        let tempfile = TempFile::new()?;
-        assert_eq!(
-            test_parse(
-                &tempfile,
-                r"
-                    |- arguments: foo
-                    |  steps: []
-                    |- arguments: bar
-                    |  steps: []
-                ",
-            )?
-            .tests
-            .map(|test| test.arguments),
-            vec![vec!["foo"], vec!["bar"]]
-        );
-        Ok(())
-    }
-
-    #[test]
-    fn disallows_multiple_yaml_documents() -> R<()> {
-        let tempfile = TempFile::new()?;
-        assert_error!(
-            test_parse(
-                &tempfile,
-                r"
-                    |steps: []
-                    |---
-                    |steps: []
-                ",
-            ),
-            format!(
-                "multiple YAML documents not allowed (in {}.test.yaml)",
-                path_to_string(&tempfile.path())?
-            )
-        );
-        Ok(())
-    }
-
-    #[test]
-    fn allows_to_specify_multiple_tests_as_an_object() -> R<()> {
-        let tempfile = TempFile::new()?;
-        assert_eq!(
-            test_parse(
-                &tempfile,
-                r"
-                    |tests:
-                    |  - arguments: foo
-                    |    steps: []
-                    |  - arguments: bar
-                    |    steps: []
-                ",
-            )?
-            .tests
-            .map(|test| test.arguments),
-            vec![vec!["foo"], vec!["bar"]]
-        );
-        Ok(())
-    }
-
-    #[test]
-    fn returns_a_nice_error_when_the_required_top_level_keys_are_missing() -> R<()> {
-        let tempfile = TempFile::new()?;
-        assert_error!(
-            test_parse(&tempfile, "{}"),
-            format!(
-                "error in {}.test.yaml: \
-                 expected top-level field \"steps\" or \"tests\", \
-                 got: Hash({{}})",
-                path_to_string(&tempfile.path())?
-            )
-        );
-        Ok(())
-    }
-
-    mod script_arguments {
+    mod inherit_env {
         use super::*;
         use pretty_assertions::assert_eq;
 
         #[test]
-        fn allows_arguments() -> R<()> {
+        fn allows_to_inherit_selected_variables() -> R<()> {
+            let test = test_parse_one(
+                r"
+                    |steps: []
+                    |inheritEnv: [PATH, HOME]
+                    |unsetEnv: [LANG]
+                ",
+            )?;
             assert_eq!(
-                test_parse_one(
-                    r"
-                        |steps:
-                        |  - /bin/true
-                        |arguments: foo bar
-                    "
-                )?
-                .arguments,
-                vec!["foo", "bar"]
+                test.inherit_env,
+                Some(InheritEnv::Variables(vec![
+                    "PATH".to_string(),
+                    "HOME".to_string()
+                ]))
             );
+            assert_eq!(test.unset_env, vec!["LANG".to_string()]);
             Ok(())
         }
 
         #[test]
-        fn allows_arguments_with_whitespace() -> R<()> {
+        fn allows_to_inherit_all_variables() -> R<()> {
             assert_eq!(
                 test_parse_one(
-                    r#"
-                        |steps:
-                        |  - /bin/true
-                        |arguments: foo "bar baz"
-                    "#
+                    r"
+                        |steps: []
+                        |inheritEnv: true
+                    ",
                 )?
-                .arguments,
-                vec!["foo", "bar baz"]
+                .inherit_env,
+                Some(InheritEnv::All)
             );
             Ok(())
         }
 
         #[test]
-        fn disallows_arguments_of_invalid_type() -> R<()> {
+        fn allows_to_inherit_variables_for_all_tests() -> R<()> {
             let tempfile = TempFile::new()?;
+            let tests = test_parse(
+                &tempfile,
+                r"
+                    |tests:
+                    |  - steps: []
+                    |inheritEnv: true
+                    |unsetEnv: [LANG]
+                ",
+            )?;
+            assert_eq!(tests.inherit_env, Some(InheritEnv::All));
+            assert_eq!(tests.unset_env, vec!["LANG".to_string()]);
+            Ok(())
+        }
+
+        #[test]
+        fn gives_nice_parse_errors() -> R<()> {
+            let yaml = YamlLoader::load_from_str(&trim_margin(
+                r"
+                    |tests: []
+                    |inheritEnv: PATH
+                ",
+            )?)?;
             assert_error!(
-                test_parse(
-                    &tempfile,
-                    r"
-                        |steps:
+                Tests::parse(yaml[0].clone()),
+                "expected: boolean or array of strings, got: String(\"PATH\")"
+            );
+            Ok(())
+        }
+
+        fn parent_env() -> impl Iterator<Item = (OsString, OsString)> {
+            vec![
+                (OsString::from("INHERITED"), OsString::from("inherited")),
+                (OsString::from("UNSET"), OsString::from("unset")),
+                (OsString::from("OTHER"), OsString::from("other")),
+            ]
+            .into_iter()
+        }
+
+        #[test]
+        fn test_settings_override_file_settings() -> R<()> {
+            let mut tests = Tests::new(vec![]);
+            tests.inherit_env = Some(InheritEnv::All);
+            tests.unset_env = vec!["UNSET".to_string()];
+            let mut test = Test::empty();
+            test.inherit_env = Some(InheritEnv::Variables(vec![
+                "INHERITED".to_string(),
+                "UNSET".to_string(),
+            ]));
+            assert_eq!(
+                tests
+                    .effective_env(parent_env, &test)
+                    .into_iter()
+                    .collect::<Vec<_>>(),
+                vec![(OsString::from("INHERITED"), OsString::from("inherited"))]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn only_shows_variables_that_are_set_or_listed_in_the_test_file() -> R<()> {
+            let mut tests = Tests::new(vec![]);
+            tests.inherit_env = Some(InheritEnv::All);
+            let mut test = Test::empty();
+            test.env.insert("FOO".to_string(), "foo".to_string());
+            let mut shown: Vec<_> = tests.shown_env(parent_env, &test).into_iter().collect();
+            shown.sort();
+            assert_eq!(shown, vec![(OsString::from("FOO"), OsString::from("foo"))]);
+            test.inherit_env = Some(InheritEnv::Variables(vec!["OTHER".to_string()]));
+            let mut shown: Vec<_> = tests.shown_env(parent_env, &test).into_iter().collect();
+            shown.sort();
+            assert_eq!(
+                shown,
+                vec![
+                    (OsString::from("FOO"), OsString::from("foo")),
+                    (OsString::from("OTHER"), OsString::from("other"))
+                ]
+            );
+            Ok(())
+        }
+    }
+
+    mod skip_and_only {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn parses_skip_and_only() -> R<()> {
+            let tempfile = TempFile::new()?;
+            let tests = test_parse(
+                &tempfile,
+                r"
+                    |tests:
+                    |  - steps: []
+                    |    skip: true
+                    |  - steps: []
+                    |    only: true
+                ",
+            )?;
+            assert_eq!(
+                tests
+                    .tests
+                    .iter()
+                    .map(|test| (test.skip, test.only))
+                    .collect::<Vec<_>>(),
+                vec![(true, false), (false, true)]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_skip_and_only_together() -> R<()> {
+            let yaml = YamlLoader::load_from_str(&trim_margin(
+                r"
+                    |steps: []
+                    |skip: true
+                    |only: true
+                ",
+            )?)?;
+            assert_error!(
+                Tests::parse(yaml[0].clone()),
+                "'skip' and 'only' can't be used together"
+            );
+            Ok(())
+        }
+    }
+
+    mod inheritance {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn merges_defaults_into_every_test() -> R<()> {
+            let tempfile = TempFile::new()?;
+            let tests = test_parse(
+                &tempfile,
+                r"
+                    |defaults:
+                    |  cwd: /foo
+                    |  env: {A: a}
+                    |  steps: [setup]
+                    |tests:
+                    |  - env: {B: b}
+                    |    steps: [foo]
+                    |  - cwd: /bar
+                ",
+            )?;
+            let mut expected_env = HashMap::new();
+            expected_env.insert("A".to_string(), "a".to_string());
+            expected_env.insert("B".to_string(), "b".to_string());
+            assert_eq!(tests.tests[0].env, expected_env);
+            assert_eq!(tests.tests[0].cwd, Some(PathBuf::from("/foo")));
+            assert_eq!(
+                tests.tests[0].steps,
+                vec![Step::from_string("setup")?, Step::from_string("foo")?]
+            );
+            assert_eq!(tests.tests[1].cwd, Some(PathBuf::from("/bar")));
+            assert_eq!(tests.tests[1].steps, vec![Step::from_string("setup")?]);
+            Ok(())
+        }
+
+        #[test]
+        fn allows_tests_to_extend_named_tests() -> R<()> {
+            let tempfile = TempFile::new()?;
+            let tests = test_parse(
+                &tempfile,
+                r"
+                    |tests:
+                    |  - name: base
+                    |    steps: [setup]
+                    |    exitcode: 1
+                    |  - name: derived
+                    |    extends: base
+                    |    steps: [foo]
+                ",
+            )?;
+            assert_eq!(tests.tests[1].name, Some("derived".to_string()));
+            assert_eq!(tests.tests[1].exitcode, Some(1));
+            assert_eq!(
+                tests.tests[1].steps,
+                vec![Step::from_string("setup")?, Step::from_string("foo")?]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_names_in_defaults() -> R<()> {
+            let yaml = YamlLoader::load_from_str(&trim_margin(
+                r"
+                    |defaults:
+                    |  name: foo
+                    |tests: []
+                ",
+            )?)?;
+            assert_error!(
+                Tests::parse(yaml[0].clone()),
+                "'name' is not allowed in 'defaults'"
+            );
+            Ok(())
+        }
+    }
+
+    mod variables {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn expands_variables_in_test_fields() -> R<()> {
+            let tempfile = TempFile::new()?;
+            let directory = path_to_string(tempfile.path().parent().unwrap())?.to_string();
+            let home = std::env::var("HOME")?;
+            let test = test_parse(
+                &tempfile,
+                r"
+                    |arguments: ${TEST_DIR}/input
+                    |env:
+                    |  DIR: ${SCRIPT_DIR}
+                    |cwd: ${HOME}
+                    |steps:
+                    |  - command: cp ${SCRIPT_DIR}/foo
+                    |    stdout: ${HOME}
+                    |stdout: $${HOME}
+                ",
+            )?
+            .tests
+            .remove(0);
+            assert_eq!(
+                test.arguments,
+                vec![OsString::from(format!("{}/input", directory))]
+            );
+            assert_eq!(test.env.get("DIR"), Some(&directory));
+            assert_eq!(test.cwd, Some(PathBuf::from(&home)));
+            assert_eq!(
+                get_exact(test.steps[0].clone()),
+                Command::new(&format!("cp {}/foo", directory))?
+            );
+            assert_eq!(test.steps[0].stdout, home.as_bytes().to_vec());
+            assert_eq!(test.stdout, Some(OutputMatcher::exact(b"${HOME}".to_vec())));
+            Ok(())
+        }
+
+        #[test]
+        fn expands_variables_after_matrix_parameters() -> R<()> {
+            let test = test_parse_one(
+                r"
+                    |matrix:
+                    |  HOME: [foo]
+                    |steps:
+                    |  - cp ${HOME} $${HOME}
+                ",
+            )?;
+            assert_eq!(test.steps, vec![Step::from_string("cp foo ${HOME}")?]);
+            Ok(())
+        }
+
+        #[test]
+        fn reports_undefined_variables() -> R<()> {
+            let tempfile = TempFile::new()?;
+            assert_error!(
+                test_parse(
+                    &tempfile,
+                    r"
+                        |steps:
+                        |  - cp ${SCRIPTKEEPER_UNDEFINED_VARIABLE}
+                    "
+                ),
+                format!(
+                    "error in {}.test.yaml: \
+                     undefined variable: ${{SCRIPTKEEPER_UNDEFINED_VARIABLE}}",
+                    tempfile.path().to_string_lossy()
+                )
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn allows_to_specify_multiple_tests() -> R<()> {
+        let tempfile = TempFile::new()?;
+        assert_eq!(
+            test_parse(
+                &tempfile,
+                r"
+                    |- arguments: foo
+                    |  steps: []
+                    |- arguments: bar
+                    |  steps: []
+                ",
+            )?
+            .tests
+            .map(|test| test.arguments),
+            vec![vec!["foo"], vec!["bar"]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_multiple_yaml_documents() -> R<()> {
+        let tempfile = TempFile::new()?;
+        assert_error!(
+            test_parse(
+                &tempfile,
+                r"
+                    |steps: []
+                    |---
+                    |steps: []
+                ",
+            ),
+            format!(
+                "multiple YAML documents not allowed (in {}.test.yaml)",
+                path_to_string(&tempfile.path())?
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_specify_multiple_tests_as_an_object() -> R<()> {
+        let tempfile = TempFile::new()?;
+        assert_eq!(
+            test_parse(
+                &tempfile,
+                r"
+                    |tests:
+                    |  - arguments: foo
+                    |    steps: []
+                    |  - arguments: bar
+                    |    steps: []
+                ",
+            )?
+            .tests
+            .map(|test| test.arguments),
+            vec![vec!["foo"], vec!["bar"]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn returns_a_nice_error_when_the_required_top_level_keys_are_missing() -> R<()> {
+        let tempfile = TempFile::new()?;
+        assert_error!(
+            test_parse(&tempfile, "{}"),
+            format!(
+                "error in {}.test.yaml: \
+                 expected top-level field \"steps\" or \"tests\", \
+                 got: Hash({{}})",
+                path_to_string(&tempfile.path())?
+            )
+        );
+        Ok(())
+    }
+
+    mod script_arguments {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_arguments() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r"
+                        |steps:
+                        |  - /bin/true
+                        |arguments: foo bar
+                    "
+                )?
+                .arguments,
+                vec!["foo", "bar"]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn allows_arguments_with_whitespace() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r#"
+                        |steps:
+                        |  - /bin/true
+                        |arguments: foo "bar baz"
+                    "#
+                )?
+                .arguments,
+                vec!["foo", "bar baz"]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn allows_arguments_as_a_list() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r#"
+                        |steps:
+                        |  - /bin/true
+                        |arguments: ["", '{"foo": "bar \"baz\""}', !!binary /w==]
+                    "#
+                )?
+                .arguments,
+                vec![
+                    OsString::from(""),
+                    OsString::from(r#"{"foo": "bar \"baz\""}"#),
+                    OsString::from_vec(b"\xff".to_vec())
+                ]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_arguments_of_invalid_type() -> R<()> {
+            let tempfile = TempFile::new()?;
+            assert_error!(
+                test_parse(
+                    &tempfile,
+                    r"
+                        |steps:
+                        |  - /bin/true
+                        |arguments: 42
+                    "
+                ),
+                format!(
+                    "error in {}.test.yaml: \
+                     expected: string, got: Integer(42)",
+                    path_to_string(&tempfile.path())?
+                )
+            );
+            Ok(())
+        }
+    }
+
+    mod working_directory {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_to_specify_the_working_directory() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r"
+                        |steps:
+                        |  - /bin/true
+                        |cwd: /foo
+                    "
+                )?
+                .cwd,
+                Some(PathBuf::from("/foo"))
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn none_is_the_default() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r"
+                        |steps:
+                        |  - /bin/true
+                    "
+                )?
+                .cwd,
+                None
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_relative_paths() -> R<()> {
+            let yaml = YamlLoader::load_from_str(&trim_margin(
+                r"
+                    |steps:
+                    |  - /bin/true
+                    |cwd: foo
+                ",
+            )?)?;
+            assert_error!(
+                Tests::parse(yaml[0].clone()),
+                "cwd has to be an absolute path starting with \"/\", got: \"foo\""
+            );
+            Ok(())
+        }
+    }
+
+    mod exitcode {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_to_specify_the_expected_exit_code() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r"
+                        |steps:
                         |  - /bin/true
-                        |arguments: 42
+                        |exitcode: 42
+                    "
+                )?
+                .exitcode,
+                Some(42)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn uses_none_as_the_default() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r"
+                        |steps:
+                        |  - /bin/true
+                    "
+                )?
+                .exitcode,
+                None
+            );
+            Ok(())
+        }
+    }
+
+    mod unmocked_commands {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_to_specify_unmocked_commands() -> R<()> {
+            let tempfile = TempFile::new()?;
+            assert_eq!(
+                test_parse(
+                    &tempfile,
+                    r"
+                        |tests:
+                        |  - steps: []
+                        |unmockedCommands:
+                        |  - foo
+                    "
+                )?
+                .unmocked_commands
+                .map(|path| path.to_string_lossy().to_string()),
+                vec!["foo"]
+            );
+            Ok(())
+        }
+    }
+
+    mod mocked_executables {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_to_specify_mocked_executables() -> R<()> {
+            let tempfile = TempFile::new()?;
+            assert_eq!(
+                test_parse(
+                    &tempfile,
+                    r"
+                        |tests:
+                        |  - steps: []
+                        |mockedExecutables:
+                        |  - kubectl
+                    "
+                )?
+                .mocked_executables,
+                vec!["kubectl"]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_paths() -> R<()> {
+            let yaml = YamlLoader::load_from_str(&trim_margin(
+                r"
+                    |tests: []
+                    |mockedExecutables:
+                    |  - /usr/bin/kubectl
+                ",
+            )?)?;
+            assert_error!(
+                Tests::parse(yaml[0].clone()),
+                "mockedExecutables have to be executable names without slashes, \
+                 got: \"/usr/bin/kubectl\""
+            );
+            Ok(())
+        }
+    }
+
+    mod executable_matching {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_to_match_executables_by_basename() -> R<()> {
+            let tempfile = TempFile::new()?;
+            assert_eq!(
+                test_parse(
+                    &tempfile,
+                    r"
+                        |tests:
+                        |  - steps: []
+                        |executableMatching: basename
                     "
+                )?
+                .executable_matching,
+                ExecutableMatching::Basename
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_unknown_matching_modes() -> R<()> {
+            let yaml = YamlLoader::load_from_str(&trim_margin(
+                r"
+                    |tests: []
+                    |executableMatching: fuzzy
+                ",
+            )?)?;
+            assert_error!(
+                Tests::parse(yaml[0].clone()),
+                "expected one of 'path', 'basename', got: String(\"fuzzy\")"
+            );
+            Ok(())
+        }
+    }
+
+    mod safe_mode {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_to_enable_safe_mode_with_allowed_writes() -> R<()> {
+            let tempfile = TempFile::new()?;
+            let tests = test_parse(
+                &tempfile,
+                r"
+                    |tests:
+                    |  - steps: []
+                    |safe: true
+                    |allowedWrites:
+                    |  - /tmp/out
+                ",
+            )?;
+            assert!(tests.safe);
+            assert_eq!(tests.allowed_writes, vec![PathBuf::from("/tmp/out")]);
+            Ok(())
+        }
+
+        #[test]
+        fn is_disabled_by_default() -> R<()> {
+            let tempfile = TempFile::new()?;
+            let tests = test_parse(
+                &tempfile,
+                r"
+                    |tests:
+                    |  - steps: []
+                ",
+            )?;
+            assert!(!tests.safe);
+            assert_eq!(tests.allowed_writes, Vec::<PathBuf>::new());
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_relative_allowed_writes() -> R<()> {
+            let tempfile = TempFile::new()?;
+            assert_error!(
+                test_parse(
+                    &tempfile,
+                    r"
+                        |tests:
+                        |  - steps: []
+                        |allowedWrites:
+                        |  - out
+                    ",
+                ),
+                format!(
+                    "error in {}.test.yaml: \
+                     allowedWrites have to be absolute paths starting with \"/\", got: \"out\"",
+                    path_to_string(&tempfile.path())?
+                )
+            );
+            Ok(())
+        }
+    }
+
+    mod specified_interpreter {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_a_specified_interpreter() -> R<()> {
+            let tempfile = TempFile::new()?;
+            assert_eq!(
+                test_parse(
+                    &tempfile,
+                    r"
+                        |tests:
+                        |  - steps: []
+                        |interpreter: /bin/bash
+                    ",
+                )?
+                .interpreter
+                .unwrap()
+                .to_string_lossy(),
+                "/bin/bash",
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_an_interpreter_with_an_invalid_type() -> R<()> {
+            let tempfile = TempFile::new()?;
+            assert_error!(
+                test_parse(
+                    &tempfile,
+                    r"
+                        |tests:
+                        |  - steps: []
+                        |interpreter: 42
+                    ",
                 ),
                 format!(
                     "error in {}.test.yaml: \
@@ -830,182 +2422,272 @@ mod load {
         }
     }
 
-    mod working_directory {
+    #[test]
+    fn allows_to_specify_mocked_files() -> R<()> {
+        assert_eq!(
+            test_parse_one(
+                r"
+                    |steps: []
+                    |mockedFiles:
+                    |  - /foo
+                "
+            )?
+            .mocked_files
+            .map(|file| file.path.to_string_lossy().to_string()),
+            vec![("/foo")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_specify_mocked_file_metadata() -> R<()> {
+        let mocked_file = test_parse_one(
+            r"
+                |steps: []
+                |mockedFiles:
+                |  - path: /foo
+                |    type: symlink
+                |    mode: '755'
+            ",
+        )?
+        .mocked_files
+        .remove(0);
+        assert_eq!(
+            (mocked_file.file_type, mocked_file.mode),
+            (FileType::Symlink, 0o755)
+        );
+        Ok(())
+    }
+
+    mod expected_file_changes {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn allows_to_specify_expected_file_changes() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r"
+                        |steps: []
+                        |expectedFileChanges:
+                        |  - path: lockfile
+                        |    operation: delete
+                    "
+                )?
+                .expected_file_changes,
+                Some(vec![ExpectedFileChange {
+                    change: FileChange::Delete(PathBuf::from("lockfile")),
+                    content: None,
+                }])
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn none_is_the_default() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r"
+                        |steps: []
+                    "
+                )?
+                .expected_file_changes,
+                None
+            );
+            Ok(())
+        }
+    }
+
+    mod allowed_destinations {
         use super::*;
         use pretty_assertions::assert_eq;
 
         #[test]
-        fn allows_to_specify_the_working_directory() -> R<()> {
+        fn allows_to_specify_allowed_destinations() -> R<()> {
             assert_eq!(
                 test_parse_one(
                     r"
-                        |steps:
-                        |  - /bin/true
-                        |cwd: /foo
+                        |steps: []
+                        |allowedDestinations:
+                        |  - 127.0.0.1:8080
+                        |  - localhost
                     "
                 )?
-                .cwd,
-                Some(PathBuf::from("/foo"))
+                .allowed_destinations,
+                vec![
+                    Destination {
+                        host: "127.0.0.1".to_string(),
+                        port: Some(8080),
+                    },
+                    Destination {
+                        host: "localhost".to_string(),
+                        port: None,
+                    },
+                ]
             );
             Ok(())
         }
+    }
+
+    mod network {
+        use super::*;
+        use pretty_assertions::assert_eq;
 
         #[test]
-        fn none_is_the_default() -> R<()> {
+        fn allows_to_specify_network_mappings() -> R<()> {
             assert_eq!(
                 test_parse_one(
                     r"
-                        |steps:
-                        |  - /bin/true
+                        |steps: []
+                        |network:
+                        |  - api.example.com:443 -> 127.0.0.1:8080
                     "
                 )?
-                .cwd,
-                None
+                .network,
+                vec![NetworkMapping::parse(
+                    "api.example.com:443 -> 127.0.0.1:8080"
+                )?]
             );
             Ok(())
         }
+    }
+
+    mod ids {
+        use super::*;
+        use pretty_assertions::assert_eq;
 
         #[test]
-        fn disallows_relative_paths() -> R<()> {
+        fn allows_to_specify_user_and_group_ids() -> R<()> {
+            let test = test_parse_one(
+                r"
+                    |steps: []
+                    |uid: 0
+                    |euid: 1000
+                    |gid: 100
+                    |groups: [100, 200]
+                ",
+            )?;
+            assert_eq!(
+                (test.uid, test.euid, test.gid, test.groups),
+                (Some(0), Some(1000), Some(100), Some(vec![100, 200]))
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn none_is_the_default() -> R<()> {
+            let test = test_parse_one(
+                r"
+                    |steps: []
+                ",
+            )?;
+            assert_eq!(
+                (test.uid, test.euid, test.gid, test.groups),
+                (None, None, None, None)
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_negative_ids() -> R<()> {
             let yaml = YamlLoader::load_from_str(&trim_margin(
                 r"
-                    |steps:
-                    |  - /bin/true
-                    |cwd: foo
+                    |steps: []
+                    |uid: -1
                 ",
             )?)?;
             assert_error!(
                 Tests::parse(yaml[0].clone()),
-                "cwd has to be an absolute path starting with \"/\", got: \"foo\""
+                "expected: non-negative integer, got: -1"
             );
             Ok(())
         }
     }
 
-    mod exitcode {
+    mod interaction {
         use super::*;
         use pretty_assertions::assert_eq;
 
         #[test]
-        fn allows_to_specify_the_expected_exit_code() -> R<()> {
+        fn allows_to_specify_a_list_of_prompts_and_answers() -> R<()> {
             assert_eq!(
                 test_parse_one(
-                    r"
-                        |steps:
-                        |  - /bin/true
-                        |exitcode: 42
-                    "
+                    r#"
+                        |steps: []
+                        |interaction:
+                        |  - expect: "Continue? "
+                        |    send: "y\n"
+                    "#
                 )?
-                .exitcode,
-                Some(42)
+                .interaction
+                .len(),
+                1
             );
             Ok(())
         }
 
         #[test]
-        fn uses_none_as_the_default() -> R<()> {
-            assert_eq!(
-                test_parse_one(
-                    r"
-                        |steps:
-                        |  - /bin/true
-                    "
-                )?
-                .exitcode,
-                None
+        fn disallows_combining_stdin_and_interaction() -> R<()> {
+            let yaml = YamlLoader::load_from_str(&trim_margin(
+                r"
+                    |steps: []
+                    |stdin: foo
+                    |interaction: []
+                ",
+            )?)?;
+            assert_error!(
+                Tests::parse(yaml[0].clone()),
+                "'stdin' and 'interaction' can't be used together"
             );
             Ok(())
         }
     }
 
-    mod unmocked_commands {
+    mod tty {
         use super::*;
         use pretty_assertions::assert_eq;
 
         #[test]
-        fn allows_to_specify_unmocked_commands() -> R<()> {
-            let tempfile = TempFile::new()?;
-            assert_eq!(
-                test_parse(
-                    &tempfile,
-                    r"
-                        |tests:
-                        |  - steps: []
-                        |unmockedCommands:
-                        |  - foo
-                    "
-                )?
-                .unmocked_commands
-                .map(|path| path.to_string_lossy().to_string()),
-                vec!["foo"]
+        fn disallows_combining_tty_and_stderr() -> R<()> {
+            let yaml = YamlLoader::load_from_str(&trim_margin(
+                r"
+                    |steps: []
+                    |tty: true
+                    |stderr: foo
+                ",
+            )?)?;
+            assert_error!(
+                Tests::parse(yaml[0].clone()),
+                "'tty' and 'stderr' can't be used together"
             );
             Ok(())
         }
     }
 
-    mod specified_interpreter {
+    mod uname {
         use super::*;
         use pretty_assertions::assert_eq;
 
         #[test]
-        fn allows_a_specified_interpreter() -> R<()> {
-            let tempfile = TempFile::new()?;
+        fn allows_to_specify_uname_fields() -> R<()> {
             assert_eq!(
-                test_parse(
-                    &tempfile,
+                test_parse_one(
                     r"
-                        |tests:
-                        |  - steps: []
-                        |interpreter: /bin/bash
-                    ",
+                        |steps: []
+                        |uname:
+                        |  nodename: prod-1
+                    "
                 )?
-                .interpreter
-                .unwrap()
-                .to_string_lossy(),
-                "/bin/bash",
-            );
-            Ok(())
-        }
-
-        #[test]
-        fn disallows_an_interpreter_with_an_invalid_type() -> R<()> {
-            let tempfile = TempFile::new()?;
-            assert_error!(
-                test_parse(
-                    &tempfile,
-                    r"
-                        |tests:
-                        |  - steps: []
-                        |interpreter: 42
-                    ",
-                ),
-                format!(
-                    "error in {}.test.yaml: \
-                     expected: string, got: Integer(42)",
-                    path_to_string(&tempfile.path())?
-                )
+                .uname,
+                Some(Uname {
+                    nodename: Some("prod-1".to_string()),
+                    ..Uname::default()
+                })
             );
             Ok(())
         }
     }
 
-    #[test]
-    fn allows_to_specify_mocked_files() -> R<()> {
-        assert_eq!(
-            test_parse_one(
-                r"
-                    |steps: []
-                    |mockedFiles:
-                    |  - /foo
-                "
-            )?
-            .mocked_files
-            .map(|path| path.to_string_lossy().to_string()),
-            vec![("/foo")]
-        );
-        Ok(())
-    }
-
     mod expected_stdout {
         use super::*;
         use pretty_assertions::assert_eq;
@@ -1019,9 +2701,27 @@ mod load {
                         |  stdout: foo
                     "
                 )?
-                .stdout
-                .map(|s| String::from_utf8(s).unwrap()),
-                Some("foo".to_string())
+                .stdout,
+                Some(OutputMatcher::exact(b"foo".to_vec()))
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn allows_to_specify_matcher_objects() -> R<()> {
+            assert_eq!(
+                test_parse_one(
+                    r"
+                        |- steps: []
+                        |  stdout:
+                        |    contains: foo
+                    "
+                )?
+                .stdout,
+                Some(OutputMatcher {
+                    contains: vec![b"foo".to_vec()],
+                    ..OutputMatcher::default()
+                })
             );
             Ok(())
         }
@@ -1054,9 +2754,8 @@ mod load {
                         |  stderr: foo
                     "
                 )?
-                .stderr
-                .map(|s| String::from_utf8(s).unwrap()),
-                Some("foo".to_string())
+                .stderr,
+                Some(OutputMatcher::exact(b"foo".to_vec()))
             );
             Ok(())
         }
@@ -1198,7 +2897,6 @@ mod load {
             );
             Ok(())
         }
-
     }
 }
 
@@ -1206,6 +2904,9 @@ mod load {
 mod serialize {
     use super::*;
     use pretty_assertions::assert_eq;
+    use std::os::unix::ffi::OsStringExt;
+    use test_utils::trim_margin;
+    use yaml_rust::YamlLoader;
 
     fn roundtrip(tests: Tests) -> R<()> {
         let yaml = tests.serialize()?;
@@ -1214,6 +2915,68 @@ mod serialize {
         Ok(())
     }
 
+    #[test]
+    fn roundtrips_all_test_fields() -> R<()> {
+        let yaml = YamlLoader::load_from_str(&trim_margin(
+            r"
+                |tests:
+                |  - name: everything
+                |    arguments: foo
+                |    env:
+                |      FOO: bar
+                |    steps:
+                |      - id -u
+                |      - _
+                |    stdin:
+                |      file: input.json
+                |    tty: {rows: 50, columns: 120}
+                |    cwd: /var/lib/foo
+                |    stdout: foo
+                |    stripAnsi: true
+                |    exitcode: 1
+                |    mockedFiles:
+                |      - /etc/foo
+                |      - /var/lib/foo/
+                |      - path: /etc/bar
+                |        type: symlink
+                |        mode: '700'
+                |        mtime: 1560000000
+                |        target: /etc/foo
+                |    expectedFileChanges:
+                |      - path: VERSION
+                |        operation: write
+                |        content: |
+                |          1.2.3
+                |      - {path: a, operation: rename, to: b}
+                |      - {path: b, operation: chmod, mode: '755'}
+                |      - {path: c, operation: chmod}
+                |      - {path: d, operation: delete}
+                |    allowedDestinations:
+                |      - 127.0.0.1:8080
+                |      - '[::1]'
+                |      - localhost
+                |    network:
+                |      - api.example.com:443 -> 127.0.0.1:8443
+                |      - 10.0.0.1 -> 127.0.0.1
+                |    uid: 0
+                |    euid: 1000
+                |    gid: 100
+                |    groups: [100, 998]
+                |    uname:
+                |      sysname: Darwin
+                |      release: 18.0.0
+                |      machine: arm64
+                |  - steps: []
+                |    interaction:
+                |      - {expect: 'Continue? ', send: y}
+                |      - {expect: 'Password: ', send: secret, timeout: 10}
+                |    tty: true
+            ",
+        )?)?
+        .remove(0);
+        roundtrip(Tests::parse(yaml)?)
+    }
+
     #[test]
     fn outputs_an_empty_tests_object() -> R<()> {
         roundtrip(Tests::new(vec![]))
@@ -1229,22 +2992,70 @@ mod serialize {
         roundtrip(Tests::new(vec![Test::new(vec![Step::from_string("cp")?])]))
     }
 
+    #[test]
+    fn outputs_skip_and_only_markers() -> R<()> {
+        let mut skipped = Test::new(vec![Step::from_string("cp")?]);
+        skipped.skip = true;
+        let mut only = Test::empty();
+        only.only = true;
+        roundtrip(Tests::new(vec![skipped, only]))
+    }
+
+    #[test]
+    fn outputs_holes() -> R<()> {
+        let mut test = Test::new(vec![Step::from_string("cp")?]);
+        test.ends_with_hole = true;
+        roundtrip(Tests::new(vec![test]))
+    }
+
+    #[test]
+    fn outputs_test_names() -> R<()> {
+        let mut test = Test::new(vec![Step::from_string("cp")?]);
+        test.name = Some("foo".to_string());
+        roundtrip(Tests::new(vec![test]))
+    }
+
     mod arguments {
         use super::*;
+        use pretty_assertions::assert_eq;
 
         #[test]
         fn outputs_the_test_arguments() -> R<()> {
             let mut test = Test::new(vec![Step::from_string("cp")?]);
-            test.arguments = vec!["foo".to_string()];
+            test.arguments = vec![OsString::from("foo")];
             roundtrip(Tests::new(vec![test]))
         }
 
         #[test]
         fn works_for_arguments_with_special_characters() -> R<()> {
             let mut test = Test::new(vec![Step::from_string("cp")?]);
-            test.arguments = vec!["foo bar".to_string()];
+            test.arguments = vec![OsString::from("foo bar")];
+            roundtrip(Tests::new(vec![test]))
+        }
+
+        #[test]
+        fn quotes_empty_arguments() -> R<()> {
+            let mut test = Test::new(vec![Step::new(CommandMatcher::ExactMatch(Command {
+                executable: PathBuf::from("cp"),
+                arguments: vec![OsString::from("")],
+            }))]);
+            test.arguments = vec![OsString::from("foo"), OsString::from("")];
+            let yaml = Tests::new(vec![test.clone()]).serialize()?;
+            assert_eq!(yaml["tests"][0]["arguments"], Yaml::from_str("foo ''"));
+            assert_eq!(yaml["tests"][0]["steps"][0], Yaml::from_str("cp ''"));
             roundtrip(Tests::new(vec![test]))
         }
+
+        #[test]
+        fn uses_strings_for_unambiguous_arguments() -> R<()> {
+            let mut test = Test::new(vec![Step::from_string("cp")?]);
+            test.arguments = vec![OsString::from("foo bar"), OsString::from("\"")];
+            assert_eq!(
+                Tests::new(vec![test]).serialize()?["tests"][0]["arguments"],
+                Yaml::from_str(r#""foo bar" \""#)
+            );
+            Ok(())
+        }
     }
 
     #[test]
@@ -1259,11 +3070,25 @@ mod serialize {
         let test = Test::new(vec![Step {
             command_matcher: CommandMatcher::ExactMatch(Command::new("cp")?),
             stdout: vec![],
+            stdout_file: None,
             exitcode: 42,
+            exec_error: None,
         }]);
         roundtrip(Tests::new(vec![test]))
     }
 
+    #[test]
+    fn includes_the_step_exec_errors() -> R<()> {
+        let mut not_found = Step::from_string("docker")?;
+        not_found.exec_error = Some(ExecError::not_found());
+        let mut permission_denied = Step::from_string("./build")?;
+        permission_denied.exec_error = Some(ExecError::parse("EACCES")?);
+        roundtrip(Tests::new(vec![Test::new(vec![
+            not_found,
+            permission_denied,
+        ])]))
+    }
+
     #[test]
     fn includes_the_environment() -> R<()> {
         let mut test = Test::empty();
@@ -1271,12 +3096,97 @@ mod serialize {
         roundtrip(Tests::new(vec![test]))
     }
 
+    #[test]
+    fn includes_mocked_stdout() -> R<()> {
+        let mut step = Step::from_string("cp")?;
+        step.stdout = b"foo".to_vec();
+        roundtrip(Tests::new(vec![Test::new(vec![step])]))
+    }
+
+    #[test]
+    fn escapes_dollar_signs_that_would_be_expanded() -> R<()> {
+        let mut test = Test::new(vec![Step::new(CommandMatcher::ExactMatch(Command {
+            executable: PathBuf::from("echo"),
+            arguments: vec![OsString::from("${HOME}"), OsString::from("$$")],
+        }))]);
+        test.stdout = Some(OutputMatcher::exact(b"${HOME} $1".to_vec()));
+        let yaml = Tests::new(vec![test.clone()]).serialize()?;
+        assert_eq!(
+            yaml["tests"][0]["steps"][0],
+            Yaml::from_str(r#"echo "\$\${HOME}" \$\$\$"#)
+        );
+        assert_eq!(yaml["tests"][0]["stdout"], Yaml::from_str("$${HOME} $1"));
+        roundtrip(Tests::new(vec![test]))
+    }
+
+    #[test]
+    fn roundtrips_binary_data_losslessly() -> R<()> {
+        let mut step = Step::new(CommandMatcher::ExactMatch(Command {
+            executable: PathBuf::from("cp"),
+            arguments: vec![OsString::from_vec(b"caf\xe9".to_vec())],
+        }));
+        step.stdout = b"\x1f\x8b\x08\x00".to_vec();
+        let mut test = Test::new(vec![step]);
+        test.arguments = vec![OsString::from_vec(b"\xff".to_vec())];
+        test.stdout = Some(OutputMatcher::exact(b"\xfe".to_vec()));
+        roundtrip(Tests::new(vec![test]))
+    }
+
+    #[test]
+    fn includes_stdout_fixture_files_instead_of_their_content() -> R<()> {
+        let mut step = Step::from_string("cp")?;
+        step.stdout_file = Some(PathBuf::from("output"));
+        let mut test = Test::new(vec![step]);
+        test.stdout = Some(OutputMatcher {
+            file: Some(PathBuf::from("expected")),
+            ..OutputMatcher::default()
+        });
+        test.stderr = Some(OutputMatcher::exact(b"error".to_vec()));
+        let mut tests = Tests::new(vec![test]);
+        tests.record_output = true;
+        tests.output_fixture_lines = Some(10);
+        roundtrip(tests)
+    }
+
+    #[test]
+    fn includes_the_inherited_environment() -> R<()> {
+        let mut test = Test::empty();
+        test.inherit_env = Some(InheritEnv::Variables(vec!["HOME".to_string()]));
+        test.unset_env = vec!["LANG".to_string()];
+        let mut tests = Tests::new(vec![test]);
+        tests.inherit_env = Some(InheritEnv::All);
+        tests.unset_env = vec!["TERM".to_string()];
+        roundtrip(tests)
+    }
+
     #[test]
     fn includes_unmocked_commands() -> R<()> {
         let mut tests = Tests::new(vec![Test::new(vec![])]);
         tests.unmocked_commands = vec![PathBuf::from("sed")];
         roundtrip(tests)
     }
+
+    #[test]
+    fn includes_the_executable_matching_mode() -> R<()> {
+        let mut tests = Tests::new(vec![Test::new(vec![])]);
+        tests.executable_matching = ExecutableMatching::Basename;
+        roundtrip(tests)
+    }
+
+    #[test]
+    fn includes_mocked_executables() -> R<()> {
+        let mut tests = Tests::new(vec![Test::new(vec![])]);
+        tests.mocked_executables = vec!["kubectl".to_string()];
+        roundtrip(tests)
+    }
+
+    #[test]
+    fn includes_safe_mode_settings() -> R<()> {
+        let mut tests = Tests::new(vec![Test::new(vec![])]);
+        tests.safe = true;
+        tests.allowed_writes = vec![PathBuf::from("/tmp/out")];
+        roundtrip(tests)
+    }
 }
 
 fn find_test_file(executable: &Path) -> PathBuf {
@@ -1323,3 +3233,47 @@ mod find_test_file {
         );
     }
 }
+
+#[cfg(test)]
+mod selected {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn named(name: &str) -> Test {
+        let mut test = Test::empty();
+        test.name = Some(name.to_string());
+        test
+    }
+
+    #[test]
+    fn selects_all_tests_by_default() {
+        let tests = Tests::new(vec![named("foo"), Test::empty()]);
+        assert_eq!(tests.selected(None), vec![true, true]);
+    }
+
+    #[test]
+    fn excludes_skipped_tests() {
+        let mut skipped = named("foo");
+        skipped.skip = true;
+        let tests = Tests::new(vec![skipped, Test::empty()]);
+        assert_eq!(tests.selected(None), vec![false, true]);
+    }
+
+    #[test]
+    fn only_selects_tests_marked_with_only_if_there_are_any() {
+        let mut only = named("foo");
+        only.only = true;
+        let tests = Tests::new(vec![only, Test::empty()]);
+        assert_eq!(tests.selected(None), vec![true, false]);
+    }
+
+    #[test]
+    fn selects_named_tests_matching_the_filter() -> R<()> {
+        let tests = Tests::new(vec![named("foo"), named("bar"), Test::empty()]);
+        assert_eq!(
+            tests.selected(Some(&Regex::new("^f")?)),
+            vec![true, false, false]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,545 @@
+use super::fixture::{parse_file_field, read_fixture};
+use super::yaml::*;
+use crate::utils::strip_ansi;
+use crate::R;
+use regex::bytes::Regex;
+use std::path::{Path, PathBuf};
+use yaml_rust::{yaml::Hash, Yaml};
+
+#[derive(Debug, Clone)]
+pub struct OutputRegex {
+    original_string: String,
+    regex: Regex,
+}
+
+impl PartialEq for OutputRegex {
+    fn eq(&self, other: &OutputRegex) -> bool {
+        self.original_string == other.original_string
+    }
+}
+
+impl OutputRegex {
+    pub fn new(raw_regex: &str) -> R<OutputRegex> {
+        Ok(OutputRegex {
+            original_string: raw_regex.to_string(),
+            regex: Regex::new(raw_regex)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OutputMatcher {
+    pub equals: Option<Vec<u8>>,
+    pub file: Option<PathBuf>,
+    pub contains: Vec<Vec<u8>>,
+    pub not_contains: Vec<Vec<u8>>,
+    pub regex: Option<OutputRegex>,
+    pub lines: Option<Vec<Vec<u8>>>,
+    pub trim_trailing_whitespace: bool,
+    pub strip_ansi: bool,
+}
+
+impl OutputMatcher {
+    pub fn exact(expected: Vec<u8>) -> OutputMatcher {
+        OutputMatcher {
+            equals: Some(expected),
+            ..OutputMatcher::default()
+        }
+    }
+
+    fn parse_strings(yaml: &Yaml) -> R<Vec<Vec<u8>>> {
+        Ok(match yaml {
+            Yaml::Array(array) => array
+                .iter()
+                .map(|element| element.expect_bytes())
+                .collect::<R<Vec<Vec<u8>>>>()?,
+            _ => vec![yaml.expect_bytes()?],
+        })
+    }
+
+    fn parse_flag(object: &Hash, name: &str) -> R<bool> {
+        Ok(match object.expect_field(name) {
+            Ok(flag) => flag
+                .as_bool()
+                .ok_or_else(|| format!("expected: boolean, got: {:?}", flag))?,
+            Err(_) => false,
+        })
+    }
+
+    fn from_object(object: &Hash) -> R<OutputMatcher> {
+        check_keys(
+            &[
+                "equals",
+                "file",
+                "contains",
+                "notContains",
+                "regex",
+                "lines",
+                "trimTrailingWhitespace",
+                "stripAnsi",
+            ],
+            object,
+        )?;
+        let matcher = OutputMatcher {
+            equals: match object.expect_field("equals") {
+                Ok(equals) => Some(equals.expect_bytes()?),
+                Err(_) => None,
+            },
+            file: parse_file_field(object)?,
+            contains: match object.expect_field("contains") {
+                Ok(contains) => OutputMatcher::parse_strings(contains)?,
+                Err(_) => vec![],
+            },
+            not_contains: match object.expect_field("notContains") {
+                Ok(not_contains) => OutputMatcher::parse_strings(not_contains)?,
+                Err(_) => vec![],
+            },
+            regex: match object.expect_field("regex") {
+                Ok(regex) => Some(OutputRegex::new(regex.expect_str()?)?),
+                Err(_) => None,
+            },
+            lines: match object.expect_field("lines") {
+                Ok(lines) => Some(
+                    lines
+                        .expect_array()?
+                        .iter()
+                        .map(|line| line.expect_bytes())
+                        .collect::<R<Vec<Vec<u8>>>>()?,
+                ),
+                Err(_) => None,
+            },
+            trim_trailing_whitespace: OutputMatcher::parse_flag(object, "trimTrailingWhitespace")?,
+            strip_ansi: OutputMatcher::parse_flag(object, "stripAnsi")?,
+        };
+        if matcher.equals.is_some() && matcher.file.is_some() {
+            Err("'equals' and 'file' can't be used together")?;
+        }
+        if matcher.equals.is_none()
+            && matcher.file.is_none()
+            && matcher.contains.is_empty()
+            && matcher.not_contains.is_empty()
+            && matcher.regex.is_none()
+            && matcher.lines.is_none()
+        {
+            Err(
+                "expected at least one of 'equals', 'file', 'contains', 'notContains', 'regex', \
+                 'lines'",
+            )?;
+        }
+        Ok(matcher)
+    }
+
+    pub fn parse(yaml: &Yaml) -> R<OutputMatcher> {
+        Ok(match yaml {
+            Yaml::Hash(object) if !yaml.is_binary() => OutputMatcher::from_object(object)?,
+            _ => OutputMatcher::exact(yaml.expect_bytes()?),
+        })
+    }
+
+    pub fn resolve(&mut self, test_directory: &Path) -> R<()> {
+        if let Some(file) = &self.file {
+            self.equals = Some(read_fixture(test_directory, file)?);
+        }
+        Ok(())
+    }
+
+    fn serialize_strings(strings: &[Vec<u8>]) -> Yaml {
+        Yaml::Array(
+            strings
+                .iter()
+                .map(|string| serialize_bytes(string))
+                .collect(),
+        )
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        if let (Some(equals), None) = (&self.equals, &self.file) {
+            if *self == OutputMatcher::exact(equals.clone()) {
+                return serialize_bytes(equals);
+            }
+        }
+        let mut object = Hash::new();
+        match (&self.file, &self.equals) {
+            (Some(file), _) => {
+                object.insert(
+                    Yaml::from_str("file"),
+                    Yaml::String(file.to_string_lossy().to_string()),
+                );
+            }
+            (None, Some(equals)) => {
+                object.insert(Yaml::from_str("equals"), serialize_bytes(equals));
+            }
+            (None, None) => {}
+        }
+        if !self.contains.is_empty() {
+            object.insert(
+                Yaml::from_str("contains"),
+                OutputMatcher::serialize_strings(&self.contains),
+            );
+        }
+        if !self.not_contains.is_empty() {
+            object.insert(
+                Yaml::from_str("notContains"),
+                OutputMatcher::serialize_strings(&self.not_contains),
+            );
+        }
+        if let Some(regex) = &self.regex {
+            object.insert(
+                Yaml::from_str("regex"),
+                Yaml::from_str(&regex.original_string),
+            );
+        }
+        if let Some(lines) = &self.lines {
+            object.insert(
+                Yaml::from_str("lines"),
+                OutputMatcher::serialize_strings(lines),
+            );
+        }
+        if self.trim_trailing_whitespace {
+            object.insert(
+                Yaml::from_str("trimTrailingWhitespace"),
+                Yaml::Boolean(true),
+            );
+        }
+        if self.strip_ansi {
+            object.insert(Yaml::from_str("stripAnsi"), Yaml::Boolean(true));
+        }
+        Yaml::Hash(object)
+    }
+
+    fn trim_trailing_whitespace(output: &[u8]) -> Vec<u8> {
+        let is_whitespace = |byte: &u8| byte.is_ascii_whitespace() && *byte != b'\n';
+        let mut result = vec![];
+        for (i, line) in output.split(|byte| *byte == b'\n').enumerate() {
+            if i > 0 {
+                result.push(b'\n');
+            }
+            let length = line.len() - line.iter().rev().take_while(|b| is_whitespace(b)).count();
+            result.extend_from_slice(&line[..length]);
+        }
+        result
+    }
+
+    pub fn normalize(&self, output: &[u8]) -> Vec<u8> {
+        let mut output = output.to_vec();
+        if self.strip_ansi {
+            output = strip_ansi(&output);
+        }
+        if self.trim_trailing_whitespace {
+            output = OutputMatcher::trim_trailing_whitespace(&output);
+        }
+        output
+    }
+
+    fn lines(output: &[u8]) -> Vec<&[u8]> {
+        let output = if output.ends_with(b"\n") {
+            &output[..output.len() - 1]
+        } else {
+            output
+        };
+        if output.is_empty() {
+            vec![]
+        } else {
+            output.split(|byte| *byte == b'\n').collect()
+        }
+    }
+
+    fn contains(output: &[u8], part: &[u8]) -> bool {
+        part.is_empty() || output.windows(part.len()).any(|window| window == part)
+    }
+
+    fn format_lines(lines: &[Vec<u8>]) -> String {
+        format!(
+            "{:?}",
+            lines
+                .iter()
+                .map(|line| String::from_utf8_lossy(line).to_string())
+                .collect::<Vec<String>>()
+        )
+    }
+
+    pub fn mismatch(&self, output: &[u8]) -> Option<String> {
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).to_string();
+        if let Some(equals) = &self.equals {
+            if *output != *self.normalize(equals) {
+                return Some(format!(": {:?}", lossy(equals)));
+            }
+        }
+        for part in &self.contains {
+            if !OutputMatcher::contains(output, &self.normalize(part)) {
+                return Some(format!(" to contain: {:?}", lossy(part)));
+            }
+        }
+        for part in &self.not_contains {
+            if OutputMatcher::contains(output, &self.normalize(part)) {
+                return Some(format!(" not to contain: {:?}", lossy(part)));
+            }
+        }
+        if let Some(regex) = &self.regex {
+            if !regex.regex.is_match(output) {
+                return Some(format!(" to match regex: {:?}", regex.original_string));
+            }
+        }
+        if let Some(lines) = &self.lines {
+            let mut expected: Vec<Vec<u8>> =
+                lines.iter().map(|line| self.normalize(line)).collect();
+            let mut received: Vec<Vec<u8>> = OutputMatcher::lines(output)
+                .into_iter()
+                .map(|line| line.to_vec())
+                .collect();
+            expected.sort();
+            received.sort();
+            if expected != received {
+                return Some(format!(
+                    " to consist of the lines (in any order): {}",
+                    OutputMatcher::format_lines(lines)
+                ));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod output_matcher {
+    use super::*;
+    use yaml_rust::YamlLoader;
+
+    fn matcher(yaml: &str) -> R<OutputMatcher> {
+        OutputMatcher::parse(&YamlLoader::load_from_str(yaml)?[0])
+    }
+
+    fn mismatch(yaml: &str, output: &str) -> R<Option<String>> {
+        let matcher = matcher(yaml)?;
+        Ok(matcher.mismatch(&matcher.normalize(output.as_bytes())))
+    }
+
+    mod parse {
+        use super::*;
+        use pretty_assertions::assert_eq;
+        use test_utils::assert_error;
+
+        #[test]
+        fn parses_strings_as_exact_matches() -> R<()> {
+            assert_eq!(matcher("foo")?, OutputMatcher::exact(b"foo".to_vec()));
+            Ok(())
+        }
+
+        #[test]
+        fn parses_base64_objects_as_exact_matches() -> R<()> {
+            assert_eq!(
+                matcher("{base64: //4A}")?,
+                OutputMatcher::exact(b"\xff\xfe\x00".to_vec())
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn parses_objects() -> R<()> {
+            assert_eq!(
+                matcher("{contains: [foo, bar], notContains: baz, stripAnsi: true}")?,
+                OutputMatcher {
+                    contains: vec![b"foo".to_vec(), b"bar".to_vec()],
+                    not_contains: vec![b"baz".to_vec()],
+                    strip_ansi: true,
+                    ..OutputMatcher::default()
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_objects_without_matchers() {
+            assert_error!(
+                matcher("{stripAnsi: true}"),
+                "expected at least one of 'equals', 'file', 'contains', 'notContains', 'regex', \
+                 'lines'"
+            );
+        }
+
+        #[test]
+        fn parses_fixture_files() -> R<()> {
+            assert_eq!(
+                matcher("{file: output.txt, stripAnsi: true}")?,
+                OutputMatcher {
+                    file: Some(PathBuf::from("output.txt")),
+                    strip_ansi: true,
+                    ..OutputMatcher::default()
+                }
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn disallows_equals_together_with_file() {
+            assert_error!(
+                matcher("{equals: foo, file: output.txt}"),
+                "'equals' and 'file' can't be used together"
+            );
+        }
+
+        #[test]
+        fn disallows_invalid_regexes() {
+            assert!(matcher("{regex: '('}").is_err());
+        }
+    }
+
+    mod serialize {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn roundtrip(yaml: &str) -> R<()> {
+            let matcher = matcher(yaml)?;
+            assert_eq!(OutputMatcher::parse(&matcher.serialize())?, matcher);
+            Ok(())
+        }
+
+        #[test]
+        fn serializes_exact_matches_as_strings() {
+            assert_eq!(
+                OutputMatcher::exact(b"foo".to_vec()).serialize(),
+                Yaml::from_str("foo")
+            );
+        }
+
+        #[test]
+        fn roundtrips_objects() -> R<()> {
+            roundtrip(
+                "{equals: foo, contains: [a, b], notContains: c, regex: '\\d+', lines: [d], \
+                 trimTrailingWhitespace: true, stripAnsi: true}",
+            )?;
+            roundtrip("{file: output.txt}")?;
+            roundtrip("{base64: //4A}")?;
+            roundtrip("{equals: {base64: //4A}, contains: {base64: /w==}}")?;
+            Ok(())
+        }
+
+        #[test]
+        fn serializes_the_fixture_file_instead_of_its_content() -> R<()> {
+            let mut matcher = matcher("{file: output.txt}")?;
+            matcher.equals = Some(b"foo".to_vec());
+            assert_eq!(
+                OutputMatcher::parse(&matcher.serialize())?,
+                OutputMatcher {
+                    file: Some(PathBuf::from("output.txt")),
+                    ..OutputMatcher::default()
+                }
+            );
+            Ok(())
+        }
+    }
+
+    mod mismatch {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn checks_for_exact_matches() -> R<()> {
+            assert_eq!(mismatch("foo", "foo")?, None);
+            assert_eq!(mismatch("foo", "bar")?, Some(": \"foo\"".to_string()));
+            Ok(())
+        }
+
+        #[test]
+        fn checks_for_contained_strings() -> R<()> {
+            assert_eq!(mismatch("{contains: [a, c]}", "abc")?, None);
+            assert_eq!(
+                mismatch("{contains: [a, d]}", "abc")?,
+                Some(" to contain: \"d\"".to_string())
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn checks_for_not_contained_strings() -> R<()> {
+            assert_eq!(mismatch("{notContains: error}", "ok")?, None);
+            assert_eq!(
+                mismatch("{notContains: error}", "an error")?,
+                Some(" not to contain: \"error\"".to_string())
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn checks_regexes_anywhere_in_the_output() -> R<()> {
+            assert_eq!(
+                mismatch(r"{regex: 'took \d+ms'}", "done\ntook 42ms\n")?,
+                None
+            );
+            assert_eq!(
+                mismatch(r"{regex: 'took \d+ms'}", "took ms\n")?,
+                Some(" to match regex: \"took \\\\d+ms\"".to_string())
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn checks_lines_in_any_order() -> R<()> {
+            assert_eq!(mismatch("{lines: [b, a]}", "a\nb\n")?, None);
+            assert_eq!(
+                mismatch("{lines: [a]}", "a\nb\n")?,
+                Some(" to consist of the lines (in any order): [\"a\"]".to_string())
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn allows_to_trim_trailing_whitespace() -> R<()> {
+            assert_eq!(
+                mismatch(
+                    r#"{equals: "a\nb\n", trimTrailingWhitespace: true}"#,
+                    "a  \nb\t\n"
+                )?,
+                None
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn allows_to_strip_ansi_escape_sequences() -> R<()> {
+            assert_eq!(
+                mismatch("{equals: red, stripAnsi: true}", "\u{1b}[31mred\u{1b}[0m")?,
+                None
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn normalizes_contained_strings() -> R<()> {
+            assert_eq!(
+                mismatch(
+                    r#"{contains: "a  \nb", trimTrailingWhitespace: true}"#,
+                    "a  \nb\n"
+                )?,
+                None
+            );
+            assert_eq!(
+                mismatch(
+                    r#"{contains: "\e[31mred", stripAnsi: true}"#,
+                    "\u{1b}[31mred\u{1b}[0m"
+                )?,
+                None
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn normalizes_not_contained_strings() -> R<()> {
+            assert_eq!(
+                mismatch(
+                    r#"{notContains: "a \n", trimTrailingWhitespace: true}"#,
+                    "a \nb\n"
+                )?,
+                Some(" not to contain: \"a \\n\"".to_string())
+            );
+            assert_eq!(
+                mismatch(
+                    r#"{notContains: "\e[31mred", stripAnsi: true}"#,
+                    "\u{1b}[31mred\u{1b}[0m"
+                )?,
+                Some(" not to contain: \"\\u{1b}[31mred\"".to_string())
+            );
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use super::fixture::serialize_file_field;
+use super::yaml::*;
+use crate::R;
+use std::path::{Path, PathBuf};
+use yaml_rust::Yaml;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Stdin {
+    Bytes(Vec<u8>),
+    /// `file` is the path as written in the test file, `path` is the
+    /// path after resolving it relative to the test directory.
+    File {
+        file: PathBuf,
+        path: PathBuf,
+    },
+}
+
+impl Stdin {
+    pub fn parse(yaml: &Yaml) -> R<Stdin> {
+        Ok(match yaml {
+            yaml if yaml.is_binary() => Stdin::Bytes(yaml.expect_bytes()?),
+            Yaml::String(_) => Stdin::Bytes(yaml.expect_bytes()?),
+            Yaml::Hash(object) => {
+                check_keys(&["file"], object)?;
+                let file = PathBuf::from(object.expect_field("file")?.expect_str()?);
+                Stdin::File {
+                    path: file.clone(),
+                    file,
+                }
+            }
+            _ => Err(format!(
+                "expected: string or object with field 'file', got: {:?}",
+                yaml
+            ))?,
+        })
+    }
+
+    pub fn resolve(self, test_directory: &Path) -> Stdin {
+        match self {
+            Stdin::File { file, .. } => Stdin::File {
+                path: test_directory.join(&file),
+                file,
+            },
+            stdin => stdin,
+        }
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        match self {
+            Stdin::Bytes(bytes) => serialize_bytes(bytes),
+            Stdin::File { file, .. } => serialize_file_field(file),
+        }
+    }
+}
+
+#[cfg(test)]
+mod parse {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+    use yaml_rust::YamlLoader;
+
+    fn test_parse(yaml: &str) -> R<Stdin> {
+        Stdin::parse(&YamlLoader::load_from_str(yaml)?[0])
+    }
+
+    #[test]
+    fn parses_strings() -> R<()> {
+        assert_eq!(test_parse(r#""yes\n""#)?, Stdin::Bytes(b"yes\n".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn parses_base64_objects() -> R<()> {
+        assert_eq!(
+            test_parse("{base64: //4A}")?,
+            Stdin::Bytes(b"\xff\xfe\x00".to_vec())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parses_fixture_files() -> R<()> {
+        assert_eq!(
+            test_parse("{file: input.json}")?,
+            Stdin::File {
+                file: PathBuf::from("input.json"),
+                path: PathBuf::from("input.json"),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_other_types() {
+        assert_error!(
+            test_parse("42"),
+            "expected: string or object with field 'file', got: Integer(42)"
+        );
+    }
+}
+
+#[cfg(test)]
+mod resolve {
+    use super::*;
+
+    fn file(path: &str) -> Stdin {
+        Stdin::File {
+            file: PathBuf::from(path),
+            path: PathBuf::from(path),
+        }
+    }
+
+    #[test]
+    fn resolves_fixture_files_relative_to_the_test_directory() {
+        assert_eq!(
+            file("input.json").resolve(Path::new("/project")),
+            Stdin::File {
+                file: PathBuf::from("input.json"),
+                path: PathBuf::from("/project/input.json"),
+            }
+        );
+        assert_eq!(
+            file("/input.json").resolve(Path::new("/project")),
+            file("/input.json")
+        );
+    }
+}
+
+#[cfg(test)]
+mod serialize {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn serializes_the_fixture_file_as_written_in_the_test_file() -> R<()> {
+        let stdin = Stdin::parse(&serialize_file_field(Path::new("input.json")))?;
+        assert_eq!(
+            stdin.clone().resolve(Path::new("/project")).serialize(),
+            stdin.serialize()
+        );
+        assert_eq!(Stdin::parse(&stdin.serialize())?, stdin);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrips_bytes() -> R<()> {
+        let stdin = Stdin::Bytes(b"\xff\xfe\x00".to_vec());
+        assert_eq!(Stdin::parse(&stdin.serialize())?, stdin);
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use super::yaml::*;
+use crate::R;
+use std::convert::TryFrom;
+use yaml_rust::{yaml::Hash, Yaml};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Tty {
+    pub rows: u16,
+    pub columns: u16,
+}
+
+impl Default for Tty {
+    fn default() -> Tty {
+        Tty {
+            rows: 24,
+            columns: 80,
+        }
+    }
+}
+
+impl Tty {
+    fn parse_size(yaml: &Yaml, name: &str) -> R<Option<u16>> {
+        Ok(match yaml.expect_object()?.expect_field(name) {
+            Ok(size) => {
+                let size = size.expect_integer()?;
+                match u16::try_from(size) {
+                    Ok(size) if size > 0 => Some(size),
+                    _ => Err(format!(
+                        "tty.{} has to be between 1 and 65535, got: {}",
+                        name, size
+                    ))?,
+                }
+            }
+            Err(_) => None,
+        })
+    }
+
+    pub fn parse(yaml: &Yaml) -> R<Option<Tty>> {
+        Ok(match yaml {
+            Yaml::Boolean(false) => None,
+            Yaml::Boolean(true) => Some(Tty::default()),
+            Yaml::Hash(object) => {
+                check_keys(&["rows", "columns"], object)?;
+                let default = Tty::default();
+                Some(Tty {
+                    rows: Tty::parse_size(yaml, "rows")?.unwrap_or(default.rows),
+                    columns: Tty::parse_size(yaml, "columns")?.unwrap_or(default.columns),
+                })
+            }
+            _ => Err(format!(
+                "expected: boolean or object with fields 'rows' and 'columns', got: {:?}",
+                yaml
+            ))?,
+        })
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        if *self == Tty::default() {
+            return Yaml::Boolean(true);
+        }
+        let mut object = Hash::new();
+        object.insert(Yaml::from_str("rows"), Yaml::Integer(i64::from(self.rows)));
+        object.insert(
+            Yaml::from_str("columns"),
+            Yaml::Integer(i64::from(self.columns)),
+        );
+        Yaml::Hash(object)
+    }
+}
+
+#[cfg(test)]
+mod parse {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+    use yaml_rust::YamlLoader;
+
+    fn test_parse(yaml: &str) -> R<Option<Tty>> {
+        Tty::parse(&YamlLoader::load_from_str(yaml)?[0])
+    }
+
+    #[test]
+    fn parses_booleans() -> R<()> {
+        assert_eq!(test_parse("true")?, Some(Tty::default()));
+        assert_eq!(test_parse("false")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn parses_window_sizes() -> R<()> {
+        assert_eq!(
+            test_parse("{rows: 50, columns: 200}")?,
+            Some(Tty {
+                rows: 50,
+                columns: 200,
+            })
+        );
+        assert_eq!(
+            test_parse("{columns: 200}")?,
+            Some(Tty {
+                rows: 24,
+                columns: 200,
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_invalid_window_sizes() {
+        assert_error!(
+            test_parse("{rows: 0}"),
+            "tty.rows has to be between 1 and 65535, got: 0"
+        );
+    }
+}
+
+#[cfg(test)]
+mod serialize {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn serializes_the_default_size_as_true() {
+        assert_eq!(Tty::default().serialize(), Yaml::Boolean(true));
+    }
+
+    #[test]
+    fn roundtrips_custom_sizes() -> R<()> {
+        let tty = Tty {
+            rows: 50,
+            columns: 120,
+        };
+        assert_eq!(Tty::parse(&tty.serialize())?, Some(tty));
+        Ok(())
+    }
+}
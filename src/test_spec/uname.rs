@@ -0,0 +1,123 @@
+use super::yaml::*;
+use crate::R;
+use yaml_rust::{yaml::Hash, Yaml};
+
+const FIELD_LENGTH: usize = 65;
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Uname {
+    pub sysname: Option<String>,
+    pub nodename: Option<String>,
+    pub release: Option<String>,
+    pub machine: Option<String>,
+}
+
+impl Uname {
+    pub fn parse(yaml: &Yaml) -> R<Uname> {
+        let object = yaml.expect_object()?;
+        check_keys(&["sysname", "nodename", "release", "machine"], object)?;
+        let field = |name: &str| -> R<Option<String>> {
+            Ok(match object.expect_field(name) {
+                Ok(value) => {
+                    let value = value.expect_str()?;
+                    if value.len() >= FIELD_LENGTH {
+                        Err(format!(
+                            "uname.{} has to be shorter than {} bytes, got: {:?}",
+                            name, FIELD_LENGTH, value
+                        ))?;
+                    }
+                    Some(value.to_string())
+                }
+                Err(_) => None,
+            })
+        };
+        Ok(Uname {
+            sysname: field("sysname")?,
+            nodename: field("nodename")?,
+            release: field("release")?,
+            machine: field("machine")?,
+        })
+    }
+
+    pub fn serialize(&self) -> Yaml {
+        let mut object = Hash::new();
+        let fields = [
+            ("sysname", &self.sysname),
+            ("nodename", &self.nodename),
+            ("release", &self.release),
+            ("machine", &self.machine),
+        ];
+        for (name, value) in fields.iter() {
+            if let Some(value) = value {
+                object.insert(Yaml::from_str(name), Yaml::String(value.clone()));
+            }
+        }
+        Yaml::Hash(object)
+    }
+
+    pub fn patches(&self) -> Vec<(usize, Vec<u8>)> {
+        let fields = [
+            (0, &self.sysname),
+            (1, &self.nodename),
+            (2, &self.release),
+            (4, &self.machine),
+        ];
+        fields
+            .iter()
+            .filter_map(|(index, value)| {
+                value.as_ref().map(|value| {
+                    let mut bytes = value.as_bytes().to_vec();
+                    bytes.push(0);
+                    (index * FIELD_LENGTH, bytes)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod uname {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+    use yaml_rust::YamlLoader;
+
+    fn test_parse(yaml: &str) -> R<Uname> {
+        Uname::parse(&YamlLoader::load_from_str(yaml)?[0])
+    }
+
+    #[test]
+    fn parses_all_fields() -> R<()> {
+        assert_eq!(
+            test_parse("{sysname: Darwin, nodename: prod-1, release: 18.0.0, machine: arm64}")?,
+            Uname {
+                sysname: Some("Darwin".to_string()),
+                nodename: Some("prod-1".to_string()),
+                release: Some("18.0.0".to_string()),
+                machine: Some("arm64".to_string()),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_values_that_do_not_fit_into_utsname() {
+        let long = "x".repeat(65);
+        assert_error!(
+            test_parse(&format!("{{nodename: {}}}", long)),
+            format!(
+                "uname.nodename has to be shorter than 65 bytes, got: {:?}",
+                long
+            )
+        );
+    }
+
+    #[test]
+    fn patches_the_utsname_fields_at_their_offsets() -> R<()> {
+        assert_eq!(
+            test_parse("{nodename: a, machine: b}")?.patches(),
+            vec![(65, b"a\0".to_vec()), (260, b"b\0".to_vec())]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,387 @@
+use super::argument_parser::Parser;
+use super::Command;
+use crate::utils::path_to_string;
+use crate::R;
+use std::env;
+use std::ffi::OsString;
+use std::path::{Component, Path};
+use yaml_rust::{yaml::Hash, Yaml};
+
+const FIELDS: &[&str] = &["arguments", "env", "cwd", "steps", "stdout", "stderr"];
+
+pub struct Variables {
+    directories: Vec<(String, String)>,
+}
+
+fn absolute_directory(file: &Path) -> R<String> {
+    let mut directory = env::current_dir()?;
+    if let Some(parent) = file.parent() {
+        for component in parent.components() {
+            if component != Component::CurDir {
+                directory.push(component);
+            }
+        }
+    }
+    Ok(path_to_string(&directory)?.to_string())
+}
+
+fn parse_name(rest: &str) -> R<&str> {
+    let name = rest[2..].split('}').next().unwrap_or("");
+    let is_valid = rest[2..].contains('}')
+        && !name.is_empty()
+        && !name.starts_with(|char: char| char.is_ascii_digit())
+        && name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_');
+    if !is_valid {
+        let reference = match rest.find('}') {
+            Some(index) => &rest[..=index],
+            None => rest,
+        };
+        Err(format!(
+            "invalid variable reference {:?} (use '$$' for a literal '$')",
+            reference
+        ))?;
+    }
+    Ok(name)
+}
+
+impl Variables {
+    #[cfg(test)]
+    pub fn from_env() -> Variables {
+        Variables {
+            directories: vec![],
+        }
+    }
+
+    pub fn new(script: &Path, test_file: &Path) -> R<Variables> {
+        Ok(Variables {
+            directories: vec![
+                ("SCRIPT_DIR".to_string(), absolute_directory(script)?),
+                ("TEST_DIR".to_string(), absolute_directory(test_file)?),
+            ],
+        })
+    }
+
+    fn lookup(&self, name: &str) -> R<String> {
+        match self
+            .directories
+            .iter()
+            .find(|(directory_name, _)| directory_name == name)
+        {
+            Some((_, value)) => Ok(value.clone()),
+            None => Ok(env::var(name).map_err(|error| match error {
+                env::VarError::NotPresent => format!("undefined variable: ${{{}}}", name),
+                env::VarError::NotUnicode(_) => {
+                    format!("variable ${{{}}} is not valid UTF-8", name)
+                }
+            })?),
+        }
+    }
+
+    fn expand_string(&self, string: &str) -> R<String> {
+        let mut result = String::new();
+        let mut rest = string;
+        while let Some(index) = rest.find('$') {
+            result.push_str(&rest[..index]);
+            rest = &rest[index..];
+            if rest.starts_with("$$") {
+                result.push('$');
+                rest = &rest[2..];
+            } else if rest.starts_with("${") {
+                let name = parse_name(rest)?;
+                result.push_str(&self.lookup(name)?);
+                rest = &rest[name.len() + 3..];
+            } else {
+                result.push('$');
+                rest = &rest[1..];
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    pub fn expand_test(&self, test: &mut Hash) -> R<()> {
+        map_test(test, &|string| self.expand_string(string))
+    }
+}
+
+fn map_strings(yaml: &Yaml, f: &dyn Fn(&str) -> R<String>) -> R<Yaml> {
+    Ok(match yaml {
+        Yaml::String(string) => Yaml::String(f(string)?),
+        Yaml::Array(array) => Yaml::Array(
+            array
+                .iter()
+                .map(|element| map_strings(element, f))
+                .collect::<R<Vec<Yaml>>>()?,
+        ),
+        Yaml::Hash(object) => {
+            let mut result = Hash::new();
+            for (key, value) in object {
+                result.insert(key.clone(), map_strings(value, f)?);
+            }
+            Yaml::Hash(result)
+        }
+        yaml => yaml.clone(),
+    })
+}
+
+// Command strings are split into words first, so that values containing
+// spaces, quotes or backslashes stay within a single word.
+fn map_words(command: &Yaml, f: &dyn Fn(&str) -> R<String>) -> R<Yaml> {
+    match command {
+        Yaml::String(string) => {
+            let words = Parser::parse_arguments(string.as_bytes())?
+                .into_iter()
+                .map(|word| match word.into_string() {
+                    Ok(word) => Ok(OsString::from(f(&word)?)),
+                    Err(word) => Ok(word),
+                })
+                .collect::<R<Vec<OsString>>>()?;
+            Ok(Yaml::String(Command::format_arguments(words)))
+        }
+        yaml => map_strings(yaml, f),
+    }
+}
+
+fn map_step(step: &Yaml, f: &dyn Fn(&str) -> R<String>) -> R<Yaml> {
+    match step {
+        Yaml::Hash(object) => {
+            let mut result = Hash::new();
+            for (key, value) in object {
+                let value = if key.as_str() == Some("command") {
+                    map_words(value, f)?
+                } else {
+                    map_strings(value, f)?
+                };
+                result.insert(key.clone(), value);
+            }
+            Ok(Yaml::Hash(result))
+        }
+        step => map_words(step, f),
+    }
+}
+
+fn map_test(test: &mut Hash, f: &dyn Fn(&str) -> R<String>) -> R<()> {
+    for field in FIELDS {
+        if let Some(value) = test.get_mut(&Yaml::from_str(field)) {
+            *value = match (*field, &*value) {
+                ("arguments", arguments) => map_words(arguments, f)?,
+                ("steps", Yaml::Array(steps)) => Yaml::Array(
+                    steps
+                        .iter()
+                        .map(|step| map_step(step, f))
+                        .collect::<R<Vec<Yaml>>>()?,
+                ),
+                (_, value) => map_strings(value, f)?,
+            };
+        }
+    }
+    Ok(())
+}
+
+fn references_variable(string: &str) -> bool {
+    let mut rest = string;
+    while let Some(index) = rest.find('$') {
+        rest = &rest[index..];
+        if rest.starts_with("$$") {
+            rest = &rest[2..];
+        } else if rest.starts_with("${") {
+            return true;
+        } else {
+            rest = &rest[1..];
+        }
+    }
+    false
+}
+
+fn contains_references(yaml: &Yaml) -> bool {
+    match yaml {
+        Yaml::String(string) => references_variable(string),
+        Yaml::Array(array) => array.iter().any(contains_references),
+        Yaml::Hash(object) => object.values().any(contains_references),
+        _ => false,
+    }
+}
+
+pub fn references_variables(test: &Hash) -> bool {
+    FIELDS
+        .iter()
+        .any(|field| match test.get(&Yaml::from_str(field)) {
+            Some(value) => contains_references(value),
+            None => false,
+        })
+}
+
+fn escape_string(string: &str) -> String {
+    let mut result = String::new();
+    let mut chars = string.chars().peekable();
+    while let Some(char) = chars.next() {
+        result.push(char);
+        if char == '$' {
+            if let Some('$') | Some('{') = chars.peek() {
+                result.push('$');
+            }
+        }
+    }
+    result
+}
+
+pub fn escape_test(test: &mut Hash) -> R<()> {
+    map_test(test, &|string| Ok(escape_string(string)))
+}
+
+#[cfg(test)]
+mod variables {
+    use super::*;
+    use crate::test_spec::yaml::YamlExt;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+    use yaml_rust::YamlLoader;
+
+    fn variables() -> R<Variables> {
+        Variables::new(Path::new("/scripts/foo"), Path::new("/tests/foo.test.yaml"))
+    }
+
+    #[test]
+    fn expands_directories_and_environment_variables() -> R<()> {
+        assert_eq!(
+            variables()?.expand_string("${SCRIPT_DIR}/bin ${TEST_DIR} ${HOME}")?,
+            format!("/scripts/bin /tests {}", env::var("HOME")?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn makes_relative_directories_absolute() -> R<()> {
+        let variables = Variables::new(Path::new("foo"), Path::new("foo.test.yaml"))?;
+        assert_eq!(
+            variables.expand_string("${SCRIPT_DIR}")?,
+            path_to_string(&env::current_dir()?)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn drops_current_directory_components() -> R<()> {
+        let cwd = path_to_string(&env::current_dir()?)?.to_string();
+        let variables = Variables::new(Path::new("./t"), Path::new("./tests/./t.test.yaml"))?;
+        assert_eq!(
+            variables.expand_string("${SCRIPT_DIR} ${TEST_DIR}")?,
+            format!("{} {}/tests", cwd, cwd)
+        );
+        let variables = Variables::new(Path::new("/scripts/./t"), Path::new("/t.test.yaml"))?;
+        assert_eq!(variables.expand_string("${SCRIPT_DIR}")?, "/scripts");
+        Ok(())
+    }
+
+    #[test]
+    fn leaves_other_dollar_signs_alone() -> R<()> {
+        assert_eq!(
+            variables()?.expand_string("$1 $'\\t' $HOME foo$")?,
+            "$1 $'\\t' $HOME foo$"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_escape_dollar_signs() -> R<()> {
+        assert_eq!(
+            variables()?.expand_string("$${HOME} $$$$ $$x")?,
+            "${HOME} $$ $x"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors_on_undefined_variables() -> R<()> {
+        assert_error!(
+            variables()?.expand_string("${SCRIPTKEEPER_UNDEFINED_VARIABLE}"),
+            "undefined variable: ${SCRIPTKEEPER_UNDEFINED_VARIABLE}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors_on_invalid_variable_references() -> R<()> {
+        assert_error!(
+            variables()?.expand_string("foo ${HOME"),
+            "invalid variable reference \"${HOME\" (use '$$' for a literal '$')"
+        );
+        assert_error!(
+            variables()?.expand_string("${FOO:-bar} baz"),
+            "invalid variable reference \"${FOO:-bar}\" (use '$$' for a literal '$')"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn detects_variable_references() -> R<()> {
+        let test = |yaml: &str| -> R<bool> {
+            Ok(references_variables(
+                YamlLoader::load_from_str(yaml)?[0].expect_object()?,
+            ))
+        };
+        assert!(test("{steps: ['cp ${HOME}']}")?);
+        assert!(test("{env: {FOO: '${TEST_DIR}/foo'}}")?);
+        assert!(!test("{steps: ['cp $${HOME} $1']}")?);
+        assert!(!test("{name: '${HOME}'}")?);
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_expanded_values_in_single_words() -> R<()> {
+        let variables = Variables::new(
+            Path::new("/my dir/it's \\ \"here\"/foo"),
+            Path::new("/tests/foo.test.yaml"),
+        )?;
+        let mut test = YamlLoader::load_from_str(
+            r#"{
+                arguments: '${SCRIPT_DIR}/input --flag',
+                steps: ['cp "${SCRIPT_DIR}/a" b', {command: 'ls ${SCRIPT_DIR}'}],
+                env: {DIR: '${SCRIPT_DIR}'}
+            }"#,
+        )?[0]
+            .expect_object()?
+            .clone();
+        variables.expand_test(&mut test)?;
+        let words = |yaml: &Yaml| -> R<Vec<OsString>> {
+            Parser::parse_arguments(yaml.expect_str()?.as_bytes())
+        };
+        let directory = r#"/my dir/it's \ "here""#;
+        assert_eq!(
+            words(&test[&Yaml::from_str("arguments")])?,
+            vec![
+                OsString::from(format!("{}/input", directory)),
+                OsString::from("--flag")
+            ]
+        );
+        let steps = test[&Yaml::from_str("steps")].expect_array()?;
+        assert_eq!(
+            words(&steps[0])?,
+            vec![
+                OsString::from("cp"),
+                OsString::from(format!("{}/a", directory)),
+                OsString::from("b")
+            ]
+        );
+        assert_eq!(
+            words(&steps[1]["command"])?,
+            vec![OsString::from("ls"), OsString::from(directory)]
+        );
+        assert_eq!(
+            test[&Yaml::from_str("env")]["DIR"],
+            Yaml::from_str(directory)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn escaping_roundtrips() -> R<()> {
+        for string in &["${HOME}", "$$", "$$$", "$'\\t' $1", "\\${X}", "$"] {
+            assert_eq!(&variables()?.expand_string(&escape_string(string))?, string);
+        }
+        assert_eq!(escape_string("$1 ${X} $$"), "$1 $${X} $$$");
+        Ok(())
+    }
+}
@@ -1,11 +1,18 @@
 use crate::R;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use linked_hash_map::LinkedHashMap;
+use std::collections::HashMap;
 use std::fmt;
 use std::io;
 use std::io::Cursor;
-use yaml_rust::{yaml::Hash, Yaml, YamlEmitter};
+use yaml_rust::parser::{MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle, TokenType};
+use yaml_rust::{yaml::Hash, Event, Yaml, YamlEmitter};
 
 pub trait YamlExt {
+    fn is_binary(&self) -> bool;
+
     fn expect_bytes(&self) -> R<Vec<u8>>;
 
     fn expect_str(&self) -> R<&str>;
@@ -18,9 +25,26 @@ pub trait YamlExt {
 }
 
 impl YamlExt for Yaml {
+    fn is_binary(&self) -> bool {
+        match self {
+            Yaml::Hash(object) => {
+                object.len() == 1 && object.contains_key(&Yaml::from_str("base64"))
+            }
+            _ => false,
+        }
+    }
+
     fn expect_bytes(&self) -> R<Vec<u8>> {
-        let str = self.expect_str()?;
-        Ok(str.as_bytes().to_vec())
+        if self.is_binary() {
+            let encoded = self["base64"].expect_str()?;
+            let without_whitespace: String = encoded.split_whitespace().collect();
+            Ok(STANDARD
+                .decode(&without_whitespace)
+                .map_err(|error| format!("invalid base64: {:?} ({})", encoded, error))?)
+        } else {
+            let str = self.expect_str()?;
+            Ok(str.as_bytes().to_vec())
+        }
     }
 
     fn expect_str(&self) -> R<&str> {
@@ -111,6 +135,140 @@ pub fn check_keys(known_keys: &[&str], object: &Hash) -> R<()> {
     Ok(())
 }
 
+pub fn serialize_bytes(bytes: &[u8]) -> Yaml {
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(string) => Yaml::String(string),
+        Err(_) => {
+            let mut object = Hash::new();
+            object.insert(
+                Yaml::from_str("base64"),
+                Yaml::String(STANDARD.encode(bytes)),
+            );
+            Yaml::Hash(object)
+        }
+    }
+}
+
+#[derive(Default)]
+struct Loader {
+    documents: Vec<Yaml>,
+    document_start: usize,
+    stack: Vec<(Yaml, usize)>,
+    keys: Vec<Option<Yaml>>,
+    anchors: HashMap<usize, Yaml>,
+    error: Option<String>,
+}
+
+impl Loader {
+    fn scalar(value: String, style: TScalarStyle, tag: Option<TokenType>) -> Yaml {
+        match tag {
+            Some(TokenType::Tag(ref handle, ref suffix))
+                if handle == "!!" && suffix == "binary" =>
+            {
+                let mut object = Hash::new();
+                object.insert(Yaml::from_str("base64"), Yaml::String(value));
+                Yaml::Hash(object)
+            }
+            _ if style != TScalarStyle::Plain => Yaml::String(value),
+            Some(TokenType::Tag(ref handle, ref suffix)) if handle == "!!" => {
+                match suffix.as_str() {
+                    "bool" => value.parse().map(Yaml::Boolean).unwrap_or(Yaml::BadValue),
+                    "int" => value.parse().map(Yaml::Integer).unwrap_or(Yaml::BadValue),
+                    "float" => {
+                        let real = Yaml::Real(value);
+                        if real.as_f64().is_some() {
+                            real
+                        } else {
+                            Yaml::BadValue
+                        }
+                    }
+                    "null" => match value.as_str() {
+                        "~" | "null" => Yaml::Null,
+                        _ => Yaml::BadValue,
+                    },
+                    _ => Yaml::String(value),
+                }
+            }
+            Some(_) => Yaml::String(value),
+            None => Yaml::from_str(&value),
+        }
+    }
+
+    fn insert(&mut self, node: Yaml, anchor: usize) -> R<()> {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+        match self.stack.last_mut() {
+            None => self.documents.push(node),
+            Some((Yaml::Array(array), _)) => array.push(node),
+            Some((Yaml::Hash(object), _)) => match self.keys.last_mut() {
+                Some(key) => match key.take() {
+                    None => *key = Some(node),
+                    Some(key) => {
+                        object.insert(key, node);
+                    }
+                },
+                None => Err("scriptkeeper bug: missing yaml key")?,
+            },
+            Some(_) => Err("scriptkeeper bug: unexpected yaml collection")?,
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: Event) -> R<()> {
+        match event {
+            Event::Scalar(value, style, anchor, tag) => {
+                self.insert(Loader::scalar(value, style, tag), anchor)?
+            }
+            Event::SequenceStart(anchor) => self.stack.push((Yaml::Array(vec![]), anchor)),
+            Event::MappingStart(anchor) => {
+                self.stack.push((Yaml::Hash(Hash::new()), anchor));
+                self.keys.push(None);
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Event::MappingEnd = event {
+                    self.keys.pop();
+                }
+                if let Some((node, anchor)) = self.stack.pop() {
+                    self.insert(node, anchor)?;
+                }
+            }
+            Event::DocumentEnd if self.documents.len() == self.document_start => {
+                self.documents.push(Yaml::BadValue)
+            }
+            Event::DocumentStart => self.document_start = self.documents.len(),
+            Event::Alias(anchor) => {
+                let node = self.anchors.get(&anchor).cloned().unwrap_or(Yaml::BadValue);
+                self.insert(node, 0)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl MarkedEventReceiver for Loader {
+    fn on_event(&mut self, event: Event, _marker: Marker) {
+        if self.error.is_none() {
+            if let Err(error) = self.handle_event(event) {
+                self.error = Some(error.to_string());
+            }
+        }
+    }
+}
+
+// yaml_rust drops tags when loading, so this loads values the same way
+// yaml_rust does, except for `!!binary` scalars. Those are loaded as
+// `{base64: ...}` objects instead, which `expect_bytes` decodes.
+pub fn load_yaml(source: &str) -> R<Vec<Yaml>> {
+    let mut loader = Loader::default();
+    Parser::new(source.chars()).load(&mut loader, true)?;
+    if let Some(error) = loader.error {
+        Err(error)?;
+    }
+    Ok(loader.documents)
+}
+
 fn adjust_yaml_output(input: Vec<u8>) -> Vec<u8> {
     let mut result: Vec<u8> = input.into_iter().skip(4).collect();
     result.push(b'\n');
@@ -134,3 +292,82 @@ pub fn write_yaml(output_stream: &mut dyn io::Write, yaml: &Yaml) -> R<()> {
     output_stream.write_all(&adjust_yaml_output(buffer.inner.into_inner()))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod binary {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn loads_like_yaml_rust() -> R<()> {
+        let source = "a: [1, 'two', true, ~]\nb: &anchor {c: 3.5}\nd: *anchor\n";
+        assert_eq!(load_yaml(source)?, YamlLoader::load_from_str(source)?);
+        Ok(())
+    }
+
+    #[test]
+    fn loads_tagged_scalars_like_yaml_rust() -> R<()> {
+        let source = r#"
+            - !!int "5"
+            - !!int 5
+            - !!int five
+            - !!null ~
+            - !!null null
+            - !!null foo
+            - !!bool true
+            - !!bool yes
+            - !!float 1.5
+            - !!float .inf
+            - !!float foo
+            - !!str 5
+            - !!seq foo
+            - !custom 5
+            - !custom "5"
+        "#;
+        assert_eq!(load_yaml(source)?, YamlLoader::load_from_str(source)?);
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_binary_scalars() -> R<()> {
+        let yaml = load_yaml("stdout: !!binary //4A\n")?;
+        assert_eq!(yaml[0]["stdout"].expect_bytes()?, b"\xff\xfe\x00");
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_base64_objects() -> R<()> {
+        let yaml = load_yaml("stdout: {base64: //4A}\n")?;
+        assert_eq!(yaml[0]["stdout"].expect_bytes()?, b"\xff\xfe\x00");
+        Ok(())
+    }
+
+    #[test]
+    fn ignores_whitespace_in_base64() -> R<()> {
+        let yaml = load_yaml("stdout: !!binary |\n  Zm9v\n  Yg==\n")?;
+        assert_eq!(yaml[0]["stdout"].expect_bytes()?, b"foob");
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_invalid_base64() -> R<()> {
+        let yaml = load_yaml("stdout: !!binary Zm9!\n")?;
+        assert_error!(
+            yaml[0]["stdout"].expect_bytes(),
+            "invalid base64: \"Zm9!\" (Invalid symbol 33, offset 3.)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serializes_invalid_utf8_as_base64() -> R<()> {
+        assert_eq!(serialize_bytes(b"foo"), Yaml::from_str("foo"));
+        assert_eq!(
+            serialize_bytes(b"\xff\xfe\x00").expect_bytes()?,
+            b"\xff\xfe\x00"
+        );
+        Ok(())
+    }
+}
@@ -68,6 +68,7 @@ impl Debugger {
             Close => vec![("fd", registers.rdi.to_string())],
             Stat => vec![("filename", Debugger::string(pid, registers.rdi))],
             Fstat => vec![("fd", registers.rdi.to_string())],
+            Lstat => vec![("filename", Debugger::string(pid, registers.rdi))],
             Mmap => vec![("fd", registers.r8.to_string())],
             Access => vec![
                 ("filename", Debugger::string(pid, registers.rdi)),
@@ -87,6 +88,7 @@ impl Debugger {
                 ("cmd", format!("{:?}", FcntlCmd::from(registers.rsi))),
             ],
             Openat => vec![("filename", Debugger::string(pid, registers.rsi))],
+            Newfstatat => vec![("filename", Debugger::string(pid, registers.rsi))],
             _ => vec![],
         }
     }
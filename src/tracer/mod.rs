@@ -1,13 +1,18 @@
 mod debugging;
+mod network_mock;
+pub mod sandbox;
+mod socket_address;
 pub mod stdio_redirecting;
 pub mod syscall;
 pub mod tracee_memory;
 
 use crate::context::Context;
+use crate::test_spec::FileChange;
 use crate::utils::parse_hashbang;
 use crate::R;
 use debugging::Debugger;
-use libc::user_regs_struct;
+use libc::{c_int, c_ulonglong, mode_t, user_regs_struct};
+use network_mock::NetworkMock;
 use nix;
 use nix::sys::ptrace;
 use nix::sys::ptrace::Options;
@@ -15,17 +20,20 @@ use nix::sys::signal;
 use nix::sys::signal::Signal;
 use nix::sys::wait::{wait, waitpid, WaitStatus};
 use nix::unistd::{execve, fork, getpid, ForkResult, Pid};
+use sandbox::Sandbox;
 use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::ffi::CString;
 use std::ffi::OsString;
 use std::fs;
+use std::io::Write;
+use std::net::SocketAddr;
 use std::os::unix::ffi::OsStrExt;
 use std::os::unix::ffi::OsStringExt;
 use std::panic;
 use std::path::{Path, PathBuf};
 use std::str;
-use stdio_redirecting::{Capture, Redirector};
+use stdio_redirecting::{Redirector, Stdio};
 use syscall::Syscall;
 use tempdir::TempDir;
 
@@ -38,8 +46,8 @@ pub trait SyscallMock {
         _registers: &user_regs_struct,
         _executable: PathBuf,
         _arguments: Vec<OsString>,
-    ) -> R<()> {
-        Ok(())
+    ) -> R<Option<c_int>> {
+        Ok(None)
     }
 
     fn handle_exited(&mut self, _pid: Pid, _exitcode: i32) -> R<()> {
@@ -50,15 +58,52 @@ pub trait SyscallMock {
         Ok(())
     }
 
-    fn handle_stat_exit(
+    fn handle_id_exit(
         &self,
         _pid: Pid,
         _registers: &user_regs_struct,
+        _syscall: &Syscall,
+    ) -> R<()> {
+        Ok(())
+    }
+
+    fn handle_getgroups_exit(&self, _pid: Pid, _registers: &user_regs_struct) -> R<()> {
+        Ok(())
+    }
+
+    fn handle_uname_exit(&self, _pid: Pid, _registers: &user_regs_struct) -> R<()> {
+        Ok(())
+    }
+
+    fn handle_stat_exit(
+        &mut self,
+        _pid: Pid,
+        _registers: &user_regs_struct,
+        _statbuf: c_ulonglong,
         _filename: PathBuf,
+        _follow_symlinks: bool,
     ) -> R<()> {
         Ok(())
     }
 
+    fn handle_access_exit(
+        &mut self,
+        _pid: Pid,
+        _registers: &user_regs_struct,
+        _filename: PathBuf,
+        _mode: c_int,
+    ) -> R<()> {
+        Ok(())
+    }
+
+    fn handle_file_change(&mut self, _pid: Pid, _change: FileChange) -> R<()> {
+        Ok(())
+    }
+
+    fn handle_blocked_syscall(&mut self, _pid: Pid, _message: String) -> R<()> {
+        Ok(())
+    }
+
     fn handle_end(self, exitcode: i32, redirector: &Redirector) -> R<Self::Result>;
 }
 
@@ -69,33 +114,46 @@ pub enum SyscallStop {
 }
 
 pub struct Tracer {
+    context: Context,
     tracee_pid: Pid,
+    sandbox: Sandbox,
+    network_mock: NetworkMock,
     entered_syscalls: HashMap<Pid, Syscall>,
+    blocked_syscalls: HashMap<Pid, (Syscall, c_int)>,
+    redirected_paths: HashMap<Pid, c_ulonglong>,
 }
 
 impl Tracer {
-    fn new(tracee_pid: Pid) -> Self {
-        Tracer {
+    fn new(context: &Context, tracee_pid: Pid, sandbox: Sandbox) -> R<Self> {
+        Ok(Tracer {
+            context: context.clone(),
             tracee_pid,
+            network_mock: NetworkMock::new(&sandbox.network_mappings)?,
+            sandbox,
             entered_syscalls: HashMap::new(),
-        }
+            blocked_syscalls: HashMap::new(),
+            redirected_paths: HashMap::new(),
+        })
     }
 
     fn execve_params(
         interpreter: &Option<PathBuf>,
         program: &Path,
-        args: Vec<String>,
-        env: HashMap<String, String>,
+        args: Vec<OsString>,
+        env: HashMap<OsString, OsString>,
     ) -> R<(CString, Vec<CString>, Vec<CString>)> {
         let c_executable = CString::new(program.as_os_str().as_bytes())?;
         let mut c_args = VecDeque::new();
         c_args.push_back(c_executable.clone());
         for arg in &args {
-            c_args.push_back(CString::new(arg.clone())?);
+            c_args.push_back(CString::new(arg.as_bytes())?);
         }
         let mut c_env = vec![];
         for (key, value) in env {
-            c_env.push(CString::new(format!("{}={}", key, value))?);
+            let mut variable = key.into_vec();
+            variable.push(b'=');
+            variable.extend(value.into_vec());
+            c_env.push(CString::new(variable)?);
         }
         Ok(match interpreter {
             Some(interpreter) => {
@@ -132,8 +190,8 @@ impl Tracer {
     fn execve(
         interpreter: &Option<PathBuf>,
         program: &Path,
-        args: Vec<String>,
-        env: HashMap<String, String>,
+        args: Vec<OsString>,
+        env: HashMap<OsString, OsString>,
     ) -> R<()> {
         let (c_executable, c_args, c_env) = Tracer::execve_params(interpreter, program, args, env)?;
         execve(&c_executable, &c_args, &c_env)
@@ -141,16 +199,18 @@ impl Tracer {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn run_against_mock<MockResult>(
         context: &Context,
         interpreter: &Option<PathBuf>,
         program: &Path,
-        args: Vec<String>,
-        env: HashMap<String, String>,
-        capture: Capture,
+        args: Vec<OsString>,
+        env: HashMap<OsString, OsString>,
+        stdio: Stdio,
+        sandbox: &Sandbox,
         mut syscall_mock: impl SyscallMock<Result = MockResult>,
     ) -> R<MockResult> {
-        let redirector = Redirector::new(context, capture)?;
+        let redirector = Redirector::new(context, stdio)?;
         fork_with_child_errors(
             || {
                 redirector.child_redirect_streams()?;
@@ -169,7 +229,7 @@ impl Tracer {
                         | Options::PTRACE_O_TRACEVFORK,
                 )?;
                 ptrace::syscall(tracee_pid)?;
-                let mut tracer = Tracer::new(tracee_pid);
+                let mut tracer = Tracer::new(context, tracee_pid, sandbox.clone())?;
                 let exitcode = tracer.trace(&mut syscall_mock)?;
                 join()?;
                 syscall_mock.handle_end(exitcode, &redirector)
@@ -205,10 +265,19 @@ impl Tracer {
     ) -> R<()> {
         if let WaitStatus::PtraceSyscall(pid, ..) = status {
             let registers = ptrace::getregs(pid)?;
-            let syscall = Syscall::from(registers);
+            let blocked_syscall = self.blocked_syscalls.remove(&pid);
+            let syscall = match &blocked_syscall {
+                Some((blocked_syscall, _)) => blocked_syscall.clone(),
+                None => Syscall::from(registers),
+            };
             let syscall_stop = self.update_syscall_state(pid, &syscall)?;
             debugger.log_syscall(pid, &syscall_stop, &syscall, || -> R<()> {
-                self.handle_syscall(syscall_mock, pid, &syscall_stop, &syscall, &registers)
+                match blocked_syscall {
+                    Some((_, errno)) => Tracer::fail_blocked_syscall(pid, registers, errno),
+                    None => {
+                        self.handle_syscall(syscall_mock, pid, &syscall_stop, &syscall, &registers)
+                    }
+                }
             })?;
         }
         Ok(())
@@ -231,6 +300,244 @@ impl Tracer {
         })
     }
 
+    fn peek_path(pid: Pid, address: c_ulonglong) -> R<PathBuf> {
+        Ok(PathBuf::from(OsString::from_vec(
+            tracee_memory::peek_string(pid, address)?,
+        )))
+    }
+
+    // Invalid file descriptors and ones that don't refer to paths (e.g.
+    // 'pipe:[1234]') make the syscall fail anyway, so they're treated as
+    // unknown paths.
+    fn fd_path(pid: Pid, fd: c_ulonglong) -> Option<PathBuf> {
+        fs::read_link(format!("/proc/{}/fd/{}", pid, fd as c_int))
+            .ok()
+            .filter(|path| path.is_absolute())
+    }
+
+    fn peek_path_at(pid: Pid, dirfd: c_ulonglong, address: c_ulonglong) -> R<Option<PathBuf>> {
+        let path = Tracer::peek_path(pid, address)?;
+        Ok(if path.is_relative() && dirfd as c_int != libc::AT_FDCWD {
+            Tracer::fd_path(pid, dirfd).map(|directory| directory.join(path))
+        } else {
+            Some(path)
+        })
+    }
+
+    fn opens_for_writing(flags: c_ulonglong) -> bool {
+        let flags = flags as c_int;
+        flags & libc::O_ACCMODE != libc::O_RDONLY || flags & (libc::O_CREAT | libc::O_TRUNC) != 0
+    }
+
+    // openat2 passes its flags as the first field of a 'struct open_how'.
+    fn open_how_flags(pid: Pid, address: c_ulonglong) -> R<c_ulonglong> {
+        let mut flags = [0; 8];
+        flags.copy_from_slice(&tracee_memory::peek_bytes(pid, address, 8)?);
+        Ok(c_ulonglong::from_ne_bytes(flags))
+    }
+
+    fn is_virtual_file(path: &Path) -> bool {
+        const DEVICES: &[&str] = &[
+            "/dev/null",
+            "/dev/zero",
+            "/dev/full",
+            "/dev/random",
+            "/dev/urandom",
+            "/dev/tty",
+            "/dev/ptmx",
+            "/dev/stdin",
+            "/dev/stdout",
+            "/dev/stderr",
+        ];
+        DEVICES.iter().any(|device| path == Path::new(device))
+            || path.starts_with("/dev/fd")
+            || path.starts_with("/dev/pts")
+            || path.starts_with("/proc")
+    }
+
+    fn file_change(
+        pid: Pid,
+        syscall: &Syscall,
+        registers: &user_regs_struct,
+    ) -> R<Option<FileChange>> {
+        let change = match syscall {
+            Syscall::Open if Tracer::opens_for_writing(registers.rsi) => {
+                Some(FileChange::Write(Tracer::peek_path(pid, registers.rdi)?))
+            }
+            Syscall::Openat if Tracer::opens_for_writing(registers.rdx) => {
+                Tracer::peek_path_at(pid, registers.rdi, registers.rsi)?.map(FileChange::Write)
+            }
+            Syscall::Openat2
+                if Tracer::opens_for_writing(Tracer::open_how_flags(pid, registers.rdx)?) =>
+            {
+                Tracer::peek_path_at(pid, registers.rdi, registers.rsi)?.map(FileChange::Write)
+            }
+            Syscall::Creat => Some(FileChange::Write(Tracer::peek_path(pid, registers.rdi)?)),
+            Syscall::Unlink => Some(FileChange::Delete(Tracer::peek_path(pid, registers.rdi)?)),
+            Syscall::Unlinkat => {
+                Tracer::peek_path_at(pid, registers.rdi, registers.rsi)?.map(|path| {
+                    if registers.rdx as c_int & libc::AT_REMOVEDIR != 0 {
+                        FileChange::Rmdir(path)
+                    } else {
+                        FileChange::Delete(path)
+                    }
+                })
+            }
+            Syscall::Rmdir => Some(FileChange::Rmdir(Tracer::peek_path(pid, registers.rdi)?)),
+            Syscall::Rename => Some(FileChange::Rename(
+                Tracer::peek_path(pid, registers.rdi)?,
+                Tracer::peek_path(pid, registers.rsi)?,
+            )),
+            Syscall::Renameat | Syscall::Renameat2 => match (
+                Tracer::peek_path_at(pid, registers.rdi, registers.rsi)?,
+                Tracer::peek_path_at(pid, registers.rdx, registers.r10)?,
+            ) {
+                (Some(from), Some(to)) => Some(FileChange::Rename(from, to)),
+                _ => None,
+            },
+            Syscall::Mkdir => Some(FileChange::Mkdir(Tracer::peek_path(pid, registers.rdi)?)),
+            Syscall::Mkdirat => {
+                Tracer::peek_path_at(pid, registers.rdi, registers.rsi)?.map(FileChange::Mkdir)
+            }
+            Syscall::Chmod => Some(FileChange::Chmod(
+                Tracer::peek_path(pid, registers.rdi)?,
+                Some(registers.rsi as mode_t & 0o7777),
+            )),
+            Syscall::Fchmodat => Tracer::peek_path_at(pid, registers.rdi, registers.rsi)?
+                .map(|path| FileChange::Chmod(path, Some(registers.rdx as mode_t & 0o7777))),
+            Syscall::Truncate => Some(FileChange::Truncate(Tracer::peek_path(pid, registers.rdi)?)),
+            Syscall::Ftruncate => Tracer::fd_path(pid, registers.rdi).map(FileChange::Truncate),
+            _ => None,
+        };
+        Ok(change.filter(|change| !Tracer::is_virtual_file(change.path())))
+    }
+
+    fn connection(
+        pid: Pid,
+        syscall: &Syscall,
+        registers: &user_regs_struct,
+    ) -> R<Option<(c_ulonglong, usize, SocketAddr)>> {
+        let (pointer, length) = match syscall {
+            Syscall::Connect => (registers.rsi, registers.rdx as usize),
+            Syscall::Sendto if registers.r8 != 0 => (registers.r8, registers.r9 as usize),
+            _ => return Ok(None),
+        };
+        Ok(
+            socket_address::decode(&tracee_memory::peek_bytes(pid, pointer, length)?)
+                .map(|address| (pointer, length, address)),
+        )
+    }
+
+    fn redirect_connection(
+        pid: Pid,
+        pointer: c_ulonglong,
+        length: usize,
+        target: &SocketAddr,
+    ) -> R<()> {
+        let bytes = socket_address::encode(target);
+        if bytes.len() > length {
+            Err(format!(
+                "redirecting to {} failed: socket address buffer too small",
+                target
+            ))?;
+        }
+        tracee_memory::poke_bytes(pid, pointer, &bytes)
+    }
+
+    fn path_register<'a>(
+        syscall: &Syscall,
+        registers: &'a mut user_regs_struct,
+    ) -> Option<&'a mut c_ulonglong> {
+        match syscall {
+            Syscall::Open => Some(&mut registers.rdi),
+            Syscall::Openat => Some(&mut registers.rsi),
+            _ => None,
+        }
+    }
+
+    // The replacement path is written to the stack below the red zone, which
+    // is unused while the tracee is in a syscall. The original pointer is
+    // restored when the syscall exits.
+    fn redirect_hosts_file(
+        &mut self,
+        pid: Pid,
+        syscall: &Syscall,
+        registers: &user_regs_struct,
+    ) -> R<()> {
+        if let Some(hosts_file) = self.network_mock.hosts_file() {
+            let flags = match syscall {
+                Syscall::Open => registers.rsi,
+                Syscall::Openat => registers.rdx,
+                _ => return Ok(()),
+            };
+            let mut new_registers = *registers;
+            if let Some(pointer) = Tracer::path_register(syscall, &mut new_registers) {
+                if !Tracer::opens_for_writing(flags)
+                    && tracee_memory::peek_string(pid, *pointer)? == b"/etc/hosts"
+                {
+                    let hosts_file = hosts_file.as_os_str().as_bytes();
+                    let size = (hosts_file.len() as c_ulonglong / 8 + 1) * 8;
+                    let scratch = (registers.rsp - 128 - size) & !0xf;
+                    tracee_memory::poke_string(pid, scratch, hosts_file, size)
+                        .map_err(|error| format!("redirecting /etc/hosts failed: {}", error))?;
+                    self.redirected_paths.insert(pid, *pointer);
+                    *pointer = scratch;
+                    ptrace::setregs(pid, new_registers)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn restore_redirected_path(
+        &mut self,
+        pid: Pid,
+        syscall: &Syscall,
+        registers: &user_regs_struct,
+    ) -> R<()> {
+        if let Some(original) = self.redirected_paths.remove(&pid) {
+            let mut registers = *registers;
+            if let Some(pointer) = Tracer::path_register(syscall, &mut registers) {
+                *pointer = original;
+                ptrace::setregs(pid, registers)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn block_syscall(
+        &mut self,
+        pid: Pid,
+        syscall: &Syscall,
+        registers: &user_regs_struct,
+        errno: c_int,
+        message: &str,
+    ) -> R<()> {
+        writeln!(self.context.stderr(), "scriptkeeper: {}", message)?;
+        self.fail_syscall(pid, syscall, registers, errno)
+    }
+
+    fn fail_syscall(
+        &mut self,
+        pid: Pid,
+        syscall: &Syscall,
+        registers: &user_regs_struct,
+        errno: c_int,
+    ) -> R<()> {
+        let mut registers = *registers;
+        registers.orig_rax = -1i64 as c_ulonglong;
+        ptrace::setregs(pid, registers)?;
+        self.blocked_syscalls.insert(pid, (syscall.clone(), errno));
+        Ok(())
+    }
+
+    fn fail_blocked_syscall(pid: Pid, registers: user_regs_struct, errno: c_int) -> R<()> {
+        let mut registers = registers;
+        registers.rax = -errno as c_ulonglong;
+        ptrace::setregs(pid, registers)?;
+        Ok(())
+    }
+
     fn handle_syscall<MockResult>(
         &mut self,
         syscall_mock: &mut SyscallMock<Result = MockResult>,
@@ -250,21 +557,98 @@ impl Tracer {
                         .into_iter()
                         .map(OsString::from_vec)
                         .collect();
-                    syscall_mock.handle_execve_enter(pid, registers, executable, arguments)?;
+                    if let Some(errno) =
+                        syscall_mock.handle_execve_enter(pid, registers, executable, arguments)?
+                    {
+                        self.fail_syscall(pid, syscall, registers, errno)?;
+                    }
                 }
             }
             (Syscall::Getcwd, SyscallStop::Exit) => {
                 syscall_mock.handle_getcwd_exit(pid, registers)?
             }
-            (Syscall::Stat, SyscallStop::Exit) => {
-                let filename = PathBuf::from(OsString::from_vec(tracee_memory::peek_string(
+            (Syscall::Getuid, SyscallStop::Exit)
+            | (Syscall::Geteuid, SyscallStop::Exit)
+            | (Syscall::Getgid, SyscallStop::Exit)
+            | (Syscall::Getegid, SyscallStop::Exit) => {
+                syscall_mock.handle_id_exit(pid, registers, syscall)?
+            }
+            (Syscall::Getgroups, SyscallStop::Exit) => {
+                syscall_mock.handle_getgroups_exit(pid, registers)?
+            }
+            (Syscall::Uname, SyscallStop::Exit) => {
+                syscall_mock.handle_uname_exit(pid, registers)?
+            }
+            (Syscall::Stat, SyscallStop::Exit) | (Syscall::Lstat, SyscallStop::Exit) => {
+                let filename = Tracer::peek_path(pid, registers.rdi)?;
+                let follow_symlinks = *syscall == Syscall::Stat;
+                syscall_mock.handle_stat_exit(
+                    pid,
+                    registers,
+                    registers.rsi,
+                    filename,
+                    follow_symlinks,
+                )?
+            }
+            (Syscall::Newfstatat, SyscallStop::Exit) => {
+                let filename = Tracer::peek_path(pid, registers.rsi)?;
+                let follow_symlinks =
+                    registers.r10 & (libc::AT_SYMLINK_NOFOLLOW as c_ulonglong) == 0;
+                syscall_mock.handle_stat_exit(
                     pid,
-                    registers.rdi,
-                )?));
-                syscall_mock.handle_stat_exit(pid, registers, filename)?
+                    registers,
+                    registers.rdx,
+                    filename,
+                    follow_symlinks,
+                )?
+            }
+            (Syscall::Access, SyscallStop::Exit) => {
+                let filename = Tracer::peek_path(pid, registers.rdi)?;
+                syscall_mock.handle_access_exit(pid, registers, filename, registers.rsi as c_int)?
+            }
+            (Syscall::Faccessat, SyscallStop::Exit) | (Syscall::Faccessat2, SyscallStop::Exit) => {
+                let filename = Tracer::peek_path(pid, registers.rsi)?;
+                syscall_mock.handle_access_exit(pid, registers, filename, registers.rdx as c_int)?
             }
             _ => {}
         }
+        match syscall_stop {
+            SyscallStop::Enter => {
+                if self.sandbox.safe_mode {
+                    if let Some(change) = Tracer::file_change(pid, syscall, registers)? {
+                        if self.sandbox.blocks(pid, &change)? {
+                            let message = format!("blocked in safe mode: {}", change);
+                            self.block_syscall(pid, syscall, registers, libc::EPERM, &message)?;
+                            syscall_mock.handle_blocked_syscall(pid, message)?;
+                        }
+                    }
+                }
+                self.redirect_hosts_file(pid, syscall, registers)?;
+                if let Some((pointer, length, address)) =
+                    Tracer::connection(pid, syscall, registers)?
+                {
+                    if let Some(target) = self.network_mock.redirect(&address) {
+                        Tracer::redirect_connection(pid, pointer, length, &target)?;
+                    } else if self.sandbox.blocks_connection(&address) {
+                        let message = format!(
+                            "blocked network access: {} {}",
+                            format!("{:?}", syscall).to_lowercase(),
+                            address
+                        );
+                        self.block_syscall(pid, syscall, registers, libc::ENETUNREACH, &message)?;
+                        syscall_mock.handle_blocked_syscall(pid, message)?;
+                    }
+                }
+            }
+            SyscallStop::Exit => {
+                self.restore_redirected_path(pid, syscall, registers)?;
+                if (registers.rax as i64) >= 0 {
+                    if let Some(change) = Tracer::file_change(pid, syscall, registers)? {
+                        syscall_mock.handle_file_change(pid, change)?;
+                    }
+                }
+            }
+        }
         Ok(())
     }
 }
@@ -273,17 +657,41 @@ impl Tracer {
 mod test_tracer {
     use super::*;
 
+    mod is_virtual_file {
+        use super::*;
+
+        #[test]
+        fn includes_harmless_devices_and_proc() {
+            for path in &[
+                "/dev/null",
+                "/dev/tty",
+                "/dev/fd/3",
+                "/dev/pts/0",
+                "/proc/self/fd/1",
+            ] {
+                assert!(Tracer::is_virtual_file(Path::new(path)), "{}", path);
+            }
+        }
+
+        #[test]
+        fn excludes_other_devices() {
+            for path in &["/dev/sda", "/dev/shm/file", "/dev/nullx", "/devfoo"] {
+                assert!(!Tracer::is_virtual_file(Path::new(path)), "{}", path);
+            }
+        }
+    }
+
     mod update_syscall_state {
         use super::*;
         use test_utils::assert_error;
 
-        fn tracer() -> Tracer {
-            Tracer::new(Pid::from_raw(1))
+        fn tracer() -> R<Tracer> {
+            Tracer::new(&Context::new()?, Pid::from_raw(1), Sandbox::default())
         }
 
         #[test]
         fn returns_entry_for_new_syscalls() -> R<()> {
-            let mut tracer = tracer();
+            let mut tracer = tracer()?;
             assert_eq!(
                 tracer.update_syscall_state(Pid::from_raw(2), &Syscall::Unknown(23))?,
                 SyscallStop::Enter
@@ -293,7 +701,7 @@ mod test_tracer {
 
         #[test]
         fn tracks_entry_and_exit_for_multiple_syscalls() -> R<()> {
-            let mut tracer = tracer();
+            let mut tracer = tracer()?;
             tracer.update_syscall_state(Pid::from_raw(2), &Syscall::Unknown(23))?;
             assert_eq!(
                 tracer.update_syscall_state(Pid::from_raw(2), &Syscall::Unknown(23))?,
@@ -315,7 +723,7 @@ mod test_tracer {
 
             #[test]
             fn tracks_entry_and_exit_for_multiple_syscalls() -> R<()> {
-                let mut tracer = tracer();
+                let mut tracer = tracer()?;
                 tracer.update_syscall_state(Pid::from_raw(42), &Syscall::Unknown(23))?;
                 assert_eq!(
                     tracer.update_syscall_state(Pid::from_raw(2), &Syscall::Unknown(23))?,
@@ -339,7 +747,7 @@ mod test_tracer {
 
         #[test]
         fn complains_when_exiting_with_a_different_syscall() -> R<()> {
-            let mut tracer = tracer();
+            let mut tracer = tracer()?;
             tracer.update_syscall_state(Pid::from_raw(2), &Syscall::Unknown(1))?;
             assert_error!(
                 tracer.update_syscall_state(Pid::from_raw(2), &Syscall::Unknown(2)),
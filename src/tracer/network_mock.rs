@@ -0,0 +1,152 @@
+use crate::test_spec::destination::NetworkMapping;
+use crate::utils::short_temp_files::ShortTempFile;
+use crate::R;
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub struct NetworkMock {
+    redirects: Vec<(IpAddr, Option<u16>, SocketAddr)>,
+    hosts_file: Option<ShortTempFile>,
+}
+
+impl NetworkMock {
+    fn placeholder_ip(index: usize, target: &IpAddr) -> IpAddr {
+        match target {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::new(
+                198,
+                18,
+                (index / 254) as u8,
+                (index % 254 + 1) as u8,
+            )),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::new(0x100, 0, 0, 0, 0, 0, 0, index as u16 + 1)),
+        }
+    }
+
+    pub fn new(mappings: &[NetworkMapping]) -> R<NetworkMock> {
+        let mut redirects = vec![];
+        let mut placeholders: HashMap<&str, IpAddr> = HashMap::new();
+        let mut hosts_entries = String::new();
+        for (index, mapping) in mappings.iter().enumerate() {
+            let target_ip = mapping.target_ip()?;
+            let source_ip = match mapping.from.host.parse::<IpAddr>() {
+                Ok(source_ip) => {
+                    if source_ip.is_ipv4() != target_ip.is_ipv4() {
+                        Err(format!(
+                            "network mappings can't change the address family: {}",
+                            mapping
+                        ))?;
+                    }
+                    source_ip
+                }
+                Err(_) => match placeholders.get(mapping.from.host.as_str()) {
+                    Some(placeholder) => *placeholder,
+                    None => {
+                        let placeholder = NetworkMock::placeholder_ip(index, &target_ip);
+                        hosts_entries.push_str(&format!("{} {}\n", placeholder, mapping.from.host));
+                        placeholders.insert(&mapping.from.host, placeholder);
+                        placeholder
+                    }
+                },
+            };
+            redirects.push((
+                source_ip,
+                mapping.from.port,
+                SocketAddr::new(target_ip, mapping.to.port.unwrap_or(0)),
+            ));
+        }
+        let hosts_file = if hosts_entries.is_empty() {
+            None
+        } else {
+            hosts_entries.push_str(&fs::read_to_string("/etc/hosts").unwrap_or_default());
+            Some(ShortTempFile::new(hosts_entries.as_bytes())?)
+        };
+        Ok(NetworkMock {
+            redirects,
+            hosts_file,
+        })
+    }
+
+    pub fn hosts_file(&self) -> Option<PathBuf> {
+        self.hosts_file.as_ref().map(|file| file.path())
+    }
+
+    pub fn redirect(&self, address: &SocketAddr) -> Option<SocketAddr> {
+        self.redirects
+            .iter()
+            .find(|(ip, port, _)| {
+                *ip == address.ip()
+                    && match port {
+                        Some(port) => *port == address.port(),
+                        None => true,
+                    }
+            })
+            .map(|(_, _, target)| {
+                let port = if target.port() == 0 {
+                    address.port()
+                } else {
+                    target.port()
+                };
+                SocketAddr::new(target.ip(), port)
+            })
+    }
+}
+
+#[cfg(test)]
+mod network_mock {
+    use super::*;
+    use test_utils::assert_error;
+
+    fn network_mock(mappings: &[&str]) -> R<NetworkMock> {
+        NetworkMock::new(
+            &mappings
+                .iter()
+                .map(|mapping| NetworkMapping::parse(mapping))
+                .collect::<R<Vec<NetworkMapping>>>()?,
+        )
+    }
+
+    #[test]
+    fn redirects_ip_addresses() -> R<()> {
+        let mock = network_mock(&["10.0.0.1:443 -> 127.0.0.1:8080"])?;
+        assert_eq!(
+            mock.redirect(&"10.0.0.1:443".parse()?),
+            Some("127.0.0.1:8080".parse()?)
+        );
+        assert_eq!(mock.redirect(&"10.0.0.1:80".parse()?), None);
+        assert_eq!(mock.hosts_file(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_the_port_when_the_target_has_none() -> R<()> {
+        let mock = network_mock(&["10.0.0.1 -> 127.0.0.1"])?;
+        assert_eq!(
+            mock.redirect(&"10.0.0.1:443".parse()?),
+            Some("127.0.0.1:443".parse()?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resolves_host_names_to_placeholder_addresses_in_a_hosts_file() -> R<()> {
+        let mock = network_mock(&["api.example.com:443 -> 127.0.0.1:8080"])?;
+        let hosts_file = fs::read_to_string(mock.hosts_file().unwrap())?;
+        assert!(hosts_file.starts_with("198.18.0.1 api.example.com\n"));
+        assert_eq!(
+            mock.redirect(&"198.18.0.1:443".parse()?),
+            Some("127.0.0.1:8080".parse()?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disallows_changing_the_address_family() {
+        assert_error!(
+            network_mock(&["10.0.0.1 -> ::1"]),
+            "network mappings can't change the address family: 10.0.0.1 -> [::1]"
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::test_spec::{FileChange, NetworkMapping, ResolvedDestination};
+use crate::R;
+use nix::unistd::Pid;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Sandbox {
+    pub safe_mode: bool,
+    pub allowed_writes: Vec<PathBuf>,
+    pub block_network: bool,
+    pub allowed_destinations: Vec<ResolvedDestination>,
+    pub network_mappings: Vec<NetworkMapping>,
+}
+
+impl Sandbox {
+    fn normalize(path: &Path) -> PathBuf {
+        let mut result = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    result.pop();
+                }
+                component => result.push(component.as_os_str()),
+            }
+        }
+        result
+    }
+
+    // Resolves symlinks in the closest existing parent directory, so that
+    // symlinks inside of allowed paths can't be used to escape them. The last
+    // component is only resolved when the operation follows it.
+    fn resolve(path: &Path, follow_last: bool) -> PathBuf {
+        let mut existing = path.to_path_buf();
+        if follow_last {
+            for _ in 0..40 {
+                match fs::read_link(&existing) {
+                    Ok(target) => {
+                        existing = existing
+                            .parent()
+                            .unwrap_or_else(|| Path::new("/"))
+                            .join(target)
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+        let path = existing.clone();
+        let mut rest = vec![];
+        if !follow_last {
+            if let Some(name) = path.file_name() {
+                rest.push(name.to_os_string());
+                existing.pop();
+            }
+        }
+        loop {
+            if let Ok(mut result) = fs::canonicalize(&existing) {
+                for component in rest.iter().rev() {
+                    result.push(component);
+                }
+                return Sandbox::normalize(&result);
+            }
+            match existing.file_name() {
+                Some(name) => {
+                    rest.push(name.to_os_string());
+                    existing.pop();
+                }
+                None => return Sandbox::normalize(&path),
+            }
+        }
+    }
+
+    fn is_allowed(&self, path: &Path, follow_last: bool) -> bool {
+        let path = Sandbox::resolve(path, follow_last);
+        self.allowed_writes
+            .iter()
+            .any(|allowed| path.starts_with(Sandbox::resolve(allowed, true)))
+    }
+
+    fn blocks_change(&self, cwd: &Path, change: &FileChange) -> bool {
+        let is_allowed = |path: &Path, follow_last| self.is_allowed(&cwd.join(path), follow_last);
+        match change {
+            FileChange::Write(path) | FileChange::Truncate(path) => !is_allowed(path, true),
+            FileChange::Delete(path) | FileChange::Rmdir(path) => !is_allowed(path, false),
+            FileChange::Rename(from, to) => !is_allowed(from, false) || !is_allowed(to, false),
+            FileChange::Mkdir(_) | FileChange::Chmod(_, _) => false,
+        }
+    }
+
+    pub fn blocks(&self, pid: Pid, change: &FileChange) -> R<bool> {
+        Ok(if self.safe_mode {
+            let cwd = fs::read_link(format!("/proc/{}/cwd", pid))?;
+            self.blocks_change(&cwd, change)
+        } else {
+            false
+        })
+    }
+
+    pub fn blocks_connection(&self, address: &SocketAddr) -> bool {
+        self.block_network
+            && !self
+                .allowed_destinations
+                .iter()
+                .any(|destination| destination.matches(address))
+    }
+}
+
+#[cfg(test)]
+mod blocks_change {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempdir::TempDir;
+
+    fn sandbox() -> Sandbox {
+        Sandbox {
+            safe_mode: true,
+            allowed_writes: vec![PathBuf::from("/tmp/out")],
+            ..Sandbox::default()
+        }
+    }
+
+    #[test]
+    fn blocks_destructive_changes_outside_of_the_allowed_paths() {
+        let cwd = PathBuf::from("/project");
+        for change in vec![
+            FileChange::Write(PathBuf::from("/etc/passwd")),
+            FileChange::Delete(PathBuf::from("file")),
+            FileChange::Rmdir(PathBuf::from("/project/dir")),
+            FileChange::Truncate(PathBuf::from("/tmp/other")),
+        ] {
+            assert!(sandbox().blocks_change(&cwd, &change), "{}", change);
+        }
+    }
+
+    #[test]
+    fn allows_changes_inside_of_the_allowed_paths() {
+        let cwd = PathBuf::from("/tmp/out");
+        assert!(!sandbox().blocks_change(&cwd, &FileChange::Write(PathBuf::from("file"))));
+        assert!(!sandbox().blocks_change(&cwd, &FileChange::Delete(PathBuf::from("/tmp/out/a/b"))));
+    }
+
+    #[test]
+    fn does_not_allow_to_escape_the_allowed_paths_with_parent_directories() {
+        let cwd = PathBuf::from("/tmp/out");
+        assert!(sandbox().blocks_change(&cwd, &FileChange::Write(PathBuf::from("../foo"))));
+    }
+
+    #[test]
+    fn does_not_allow_to_escape_the_allowed_paths_with_symlinks() -> R<()> {
+        let tempdir = TempDir::new("test")?;
+        let allowed = tempdir.path().join("allowed");
+        let outside = tempdir.path().join("outside");
+        fs::create_dir(&allowed)?;
+        fs::create_dir(&outside)?;
+        symlink(&outside, allowed.join("dir"))?;
+        symlink(outside.join("file"), allowed.join("file"))?;
+        let sandbox = Sandbox {
+            safe_mode: true,
+            allowed_writes: vec![allowed.clone()],
+            ..Sandbox::default()
+        };
+        let write = |path: &str| FileChange::Write(PathBuf::from(path));
+        assert!(sandbox.blocks_change(&allowed, &write("dir/file")));
+        assert!(sandbox.blocks_change(&allowed, &write("dir/new/file")));
+        assert!(sandbox.blocks_change(&allowed, &write("file")));
+        assert!(!sandbox.blocks_change(&allowed, &FileChange::Delete(PathBuf::from("file"))));
+        assert!(!sandbox.blocks_change(&allowed, &write("other")));
+        Ok(())
+    }
+
+    #[test]
+    fn blocks_renames_when_either_path_is_outside_of_the_allowed_paths() {
+        let cwd = PathBuf::from("/tmp/out");
+        assert!(sandbox().blocks_change(
+            &cwd,
+            &FileChange::Rename(PathBuf::from("a"), PathBuf::from("/home/b"))
+        ));
+        assert!(sandbox().blocks_change(
+            &cwd,
+            &FileChange::Rename(PathBuf::from("/home/a"), PathBuf::from("b"))
+        ));
+        assert!(!sandbox().blocks_change(
+            &cwd,
+            &FileChange::Rename(PathBuf::from("a"), PathBuf::from("b"))
+        ));
+    }
+
+    #[test]
+    fn does_not_block_mkdir_and_chmod() {
+        let cwd = PathBuf::from("/project");
+        assert!(!sandbox().blocks_change(&cwd, &FileChange::Mkdir(PathBuf::from("dir"))));
+        assert!(!sandbox().blocks_change(&cwd, &FileChange::Chmod(PathBuf::from("file"), None)));
+    }
+}
+
+#[cfg(test)]
+mod blocks_connection {
+    use super::*;
+    use crate::test_spec::Destination;
+
+    fn sandbox(allowed_destinations: &[&str]) -> R<Sandbox> {
+        Ok(Sandbox {
+            block_network: true,
+            allowed_destinations: allowed_destinations
+                .iter()
+                .map(|destination| Ok(Destination::parse(destination)?.resolve()))
+                .collect::<R<Vec<ResolvedDestination>>>()?,
+            ..Sandbox::default()
+        })
+    }
+
+    #[test]
+    fn blocks_all_connections_by_default() -> R<()> {
+        assert!(sandbox(&[])?.blocks_connection(&"93.184.216.34:443".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn allows_connections_to_allowed_destinations() -> R<()> {
+        let sandbox = sandbox(&["127.0.0.1:8080"])?;
+        assert!(!sandbox.blocks_connection(&"127.0.0.1:8080".parse()?));
+        assert!(sandbox.blocks_connection(&"127.0.0.1:8081".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_block_anything_when_the_network_is_not_blocked() -> R<()> {
+        assert!(!Sandbox::default().blocks_connection(&"93.184.216.34:443".parse()?));
+        Ok(())
+    }
+}
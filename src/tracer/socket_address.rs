@@ -0,0 +1,121 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+fn read_u16_be(bytes: &[u8]) -> u16 {
+    u16::from(bytes[0]) << 8 | u16::from(bytes[1])
+}
+
+fn read_u32_be(bytes: &[u8]) -> u32 {
+    u32::from(read_u16_be(&bytes[0..2])) << 16 | u32::from(read_u16_be(&bytes[2..4]))
+}
+
+fn read_u32_le(bytes: &[u8]) -> u32 {
+    u32::from(bytes[0])
+        | u32::from(bytes[1]) << 8
+        | u32::from(bytes[2]) << 16
+        | u32::from(bytes[3]) << 24
+}
+
+pub fn decode(bytes: &[u8]) -> Option<SocketAddr> {
+    if bytes.len() < 2 {
+        return None;
+    }
+    let family = i32::from(u16::from(bytes[0]) | u16::from(bytes[1]) << 8);
+    if family == libc::AF_INET && bytes.len() >= 8 {
+        Some(SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::new(bytes[4], bytes[5], bytes[6], bytes[7]),
+            read_u16_be(&bytes[2..4]),
+        )))
+    } else if family == libc::AF_INET6 && bytes.len() >= 24 {
+        let mut address = [0; 16];
+        address.copy_from_slice(&bytes[8..24]);
+        let scope_id = if bytes.len() >= 28 {
+            read_u32_le(&bytes[24..28])
+        } else {
+            0
+        };
+        Some(SocketAddr::V6(SocketAddrV6::new(
+            Ipv6Addr::from(address),
+            read_u16_be(&bytes[2..4]),
+            read_u32_be(&bytes[4..8]),
+            scope_id,
+        )))
+    } else {
+        None
+    }
+}
+
+pub fn encode(address: &SocketAddr) -> Vec<u8> {
+    let mut result = vec![];
+    match address {
+        SocketAddr::V4(address) => {
+            result.extend_from_slice(&(libc::AF_INET as u16).to_le_bytes());
+            result.extend_from_slice(&address.port().to_be_bytes());
+            result.extend_from_slice(&address.ip().octets());
+            result.extend_from_slice(&[0; 8]);
+        }
+        SocketAddr::V6(address) => {
+            result.extend_from_slice(&(libc::AF_INET6 as u16).to_le_bytes());
+            result.extend_from_slice(&address.port().to_be_bytes());
+            result.extend_from_slice(&address.flowinfo().to_be_bytes());
+            result.extend_from_slice(&address.ip().octets());
+            result.extend_from_slice(&address.scope_id().to_le_bytes());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod decode {
+    use super::*;
+    use crate::R;
+
+    #[test]
+    fn decodes_ipv4_addresses() -> R<()> {
+        assert_eq!(
+            decode(&[2, 0, 0x1f, 0x90, 127, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Some("127.0.0.1:8080".parse()?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_ipv6_addresses() -> R<()> {
+        let mut bytes = vec![10, 0, 0, 80, 0, 0, 0, 0];
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        bytes.extend_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(decode(&bytes), Some("[::1]:80".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn ignores_other_address_families() {
+        assert_eq!(decode(b"\x01\x00/run/socket\x00"), None);
+    }
+
+    #[test]
+    fn ignores_truncated_addresses() {
+        assert_eq!(decode(&[2, 0, 0, 80]), None);
+    }
+}
+
+#[cfg(test)]
+mod encode {
+    use super::*;
+    use crate::R;
+
+    #[test]
+    fn roundtrips_ipv4_addresses() -> R<()> {
+        let address = "10.0.0.1:443".parse()?;
+        assert_eq!(encode(&address).len(), 16);
+        assert_eq!(decode(&encode(&address)), Some(address));
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrips_ipv6_addresses() -> R<()> {
+        let address = "[fe80::1]:8080".parse()?;
+        assert_eq!(encode(&address).len(), 28);
+        assert_eq!(decode(&encode(&address)), Some(address));
+        Ok(())
+    }
+}
@@ -1,57 +1,311 @@
 use crate::context::Context;
+use crate::test_spec::{Interaction, Stdin, Tty};
 use crate::R;
 use libc::c_int;
-use nix::unistd::{close, dup2, pipe, read};
+use nix::errno::Errno;
+use nix::fcntl::{open, OFlag};
+use nix::pty::{openpty, OpenptyResult, Winsize};
+use nix::sys::stat::Mode;
+use nix::sys::termios::{tcgetattr, tcsetattr, LocalFlags, OutputFlags, SetArg};
+use nix::unistd::{close, dup, dup2, pipe, read, setsid, write};
 use std::fmt;
+use std::fs;
 use std::io::{Cursor, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 type RawFd = c_int;
 
+type Captured = Arc<Mutex<Cursor<Vec<u8>>>>;
+
 pub struct Redirector {
+    pub stdin: StdinRedirect,
     pub stdout: Redirect,
     pub stderr: Redirect,
+    tty: bool,
 }
 
+#[derive(Default)]
 pub struct Capture {
     pub stdout: bool,
     pub stderr: bool,
 }
 
+#[derive(Default)]
+pub struct Stdio {
+    pub stdin: Option<Stdin>,
+    pub interaction: Vec<Interaction>,
+    pub tty: Option<Tty>,
+    pub capture: Capture,
+}
+
 impl Redirector {
-    pub fn new(context: &Context, capture: Capture) -> R<Redirector> {
+    pub fn new(context: &Context, stdio: Stdio) -> R<Redirector> {
+        let stdout_captured = if stdio.capture.stdout || !stdio.interaction.is_empty() {
+            Some(Arc::new(Mutex::new(Cursor::new(vec![]))))
+        } else {
+            None
+        };
+        let (stdout, stdin) = match &stdio.tty {
+            Some(tty) => {
+                let pty = Redirector::open_pty(tty)?;
+                let stdout = Redirect::new_tty(context, &pty, stdout_captured)?;
+                let stdin = StdinRedirect::new_tty(&stdio, &pty, &stdout)?;
+                (stdout, stdin)
+            }
+            None => {
+                let stdout = Redirect::new(context, StreamType::Stdout, stdout_captured)?;
+                let stdin = StdinRedirect::new(&stdio, &stdout)?;
+                (stdout, stdin)
+            }
+        };
         Ok(Redirector {
-            stdout: if capture.stdout {
-                Redirect::new_capturing(context, StreamType::Stdout)?
-            } else {
-                Redirect::new_non_capturing(context, StreamType::Stdout)?
-            },
-            stderr: if capture.stderr {
+            stdin,
+            stdout,
+            stderr: if stdio.capture.stderr {
                 Redirect::new_capturing(context, StreamType::Stderr)?
             } else {
                 Redirect::new_non_capturing(context, StreamType::Stderr)?
             },
+            tty: stdio.tty.is_some(),
         })
     }
 
+    fn open_pty(tty: &Tty) -> R<OpenptyResult> {
+        let window_size = Winsize {
+            ws_row: tty.rows,
+            ws_col: tty.columns,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let pty = openpty(&window_size, None)?;
+        let mut termios = tcgetattr(pty.slave)?;
+        termios.local_flags.remove(LocalFlags::ECHO);
+        termios.output_flags.remove(OutputFlags::ONLCR);
+        tcsetattr(pty.slave, SetArg::TCSANOW, &termios)?;
+        Ok(pty)
+    }
+
     pub fn child_redirect_streams(&self) -> R<()> {
+        if self.tty {
+            setsid()?;
+        }
+        self.stdin.child_redirect_stream()?;
+        if self.tty && unsafe { libc::ioctl(libc::STDIN_FILENO, libc::TIOCSCTTY, 0) } == -1 {
+            Err(format!(
+                "cannot set the controlling terminal: {}",
+                Errno::last()
+            ))?;
+        }
         self.stdout.child_redirect_stream()?;
-        self.stderr.child_redirect_stream()?;
+        if self.tty {
+            self.stderr.child_redirect_to(libc::STDOUT_FILENO)?;
+        } else {
+            self.stderr.child_redirect_stream()?;
+        }
         Ok(())
     }
 
     pub fn parent_relay_streams(&self) -> R<impl FnOnce() -> R<()>> {
-        let a = self.stdout.parent_relay_stream()?;
-        let b = self.stderr.parent_relay_stream()?;
+        let a = self.stdin.parent_feed_stream()?;
+        let b = self.stdout.parent_relay_stream()?;
+        let c = self.stderr.parent_relay_stream()?;
         Ok(|| -> R<()> {
             a.join().unwrap()?;
             b.join().unwrap()?;
+            c.join().unwrap()?;
             Ok(())
         })
     }
 }
 
+#[derive(Clone)]
+enum Input {
+    Bytes(Vec<u8>),
+    Interaction(Vec<Interaction>, Captured, Arc<AtomicBool>),
+}
+
+pub struct StdinRedirect {
+    read_end: RawFd,
+    input: Option<(RawFd, Input)>,
+    end_of_input: Vec<u8>,
+    interaction_failure: Arc<Mutex<Option<(String, String)>>>,
+}
+
+impl StdinRedirect {
+    fn open_read_only(path: &Path) -> R<RawFd> {
+        Ok(open(path, OFlag::O_RDONLY, Mode::empty()).map_err(|error| {
+            format!(
+                "cannot open stdin file {}: {}",
+                path.to_string_lossy(),
+                error
+            )
+        })?)
+    }
+
+    fn interaction_input(interaction: &[Interaction], stdout: &Redirect) -> Input {
+        let captured = stdout
+            .captured
+            .clone()
+            .expect("scriptkeeper bug: stdout has to be captured for interactions");
+        Input::Interaction(interaction.to_vec(), captured, stdout.finished.clone())
+    }
+
+    fn new(stdio: &Stdio, stdout: &Redirect) -> R<StdinRedirect> {
+        let (read_end, input) = if !stdio.interaction.is_empty() {
+            let (read_end, write_end) = pipe()?;
+            let input = StdinRedirect::interaction_input(&stdio.interaction, stdout);
+            (read_end, Some((write_end, input)))
+        } else {
+            match &stdio.stdin {
+                None => (StdinRedirect::open_read_only(Path::new("/dev/null"))?, None),
+                Some(Stdin::File { path, .. }) => (StdinRedirect::open_read_only(path)?, None),
+                Some(Stdin::Bytes(bytes)) => {
+                    let (read_end, write_end) = pipe()?;
+                    (read_end, Some((write_end, Input::Bytes(bytes.clone()))))
+                }
+            }
+        };
+        Ok(StdinRedirect {
+            read_end,
+            input,
+            end_of_input: vec![],
+            interaction_failure: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    fn new_tty(stdio: &Stdio, pty: &OpenptyResult, stdout: &Redirect) -> R<StdinRedirect> {
+        let input = if !stdio.interaction.is_empty() {
+            StdinRedirect::interaction_input(&stdio.interaction, stdout)
+        } else {
+            Input::Bytes(match &stdio.stdin {
+                None => vec![],
+                Some(Stdin::File { path, .. }) => fs::read(path).map_err(|error| {
+                    format!(
+                        "cannot open stdin file {}: {}",
+                        path.to_string_lossy(),
+                        error
+                    )
+                })?,
+                Some(Stdin::Bytes(bytes)) => bytes.clone(),
+            })
+        };
+        Ok(StdinRedirect {
+            read_end: dup(pty.slave)?,
+            input: Some((dup(pty.master)?, input)),
+            // Terminals can't be closed like pipes. The first ^D ends a
+            // pending line, the second one makes `read` return end-of-file.
+            end_of_input: b"\x04\x04".to_vec(),
+            interaction_failure: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    fn child_redirect_stream(&self) -> R<()> {
+        if let Some((write_end, _)) = &self.input {
+            close(*write_end)?;
+        }
+        dup2(self.read_end, libc::STDIN_FILENO)?;
+        close(self.read_end)?;
+        Ok(())
+    }
+
+    fn write_bytes(write_end: RawFd, bytes: &[u8]) -> Result<(), String> {
+        let mut remaining = bytes;
+        while !remaining.is_empty() {
+            match write(write_end, remaining) {
+                Ok(count) => remaining = &remaining[count..],
+                Err(nix::Error::Sys(Errno::EPIPE)) | Err(nix::Error::Sys(Errno::EIO)) => break,
+                Err(error) => return Err(error.to_string()),
+            }
+        }
+        Ok(())
+    }
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack
+            .windows(needle.len())
+            .position(|window| window == needle)
+    }
+
+    fn interact(
+        write_end: RawFd,
+        interaction: Vec<Interaction>,
+        stdout: &Captured,
+        stdout_finished: &AtomicBool,
+    ) -> Result<Option<(String, String)>, String> {
+        let mut position = 0;
+        for entry in interaction {
+            let start = Instant::now();
+            loop {
+                let finished = stdout_finished.load(Ordering::SeqCst);
+                let found = {
+                    let stdout = stdout.lock().map_err(|error| error.to_string())?;
+                    StdinRedirect::find(&stdout.get_ref()[position..], &entry.expect)
+                };
+                if let Some(index) = found {
+                    position += index + entry.expect.len();
+                    break;
+                }
+                let expected = format!(
+                    "output to stdout: {:?}",
+                    String::from_utf8_lossy(&entry.expect)
+                );
+                if finished {
+                    return Ok(Some((expected, "<script terminated>".to_string())));
+                }
+                if start.elapsed() >= entry.timeout {
+                    return Ok(Some((
+                        expected,
+                        format!("<timeout after {}s>", entry.timeout.as_secs()),
+                    )));
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            StdinRedirect::write_bytes(write_end, &entry.send)?;
+        }
+        Ok(None)
+    }
+
+    fn parent_feed_stream(&self) -> R<thread::JoinHandle<Result<(), String>>> {
+        close(self.read_end)?;
+        let input = self.input.clone();
+        let end_of_input = self.end_of_input.clone();
+        let interaction_failure = self.interaction_failure.clone();
+        Ok(thread::spawn(move || -> Result<(), String> {
+            if let Some((write_end, input)) = input {
+                match input {
+                    Input::Bytes(bytes) => StdinRedirect::write_bytes(write_end, &bytes)?,
+                    Input::Interaction(interaction, stdout, stdout_finished) => {
+                        let failure = StdinRedirect::interact(
+                            write_end,
+                            interaction,
+                            &stdout,
+                            &stdout_finished,
+                        )?;
+                        *interaction_failure
+                            .lock()
+                            .map_err(|error| error.to_string())? = failure;
+                    }
+                }
+                StdinRedirect::write_bytes(write_end, &end_of_input)?;
+                close(write_end).map_err(|error| error.to_string())?;
+            }
+            Ok(())
+        }))
+    }
+
+    pub fn interaction_failure(&self) -> R<Option<(String, String)>> {
+        Ok(self
+            .interaction_failure
+            .lock()
+            .map_err(|error| error.to_string())?
+            .clone())
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum StreamType {
     Stdout,
@@ -72,7 +326,8 @@ pub struct Redirect {
     context: Context,
     read_end: RawFd,
     write_end: RawFd,
-    captured: Option<Arc<Mutex<Cursor<Vec<u8>>>>>,
+    captured: Option<Captured>,
+    finished: Arc<AtomicBool>,
 }
 
 impl Redirect {
@@ -88,11 +343,7 @@ impl Redirect {
         Redirect::new(context, stream_type, None)
     }
 
-    fn new(
-        context: &Context,
-        stream_type: StreamType,
-        captured: Option<Arc<Mutex<Cursor<Vec<u8>>>>>,
-    ) -> R<Redirect> {
+    fn new(context: &Context, stream_type: StreamType, captured: Option<Captured>) -> R<Redirect> {
         let (read_end, write_end) = pipe()?;
         Ok(Redirect {
             stream_type,
@@ -100,6 +351,18 @@ impl Redirect {
             read_end,
             write_end,
             captured,
+            finished: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    fn new_tty(context: &Context, pty: &OpenptyResult, captured: Option<Captured>) -> R<Redirect> {
+        Ok(Redirect {
+            stream_type: StreamType::Stdout,
+            context: context.clone(),
+            read_end: pty.master,
+            write_end: pty.slave,
+            captured,
+            finished: Arc::new(AtomicBool::new(false)),
         })
     }
 
@@ -114,17 +377,34 @@ impl Redirect {
         Ok(())
     }
 
+    fn child_redirect_to(&self, fd: RawFd) -> R<()> {
+        close(self.read_end)?;
+        close(self.write_end)?;
+        let stdstream_fileno = match self.stream_type {
+            StreamType::Stdout => libc::STDOUT_FILENO,
+            StreamType::Stderr => libc::STDERR_FILENO,
+        };
+        dup2(fd, stdstream_fileno)?;
+        Ok(())
+    }
+
     fn parent_relay_stream(&self) -> R<thread::JoinHandle<Result<(), String>>> {
         close(self.write_end)?;
         let read_end = self.read_end;
         let context = self.context.clone();
         let stream_type = self.stream_type;
         let captured = self.captured.clone();
+        let finished = self.finished.clone();
         Ok(thread::spawn(move || -> Result<(), String> {
             let mut buffer = [0; 1024];
             loop {
-                let count = read(read_end, &mut buffer).map_err(|error| error.to_string())?;
+                let count = match read(read_end, &mut buffer) {
+                    Ok(count) => count,
+                    Err(nix::Error::Sys(Errno::EIO)) => 0,
+                    Err(error) => return Err(error.to_string()),
+                };
                 if count == 0 {
+                    finished.store(true, Ordering::SeqCst);
                     return Ok(());
                 }
                 let mut stdstream = match stream_type {
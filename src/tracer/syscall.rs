@@ -335,9 +335,14 @@ pub enum Syscall {
     Pkey_mprotect,
     Pkey_alloc,
     Pkey_free,
+    Openat2,
+    Faccessat2,
     Unknown(c_ulonglong),
 }
 
+const SYS_OPENAT2: i64 = 437;
+const SYS_FACCESSAT2: i64 = 439;
+
 impl From<user_regs_struct> for Syscall {
     fn from(registers: user_regs_struct) -> Self {
         use Syscall::*;
@@ -674,6 +679,8 @@ impl From<user_regs_struct> for Syscall {
             libc::SYS_pkey_mprotect => Pkey_mprotect,
             libc::SYS_pkey_alloc => Pkey_alloc,
             libc::SYS_pkey_free => Pkey_free,
+            SYS_OPENAT2 => Openat2,
+            SYS_FACCESSAT2 => Faccessat2,
             _ => Unknown(registers.orig_rax),
         }
     }
@@ -1,17 +1,8 @@
 use crate::R;
-use libc::{c_uint, c_ulonglong, c_void};
+use libc::{c_ulonglong, c_void};
 use nix::sys::ptrace;
 use nix::unistd::Pid;
 
-fn cast_to_four_byte_array(x: c_uint) -> [u8; 4] {
-    [
-        (x & 0xff) as u8,
-        ((x >> 8) & 0xff) as u8,
-        ((x >> 16) & 0xff) as u8,
-        ((x >> 24) & 0xff) as u8,
-    ]
-}
-
 fn cast_to_eight_byte_array(x: c_ulonglong) -> [u8; 8] {
     [
         (x & 0xff) as u8,
@@ -76,9 +67,32 @@ pub fn peek_string(pid: Pid, address: c_ulonglong) -> R<Vec<u8>> {
     data_to_string(peekdata_iter(pid, address))
 }
 
+pub fn peek_bytes(pid: Pid, address: c_ulonglong, length: usize) -> R<Vec<u8>> {
+    let mut result = vec![];
+    for word in peekdata_iter(pid, address) {
+        result.extend_from_slice(&cast_to_eight_byte_array(word?));
+        if result.len() >= length {
+            break;
+        }
+    }
+    result.truncate(length);
+    Ok(result)
+}
+
 pub fn peek_string_array(pid: Pid, address: c_ulonglong) -> R<Vec<Vec<u8>>> {
+    Ok(peek_null_terminated_array(pid, address)?
+        .into_iter()
+        .skip(1)
+        .collect())
+}
+
+pub fn peek_environment(pid: Pid, address: c_ulonglong) -> R<Vec<Vec<u8>>> {
+    peek_null_terminated_array(pid, address)
+}
+
+fn peek_null_terminated_array(pid: Pid, address: c_ulonglong) -> R<Vec<Vec<u8>>> {
     let mut result = vec![];
-    for word in peekdata_iter(pid, address).skip(1) {
+    for word in peekdata_iter(pid, address) {
         let word = word?;
         if word == 0 {
             break;
@@ -127,22 +141,25 @@ mod peeking {
     }
 }
 
-pub fn poke_four_bytes(pid: Pid, address: c_ulonglong, small_word: c_uint) -> R<()> {
-    let existing_word: c_ulonglong = peekdata(pid, address)?;
-    let mut overlapping_data_array: [u8; 8] = cast_to_eight_byte_array(existing_word);
-
-    let first_four_bytes: [u8; 4] = cast_to_four_byte_array(small_word);
-    overlapping_data_array[..4].clone_from_slice(&first_four_bytes);
-
-    let new_word: c_ulonglong = cast_to_eight_byte_word(overlapping_data_array);
-    pokedata(pid, address, new_word)
-}
-
 fn pokedata(pid: Pid, address: c_ulonglong, words: c_ulonglong) -> R<()> {
     ptrace::write(pid, address as *mut c_void, words as *mut c_void)?;
     Ok(())
 }
 
+pub fn poke_bytes(pid: Pid, mut address: c_ulonglong, bytes: &[u8]) -> R<()> {
+    for chunk in bytes.chunks(8) {
+        let mut word = cast_to_eight_byte_array(if chunk.len() < 8 {
+            peekdata(pid, address)?
+        } else {
+            0
+        });
+        word[..chunk.len()].clone_from_slice(chunk);
+        pokedata(pid, address, cast_to_eight_byte_word(word))?;
+        address += 8;
+    }
+    Ok(())
+}
+
 fn string_to_data(string: &[u8], max_size: c_ulonglong) -> R<Vec<c_ulonglong>> {
     if string.len() as c_ulonglong >= max_size {
         Err("string_to_data: string too long")?
@@ -265,13 +282,6 @@ mod poking {
         use super::*;
         use libc::user_regs_struct;
 
-        fn cast_to_four_byte_word(bytes: [u8; 4]) -> c_uint {
-            u32::from(bytes[0])
-                + (u32::from(bytes[1]) << 8)
-                + (u32::from(bytes[2]) << 16)
-                + (u32::from(bytes[3]) << 24)
-        }
-
         fn run_roundtrip_test(test: fn(child: Pid, registers: user_regs_struct) -> R<()>) -> R<()> {
             fork_with_child_errors(
                 || {
@@ -315,18 +325,11 @@ mod poking {
         }
 
         #[test]
-        fn roundtrip_for_four_byte_word_doesnt_clobber_adjacent_four_bytes() -> R<()> {
+        fn roundtrip_for_bytes_doesnt_clobber_adjacent_bytes() -> R<()> {
             run_roundtrip_test(|child, registers| {
-                let eight_bytes: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
-                let four_bytes: [u8; 4] = [10, 20, 30, 40];
-
-                pokedata(child, registers.rdi, cast_to_eight_byte_word(eight_bytes))?;
-                poke_four_bytes(child, registers.rdi, cast_to_four_byte_word(four_bytes))?;
-
-                assert_eq!(
-                    cast_to_eight_byte_array(peekdata(child, registers.rdi)?),
-                    [10, 20, 30, 40, 5, 6, 7, 8],
-                );
+                poke_string(child, registers.rdi, b"foo_bar_baz_qux", 16)?;
+                poke_bytes(child, registers.rdi, b"1234567890")?;
+                assert_eq!(peek_string(child, registers.rdi)?, b"1234567890z_qux");
                 Ok(())
             })
         }
@@ -1,6 +1,7 @@
 pub mod short_temp_files;
 
 use crate::R;
+use regex::bytes::Regex;
 use std::fs;
 use std::iter::Peekable;
 use std::path::Path;
@@ -24,6 +25,15 @@ pub fn parse_hashbang(program: &Path) -> Option<String> {
     }
 }
 
+lazy_static! {
+    static ref ESCAPE_SEQUENCE: Regex =
+        Regex::new(r"\x1b(\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(\x07|\x1b\\)|[@-Z\\-_])").unwrap();
+}
+
+pub fn strip_ansi(bytes: &[u8]) -> Vec<u8> {
+    ESCAPE_SEQUENCE.replace_all(bytes, &b""[..]).to_vec()
+}
+
 pub fn with_has_more<Element>(
     input: impl IntoIterator<Item = Element>,
 ) -> impl Iterator<Item = (Element, bool)> {
@@ -43,3 +53,26 @@ pub fn with_has_more<Element>(
 
     Iter(input.into_iter().peekable())
 }
+
+#[cfg(test)]
+mod strip_ansi {
+    use super::*;
+
+    #[test]
+    fn removes_colors() {
+        assert_eq!(strip_ansi(b"\x1b[1;31merror\x1b[0m: foo"), b"error: foo");
+    }
+
+    #[test]
+    fn removes_cursor_movements_and_titles() {
+        assert_eq!(
+            strip_ansi(b"\x1b]0;title\x07\x1b[2K\x1b[1Gprogress\x1bM"),
+            b"progress"
+        );
+    }
+
+    #[test]
+    fn leaves_other_output_untouched() {
+        assert_eq!(strip_ansi(b"[1;31m \xff"), b"[1;31m \xff");
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::collections::VecDeque;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use tempdir::TempDir;
 use trim_margin::MarginTrimmable;
@@ -35,6 +35,11 @@ impl TempFile {
         Ok(TempFile { tempdir })
     }
 
+    pub fn new_in(directory: &Path) -> R<TempFile> {
+        let tempdir = TempDir::new_in(directory, "test")?;
+        Ok(TempFile { tempdir })
+    }
+
     pub fn write_temp_script(script: &[u8]) -> R<TempFile> {
         let tempfile = TempFile::new()?;
         fs::write(&tempfile.path(), script)?;
@@ -0,0 +1,160 @@
+#![cfg_attr(
+    feature = "dev",
+    allow(dead_code, unused_variables, unused_imports, unreachable_code)
+)]
+#![cfg_attr(feature = "ci", deny(warnings))]
+#![deny(clippy::all)]
+
+use crate::utils::{test_run, Expect};
+use scriptkeeper::utils::path_to_string;
+use scriptkeeper::R;
+use std::fs;
+use tempdir::TempDir;
+
+fn test_run_in_tempdir(script_code: &str, tests: &str, expected_error: Option<&str>) -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    let dir = path_to_string(tempdir.path())?;
+    test_run(
+        &script_code.replace("$DIR", dir),
+        &tests.replace("$DIR", dir),
+        match expected_error {
+            None => Expect::tests_pass(),
+            Some(error) => Expect::error_message(&error.replace("$DIR", dir))?,
+        },
+    )
+}
+
+#[test]
+fn passes_when_the_file_changes_match() -> R<()> {
+    test_run_in_tempdir(
+        r"
+            |#!/usr/bin/env bash
+            |echo 1.2.3 > $DIR/VERSION
+            |mkdir $DIR/out
+            |rm $DIR/VERSION
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    expectedFileChanges:
+            |      - path: $DIR/VERSION
+            |        operation: write
+            |      - path: $DIR/out
+            |        operation: mkdir
+            |      - path: $DIR/VERSION
+            |        operation: delete
+            |unmockedCommands:
+            |  - mkdir
+            |  - rm
+        ",
+        None,
+    )
+}
+
+#[test]
+fn checks_the_written_content() -> R<()> {
+    test_run_in_tempdir(
+        r"
+            |#!/usr/bin/env bash
+            |echo 1.2.4 > $DIR/VERSION
+        ",
+        r#"
+            |tests:
+            |  - steps: []
+            |    expectedFileChanges:
+            |      - path: $DIR/VERSION
+            |        operation: write
+            |        content: "1.2.3\n"
+        "#,
+        Some(
+            r#"
+                |error:
+                |  expected content of $DIR/VERSION: "1.2.3\n"
+                |  received content of $DIR/VERSION: "1.2.4\n"
+            "#,
+        ),
+    )
+}
+
+#[test]
+fn reports_unexpected_file_changes() -> R<()> {
+    test_run_in_tempdir(
+        r"
+            |#!/usr/bin/env bash
+            |echo foo > $DIR/lockfile
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    expectedFileChanges: []
+        ",
+        Some(
+            r"
+                |error:
+                |  expected: <no more file changes>
+                |  received: write $DIR/lockfile
+            ",
+        ),
+    )
+}
+
+#[test]
+fn reports_missing_file_changes() -> R<()> {
+    test_run_in_tempdir(
+        r"
+            |#!/usr/bin/env bash
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    expectedFileChanges:
+            |      - path: $DIR/out
+            |        operation: chmod
+            |        mode: '755'
+        ",
+        Some(
+            r"
+                |error:
+                |  expected: chmod $DIR/out 755
+                |  received: <no more file changes>
+            ",
+        ),
+    )
+}
+
+#[test]
+fn ignores_file_changes_when_not_specified() -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    test_run(
+        &format!(
+            r"
+                |#!/usr/bin/env bash
+                |echo foo > {}/file
+            ",
+            path_to_string(tempdir.path())?
+        ),
+        r"
+            |tests:
+            |  - steps: []
+        ",
+        Expect::tests_pass(),
+    )?;
+    assert!(fs::metadata(tempdir.path().join("file")).is_ok());
+    Ok(())
+}
+
+#[test]
+fn ignores_writes_to_dev_null() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |echo foo > /dev/null
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    expectedFileChanges: []
+        ",
+        Expect::tests_pass(),
+    )
+}
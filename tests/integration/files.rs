@@ -67,3 +67,109 @@ fn does_not_mock_existence_of_unspecified_files() -> R<()> {
     )?;
     Ok(())
 }
+
+mod metadata {
+    use super::*;
+
+    fn test_condition(condition: &str, mocked_files: &str) -> R<()> {
+        test_run(
+            &format!(
+                r"
+                    |#!/usr/bin/env bash
+                    |if {}; then
+                    |  cp
+                    |fi
+                ",
+                condition
+            ),
+            &format!(
+                r"
+                    |tests:
+                    |  - steps:
+                    |      - cp
+                    |    mockedFiles: {}
+                ",
+                mocked_files
+            ),
+            Expect::tests_pass(),
+        )
+    }
+
+    #[test]
+    fn allows_to_mock_executable_files() -> R<()> {
+        test_condition("[ -x /foo ]", "[{path: /foo, mode: '755'}]")
+    }
+
+    #[test]
+    fn does_not_mock_files_as_executable_by_default() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |if [ -x /foo ]; then
+                |  cp
+                |fi
+            ",
+            r"
+                |tests:
+                |  - steps: []
+                |    mockedFiles:
+                |      - /foo
+            ",
+            Expect::tests_pass(),
+        )
+    }
+
+    #[test]
+    fn allows_to_mock_symlinks() -> R<()> {
+        test_condition("[ -L /foo ]", "[{path: /foo, type: symlink}]")
+    }
+
+    #[test]
+    fn follows_mocked_symlinks_to_their_targets() -> R<()> {
+        test_condition(
+            "[ -d /foo ]",
+            "[{path: /foo, type: symlink, target: /bar}, /bar/]",
+        )
+    }
+
+    #[test]
+    fn allows_to_mock_fifos() -> R<()> {
+        test_condition("[ -p /foo ]", "[{path: /foo, type: fifo}]")
+    }
+
+    #[test]
+    fn allows_to_mock_file_sizes() -> R<()> {
+        test_condition("[ -s /foo ]", "[{path: /foo, size: 42}]")
+    }
+
+    #[test]
+    fn allows_to_mock_modification_times() -> R<()> {
+        test_condition(
+            "[ /foo -nt /bar ]",
+            "[{path: /foo, mtime: 1560000000}, {path: /bar, mtime: 1500000000}]",
+        )
+    }
+
+    #[test]
+    fn allows_to_mock_file_owners() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |if [ -O /foo ]; then
+                |  cp
+                |fi
+            ",
+            &format!(
+                r"
+                    |tests:
+                    |  - steps: []
+                    |    mockedFiles:
+                    |      - path: /foo
+                    |        uid: {}
+                ",
+                nix::unistd::getuid().as_raw() + 1
+            ),
+            Expect::tests_pass(),
+        )
+    }
+}
@@ -11,6 +11,7 @@ use scriptkeeper::context::Context;
 use scriptkeeper::utils::path_to_string;
 use scriptkeeper::{cli, run_main, R};
 use std::fs;
+use std::path::PathBuf;
 use test_utils::trim_margin;
 
 fn test_holes(script_code: &str, existing: &str, expected: &str) -> R<()> {
@@ -20,6 +21,9 @@ fn test_holes(script_code: &str, existing: &str, expected: &str) -> R<()> {
         &cli::Args::Scriptkeeper {
             script_path: script.path(),
             record: false,
+            safe: false,
+            filter: None,
+            list: false,
         },
     )?;
     assert_eq_yaml(
@@ -49,6 +53,34 @@ fn fills_in_holes_in_test_files() -> R<()> {
     )
 }
 
+#[test]
+fn keeps_ids_when_filling_in_holes() -> R<()> {
+    test_holes(
+        "
+            |#!/usr/bin/env bash
+            |id -u
+        ",
+        "
+            |tests:
+            |  - steps:
+            |      - _
+            |    uid: 0
+            |    euid: 1000
+            |    gid: 100
+            |    groups: [100, 998]
+        ",
+        "
+            |tests:
+            |  - steps:
+            |      - id -u
+            |    uid: 0
+            |    euid: 1000
+            |    gid: 100
+            |    groups: [100, 998]
+        ",
+    )
+}
+
 #[test]
 fn indicates_on_stdout_that_the_test_file_was_written_to() -> R<()> {
     let (script, test_file) = prepare_script(
@@ -68,6 +100,9 @@ fn indicates_on_stdout_that_the_test_file_was_written_to() -> R<()> {
         &cli::Args::Scriptkeeper {
             script_path: script.path(),
             record: false,
+            safe: false,
+            filter: None,
+            list: false,
         },
     )?;
     assert_eq!(
@@ -99,6 +134,9 @@ fn does_not_modify_files_without_holes() -> R<()> {
         &cli::Args::Scriptkeeper {
             script_path: script.path(),
             record: false,
+            safe: false,
+            filter: None,
+            list: false,
         },
     )?;
     let new_modification_time = fs::metadata(&test_file)?.modified()?;
@@ -214,6 +252,9 @@ mod errors_in_tests {
             &cli::Args::Scriptkeeper {
                 script_path: script.path(),
                 record: false,
+                safe: false,
+                filter: None,
+                list: false,
             },
         )?;
         assert_eq!(
@@ -249,14 +290,105 @@ mod errors_in_tests {
             &cli::Args::Scriptkeeper {
                 script_path: script.path(),
                 record: false,
+                safe: false,
+                filter: None,
+                list: false,
             },
         )?;
         assert_eq!(
             context.get_captured_stdout(),
-            "error:\n  expected: ls -foo\n  received: ls\n"
+            "error in test 1:\n  expected: ls -foo\n  received: ls\n"
+        );
+        Ok(())
+    }
+}
+
+mod expanded_features {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_utils::assert_error;
+
+    fn test_refused_holes(tests: &str, files: &[(&str, &str)], expected_error: &str) -> R<()> {
+        let (script, test_file) = prepare_script(
+            "
+                |#!/usr/bin/env bash
+                |ls
+            ",
+            tests,
+        )?;
+        for (path, contents) in files {
+            fs::write(test_file.parent().unwrap().join(path), contents)?;
+        }
+        let original = fs::read(&test_file)?;
+        assert_error!(
+            run_main(
+                &Context::new_mock(),
+                &cli::Args::Scriptkeeper {
+                    script_path: script.path(),
+                    record: false,
+                    safe: false,
+                    filter: None,
+                    list: false,
+                },
+            ),
+            format!(
+                "holes ('_') can't be filled in test files that use {}, \
+                 since these would be lost when rewriting the file",
+                expected_error
+            )
         );
+        assert_eq!(fs::read(&test_file)?, original);
         Ok(())
     }
+
+    #[test]
+    fn refuses_to_fill_in_holes_in_files_with_defaults() -> R<()> {
+        test_refused_holes(
+            "
+                |defaults:
+                |  env:
+                |    FOO: foo
+                |tests:
+                |  - steps:
+                |      - _
+            ",
+            &[],
+            "'defaults'",
+        )
+    }
+
+    #[test]
+    fn refuses_to_fill_in_holes_in_files_with_includes_and_variables() -> R<()> {
+        test_refused_holes(
+            "
+                |tests:
+                |  - steps:
+                |      - include: preamble.yaml
+                |      - _
+                |    cwd: ${TEST_DIR}
+            ",
+            &[("preamble.yaml", "[]")],
+            "'include', '${...}'",
+        )
+    }
+
+    #[test]
+    fn refuses_to_fill_in_holes_in_files_with_extends_and_matrix() -> R<()> {
+        test_refused_holes(
+            "
+                |tests:
+                |  - name: base
+                |    steps: []
+                |  - extends: base
+                |    matrix:
+                |      FOO: [a, b]
+                |    steps:
+                |      - _
+            ",
+            &[],
+            "'extends', 'matrix'",
+        )
+    }
 }
 
 #[test]
@@ -377,4 +509,132 @@ mod unmocked_commands {
             ",
         )
     }
+
+    #[test]
+    fn matches_unmocked_commands_by_basename_when_configured() -> R<()> {
+        test_holes(
+            "
+                |#!/usr/bin/env bash
+                |ls
+            ",
+            "
+                |unmockedCommands:
+                |  - /opt/tools/bin/ls
+                |executableMatching: basename
+                |tests:
+                |  - steps:
+                |      - _
+            ",
+            "
+                |unmockedCommands:
+                |  - /opt/tools/bin/ls
+                |executableMatching: basename
+                |tests:
+                |  - steps: []
+            ",
+        )
+    }
+}
+
+mod record_output {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn records_the_output_of_the_tested_script() -> R<()> {
+        test_holes(
+            r"
+                |#!/usr/bin/env bash
+                |echo foo
+                |echo bar 1>&2
+            ",
+            r"
+                |recordOutput: true
+                |tests:
+                |  - steps:
+                |      - _
+            ",
+            r#"
+                |recordOutput: true
+                |tests:
+                |  - steps: []
+                |    stdout: "foo\n"
+                |    stderr: "bar\n"
+            "#,
+        )
+    }
+
+    #[test]
+    fn does_not_record_empty_output() -> R<()> {
+        test_holes(
+            r"
+                |#!/usr/bin/env bash
+                |true
+            ",
+            r"
+                |recordOutput: true
+                |tests:
+                |  - steps:
+                |      - _
+            ",
+            r"
+                |recordOutput: true
+                |tests:
+                |  - steps: []
+            ",
+        )
+    }
+
+    #[test]
+    fn writes_large_outputs_to_fixture_files() -> R<()> {
+        let (script, test_file) = prepare_script(
+            r"
+                |#!/usr/bin/env bash
+                |echo foo
+                |echo bar
+                |echo baz 1>&2
+            ",
+            r"
+                |recordOutput: true
+                |outputFixtureLines: 1
+                |tests:
+                |  - steps:
+                |      - _
+            ",
+        )?;
+        run_main(
+            &Context::new_mock(),
+            &cli::Args::Scriptkeeper {
+                script_path: script.path(),
+                record: false,
+                safe: false,
+                filter: None,
+                list: false,
+            },
+        )?;
+        let fixture = PathBuf::from("fixtures").join(format!(
+            "{}-1.stdout",
+            script.path().file_name().unwrap().to_string_lossy()
+        ));
+        assert_eq_yaml(
+            &String::from_utf8(fs::read(&test_file)?)?,
+            &trim_margin(&format!(
+                r#"
+                    |recordOutput: true
+                    |outputFixtureLines: 1
+                    |tests:
+                    |  - steps: []
+                    |    stdout:
+                    |      file: {}
+                    |    stderr: "baz\n"
+                "#,
+                path_to_string(&fixture)?
+            ))?,
+        )?;
+        assert_eq!(
+            fs::read(script.path().with_file_name(&fixture))?,
+            b"foo\nbar\n"
+        );
+        Ok(())
+    }
 }
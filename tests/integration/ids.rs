@@ -0,0 +1,100 @@
+#![cfg_attr(
+    feature = "dev",
+    allow(dead_code, unused_variables, unused_imports, unreachable_code)
+)]
+#![cfg_attr(feature = "ci", deny(warnings))]
+#![deny(clippy::all)]
+
+use crate::utils::{test_run, Expect};
+use scriptkeeper::R;
+
+#[test]
+fn allows_to_mock_the_user_id() -> R<()> {
+    let script = r#"
+        |#!/usr/bin/env bash
+        |if [ "$EUID" -ne 0 ]; then echo "run as root"; exit 1; fi
+        |echo root
+    "#;
+    test_run(
+        script,
+        r"
+            |tests:
+            |  - steps: []
+            |    uid: 0
+        ",
+        Expect::tests_pass().with_stdout("root\nAll tests passed.\n"),
+    )?;
+    test_run(
+        script,
+        r#"
+            |tests:
+            |  - steps: []
+            |    uid: 1000
+            |    exitcode: 1
+        "#,
+        Expect::tests_pass().with_stdout("run as root\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn allows_to_mock_the_effective_user_id_separately() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |id -u
+            |id -ru
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    uid: 1000
+            |    euid: 0
+            |unmockedCommands:
+            |  - id
+        ",
+        Expect::tests_pass().with_stdout("0\n1000\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn patches_the_ids_in_unmocked_commands() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |id -u
+            |id -g
+            |id -G
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    uid: 1000
+            |    gid: 100
+            |    groups: [100, 200]
+            |unmockedCommands:
+            |  - id
+        ",
+        Expect::tests_pass().with_stdout("1000\n100\n100 200\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn uses_the_mocked_ids_for_access_checks_on_mocked_files() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |if [ -r /foo ]; then
+            |  echo readable
+            |fi
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    uid: 1000
+            |    gid: 1000
+            |    mockedFiles:
+            |      - {path: /foo, mode: '600', uid: 0, gid: 0}
+        ",
+        Expect::tests_pass(),
+    )
+}
@@ -1,8 +1,15 @@
 mod examples;
+mod file_changes;
 mod files;
 mod holes;
+mod ids;
+mod network;
 mod path;
 mod recording;
 mod run;
+mod safety;
+mod selection;
 mod stdio;
+mod tty;
+mod uname;
 mod utils;
@@ -0,0 +1,162 @@
+#![cfg_attr(
+    feature = "dev",
+    allow(dead_code, unused_variables, unused_imports, unreachable_code)
+)]
+#![cfg_attr(feature = "ci", deny(warnings))]
+#![deny(clippy::all)]
+
+use crate::utils::{test_run, Expect};
+use pretty_assertions::assert_eq;
+use scriptkeeper::R;
+use std::io::Read;
+use std::net::TcpListener;
+use std::thread;
+
+#[test]
+fn blocks_connections_by_default() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |(echo foo > /dev/tcp/127.0.0.1/1) 2> /dev/null || echo unreachable
+        ",
+        r#"
+            |tests:
+            |  - steps: []
+            |    stdout: "unreachable\n"
+        "#,
+        Expect::error_message(
+            r"
+                |unreachable
+                |error:
+                |  blocked network access: connect 127.0.0.1:1
+            ",
+        )?
+        .with_stderr("scriptkeeper: blocked network access: connect 127.0.0.1:1\n"),
+    )
+}
+
+#[test]
+fn fails_blocked_connections_with_enetunreach() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |(echo foo > /dev/tcp/127.0.0.1/1) 2>&1 | (read output; [[ $output == *unreachable* ]])
+        ",
+        r"
+            |tests:
+            |  - steps: []
+        ",
+        Expect::error_message(
+            r"
+                |error:
+                |  blocked network access: connect 127.0.0.1:1
+            ",
+        )?
+        .with_stderr("scriptkeeper: blocked network access: connect 127.0.0.1:1\n"),
+    )
+}
+
+fn test_with_server(script_code: &str, tests: &str) -> R<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port().to_string();
+    let server = thread::spawn(move || -> String {
+        let mut received = String::new();
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.read_to_string(&mut received).unwrap();
+        received
+    });
+    test_run(
+        &script_code.replace("$PORT", &port),
+        &tests.replace("$PORT", &port),
+        Expect::tests_pass(),
+    )?;
+    assert_eq!(server.join().unwrap(), "foo\n");
+    Ok(())
+}
+
+fn test_allowed_destination(destination: &str) -> R<()> {
+    test_with_server(
+        r"
+            |#!/usr/bin/env bash
+            |echo foo > /dev/tcp/127.0.0.1/$PORT
+        ",
+        &format!(
+            r"
+                |tests:
+                |  - steps: []
+                |    allowedDestinations:
+                |      - {}
+            ",
+            destination
+        ),
+    )
+}
+
+#[test]
+fn allows_connections_to_allowed_destinations() -> R<()> {
+    test_allowed_destination("127.0.0.1:$PORT")
+}
+
+#[test]
+fn allows_to_specify_allowed_destinations_by_host_name() -> R<()> {
+    test_allowed_destination("localhost")
+}
+
+mod network_mappings {
+    use super::*;
+
+    #[test]
+    fn redirects_connections_to_ip_addresses() -> R<()> {
+        test_with_server(
+            r"
+                |#!/usr/bin/env bash
+                |echo foo > /dev/tcp/10.1.2.3/443
+            ",
+            r"
+                |tests:
+                |  - steps: []
+                |    network:
+                |      - 10.1.2.3:443 -> 127.0.0.1:$PORT
+            ",
+        )
+    }
+
+    #[test]
+    fn redirects_connections_to_host_names() -> R<()> {
+        test_with_server(
+            r"
+                |#!/usr/bin/env bash
+                |echo foo > /dev/tcp/api.example.com/443
+            ",
+            r"
+                |tests:
+                |  - steps: []
+                |    network:
+                |      - api.example.com:443 -> 127.0.0.1:$PORT
+            ",
+        )
+    }
+
+    #[test]
+    fn refuses_unmapped_destinations() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |(echo foo > /dev/tcp/10.1.2.3/80) 2> /dev/null || true
+            ",
+            r"
+                |tests:
+                |  - steps: []
+                |    network:
+                |      - 10.1.2.3:443 -> 127.0.0.1:8080
+            ",
+            Expect::error_message(
+                r"
+                    |error:
+                    |  blocked network access: connect 10.1.2.3:80
+                ",
+            )?
+            .with_stderr("scriptkeeper: blocked network access: connect 10.1.2.3:80\n"),
+        )
+    }
+}
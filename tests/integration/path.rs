@@ -78,3 +78,220 @@ fn runs_step_executable_that_is_not_in_path() -> R<()> {
     )?;
     Ok(())
 }
+
+mod mocked_executables {
+    use super::*;
+
+    #[test]
+    fn allows_to_mock_executables_that_are_not_installed() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |frobnicate get pods
+            ",
+            r"
+                |tests:
+                |  - steps:
+                |      - command: frobnicate get pods
+                |        stdout: 'pod-1'
+                |mockedExecutables:
+                |  - frobnicate
+            ",
+            Expect::tests_pass().with_stdout("pod-1All tests passed.\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn makes_mocked_executables_visible_to_command_lookups() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |if command -v frobnicate > /dev/null; then
+                |  frobnicate version
+                |fi
+            ",
+            r"
+                |tests:
+                |  - steps:
+                |      - frobnicate version
+                |mockedExecutables:
+                |  - frobnicate
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_mocked_executables_by_name() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |frobnicate delete pods
+            ",
+            r"
+                |tests:
+                |  - steps:
+                |      - frobnicate get pods
+                |mockedExecutables:
+                |  - frobnicate
+            ",
+            Expect::error_message(
+                "
+                    |error:
+                    |  expected: frobnicate get pods
+                    |  received: frobnicate delete pods
+                ",
+            )?,
+        )?;
+        Ok(())
+    }
+}
+
+mod tracee_path {
+    use super::*;
+
+    #[test]
+    fn looks_up_executables_in_the_path_of_the_tested_script() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |cp
+            ",
+            r"
+                |tests:
+                |  - env:
+                |      PATH: /bin
+                |    steps:
+                |      - cp
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn resolves_step_executables_in_the_path_of_the_tested_script() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |cp
+            ",
+            r"
+                |tests:
+                |  - env:
+                |      PATH: /bin
+                |    steps:
+                |      - /bin/cp
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn resolves_unmocked_commands_in_the_path_of_the_tested_script() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |ls > /dev/null
+            ",
+            r"
+                |tests:
+                |  - env:
+                |      PATH: /bin
+                |    steps: []
+                |unmockedCommands:
+                |  - /bin/ls
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn follows_path_changes_inside_the_tested_script() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |export PATH=/bin
+                |cp
+            ",
+            r"
+                |steps:
+                |  - cp
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+}
+
+mod basename_matching {
+    use super::*;
+
+    #[test]
+    fn matches_executables_in_any_directory_by_their_basename() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |/opt/tools/bin/git status
+            ",
+            r"
+                |tests:
+                |  - steps:
+                |      - /usr/bin/git status
+                |executableMatching: basename
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_basenames_in_step_errors() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |/opt/tools/bin/git push
+            ",
+            r"
+                |tests:
+                |  - steps:
+                |      - /usr/bin/git status
+                |executableMatching: basename
+            ",
+            Expect::error_message(
+                "
+                    |error:
+                    |  expected: git status
+                    |  received: git push
+                ",
+            )?,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn compares_paths_by_default() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |/opt/tools/bin/git status
+            ",
+            r"
+                |steps:
+                |  - git status
+            ",
+            Expect::error_message(
+                "
+                    |error:
+                    |  expected: git status
+                    |  received: /opt/tools/bin/git status
+                ",
+            )?,
+        )?;
+        Ok(())
+    }
+}
@@ -21,6 +21,9 @@ mod yaml_formatting {
             &cli::Args::Scriptkeeper {
                 script_path: TempFile::write_temp_script(b"#!/usr/bin/env bash")?.path(),
                 record: true,
+                safe: false,
+                filter: None,
+                list: false,
             },
         )?;
         assert!(context.get_captured_stdout().ends_with('\n'));
@@ -35,6 +38,9 @@ mod yaml_formatting {
             &cli::Args::Scriptkeeper {
                 script_path: TempFile::write_temp_script(b"#!/usr/bin/env bash")?.path(),
                 record: true,
+                safe: false,
+                filter: None,
+                list: false,
             },
         )?;
         assert!(!context.get_captured_stdout().starts_with("---"));
@@ -50,6 +56,9 @@ fn test_recording(script: &str, expected: &str) -> R<()> {
         &cli::Args::Scriptkeeper {
             script_path: script.path(),
             record: true,
+            safe: false,
+            filter: None,
+            list: false,
         },
     )?;
     let output = context.get_captured_stdout();
@@ -85,6 +94,22 @@ fn records_test_steps() -> R<()> {
     )
 }
 
+#[test]
+fn records_executables_found_in_the_path_of_the_tested_script_by_name() -> R<()> {
+    test_recording(
+        "
+            |#!/usr/bin/env bash
+            |export PATH=/bin
+            |ls >/dev/null
+        ",
+        "
+            |tests:
+            |  - steps:
+            |      - ls
+        ",
+    )
+}
+
 #[test]
 fn records_multiple_steps() -> R<()> {
     test_recording(
@@ -117,6 +142,38 @@ fn records_command_arguments() -> R<()> {
     )
 }
 
+#[test]
+fn records_non_utf8_arguments_losslessly() -> R<()> {
+    test_recording(
+        r"
+            |#!/usr/bin/env bash
+            |date --date=$'caf\xe9' 2> /dev/null || true
+        ",
+        r#"
+            |tests:
+            |  - steps:
+            |      - command: "date $'--date=caf\\xe9'"
+            |        exitcode: 1
+        "#,
+    )
+}
+
+#[test]
+fn records_empty_arguments_and_special_characters_faithfully() -> R<()> {
+    test_recording(
+        r#"
+            |#!/usr/bin/env bash
+            |ls '' $'a\tb' "it's" '$HOME' 2> /dev/null || true
+        "#,
+        r#"
+            |tests:
+            |  - steps:
+            |      - command: "ls '' $'a\\tb' \"it's\" \\$HOME"
+            |        exitcode: 2
+        "#,
+    )
+}
+
 #[test]
 fn records_script_exitcode() -> R<()> {
     test_recording(
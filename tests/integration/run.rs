@@ -10,7 +10,7 @@ use scriptkeeper::utils::path_to_string;
 use scriptkeeper::{context::Context, run_scriptkeeper, R};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use test_utils::{assert_error, trim_margin, TempFile};
 
 #[test]
@@ -50,7 +50,7 @@ fn does_not_execute_the_commands() -> R<()> {
         ),
     )?;
     let context = Context::new_mock();
-    run_scriptkeeper(&context, &script.path())?;
+    run_scriptkeeper(&context, &script.path(), false)?;
     assert_eq!(context.get_captured_stdout(), "All tests passed.\n");
     assert!(!testfile.path().exists(), "touch was executed");
     Ok(())
@@ -79,7 +79,7 @@ fn works_for_longer_file_names() -> R<()> {
         ),
     )?;
     let context = Context::new_mock();
-    run_scriptkeeper(&context, &script.path())?;
+    run_scriptkeeper(&context, &script.path(), false)?;
     assert_eq!(context.get_captured_stdout(), "All tests passed.\n");
     Ok(())
 }
@@ -237,7 +237,11 @@ mod nice_user_errors {
     #[test]
     fn nice_error_when_script_does_not_exist() {
         assert_error!(
-            run_scriptkeeper(&Context::new_mock(), &PathBuf::from("./does-not-exist")),
+            run_scriptkeeper(
+                &Context::new_mock(),
+                &PathBuf::from("./does-not-exist"),
+                false
+            ),
             "executable file not found: ./does-not-exist"
         );
     }
@@ -354,6 +358,23 @@ mod nice_user_errors {
 mod arguments {
     use super::*;
 
+    #[test]
+    fn allows_lists_for_script_arguments_and_commands() -> R<()> {
+        test_run(
+            r#"
+                |#!/usr/bin/env bash
+                |cp "$1" "$2"
+            "#,
+            r#"
+                |arguments: ['{"foo": "bar baz"}', ""]
+                |steps:
+                |  - command: [cp, '{"foo": "bar baz"}', ""]
+            "#,
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
     #[test]
     fn arguments() -> R<()> {
         test_run(
@@ -658,6 +679,236 @@ mod multiple_tests {
         )?;
         Ok(())
     }
+
+    mod inheritance {
+        use super::*;
+
+        #[test]
+        fn merges_defaults_into_all_tests() -> R<()> {
+            test_run(
+                r"
+                    |#!/usr/bin/env bash
+                    |cp $FOO
+                    |cp $1
+                ",
+                r"
+                    |defaults:
+                    |  env:
+                    |    FOO: foo
+                    |  steps:
+                    |    - cp foo
+                    |tests:
+                    |  - arguments: bar
+                    |    steps:
+                    |      - cp bar
+                    |  - arguments: baz
+                    |    steps:
+                    |      - cp baz
+                ",
+                Expect::tests_pass(),
+            )?;
+            Ok(())
+        }
+
+        #[test]
+        fn allows_to_extend_named_tests() -> R<()> {
+            test_run(
+                r"
+                    |#!/usr/bin/env bash
+                    |cp $FOO
+                    |for argument in $@; do
+                    |  cp $argument
+                    |done
+                ",
+                r"
+                    |tests:
+                    |  - name: base
+                    |    env:
+                    |      FOO: foo
+                    |    arguments: bar
+                    |    steps:
+                    |      - cp foo
+                    |      - cp bar
+                    |  - extends: base
+                    |    arguments: bar baz
+                    |    steps:
+                    |      - cp baz
+                ",
+                Expect::tests_pass(),
+            )?;
+            Ok(())
+        }
+    }
+}
+
+mod matrix {
+    use super::*;
+
+    #[test]
+    fn runs_the_test_for_all_parameter_values() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |cp $1.conf
+                |if [ $1 == prod ]; then cp backup; fi
+            ",
+            r"
+                |tests:
+                |  - name: deploy
+                |    matrix:
+                |      TARGET: [staging, prod]
+                |    arguments: ${TARGET}
+                |    steps:
+                |      - cp ${TARGET}.conf
+            ",
+            Expect::error_message(
+                "
+                    |test 1 (deploy [TARGET=staging]):
+                    |  Tests passed.
+                    |error in test 2 (deploy [TARGET=prod]):
+                    |  expected: <script termination>
+                    |  received: cp backup
+                ",
+            )?,
+        )
+    }
+}
+
+mod variables {
+    use super::*;
+
+    #[test]
+    fn expands_directories_and_environment_variables() -> R<()> {
+        test_run(
+            r#"
+                |#!/usr/bin/env bash
+                |cp "${0%/*}/config" "$HOME/config"
+                |cp '${HOME}'
+            "#,
+            r"
+                |inheritEnv: [HOME]
+                |steps:
+                |  - cp ${SCRIPT_DIR}/config ${HOME}/config
+                |  - cp $${HOME}
+            ",
+            Expect::tests_pass(),
+        )
+    }
+
+    #[test]
+    fn keeps_directories_with_spaces_in_single_arguments() -> R<()> {
+        let tempfile = TempFile::new()?;
+        let directory = tempfile.path().parent().unwrap().join("my dir");
+        fs::create_dir(&directory)?;
+        let script = directory.join("script");
+        fs::write(
+            &script,
+            trim_margin(
+                r#"
+                    |#!/usr/bin/env bash
+                    |cp "${0%/*}/config" out
+                "#,
+            )?,
+        )?;
+        test_utils::run("chmod", vec!["+x", path_to_string(&script)?])?;
+        fs::write(
+            directory.join("script.test.yaml"),
+            trim_margin(
+                r"
+                    |steps:
+                    |  - cp ${SCRIPT_DIR}/config out
+                ",
+            )?,
+        )?;
+        let context = Context::new_mock();
+        run_scriptkeeper(&context, &script, false)?;
+        assert_eq!(context.get_captured_stdout(), "All tests passed.\n");
+        Ok(())
+    }
+
+    #[test]
+    fn works_for_scripts_given_relative_to_the_current_directory() -> R<()> {
+        let tempfile = TempFile::new_in(Path::new("./target"))?;
+        let script = tempfile.path();
+        fs::write(
+            &script,
+            trim_margin(
+                r#"
+                    |#!/usr/bin/env bash
+                    |cp "$(cd "${0%/*}" && pwd)/config"
+                "#,
+            )?,
+        )?;
+        test_utils::run("chmod", vec!["+x", path_to_string(&script)?])?;
+        fs::write(
+            format!("{}.test.yaml", path_to_string(&script)?),
+            trim_margin(
+                r"
+                    |steps:
+                    |  - cp ${SCRIPT_DIR}/config
+                ",
+            )?,
+        )?;
+        let context = Context::new_mock();
+        run_scriptkeeper(&context, &script, false)?;
+        assert_eq!(context.get_captured_stdout(), "All tests passed.\n");
+        Ok(())
+    }
+}
+
+mod includes {
+    use super::*;
+
+    #[test]
+    fn includes_steps_from_other_files() -> R<()> {
+        let (script, test_file) = prepare_script(
+            r"
+                |#!/usr/bin/env bash
+                |cp foo
+                |cp bar
+            ",
+            r"
+                |steps:
+                |  - include: common/preamble.yaml
+                |  - cp bar
+            ",
+        )?;
+        let common = test_file.parent().unwrap().join("common");
+        fs::create_dir_all(&common)?;
+        fs::write(common.join("preamble.yaml"), "- cp foo")?;
+        let context = Context::new_mock();
+        run_scriptkeeper(&context, &script.path(), false)?;
+        assert_eq!(context.get_captured_stdout(), "All tests passed.\n");
+        Ok(())
+    }
+
+    #[test]
+    fn reports_include_paths_in_errors() -> R<()> {
+        let (script, test_file) = prepare_script(
+            r"
+                |#!/usr/bin/env bash
+                |cp foo
+            ",
+            r"
+                |steps:
+                |  - include: preamble.yaml
+            ",
+        )?;
+        fs::write(
+            test_file.parent().unwrap().join("preamble.yaml"),
+            "- command: cp foo\n  foo: 42",
+        )?;
+        assert_error!(
+            run_scriptkeeper(&Context::new_mock(), &script.path(), false),
+            format!(
+                "error in {}: in included file preamble.yaml: \
+                 unexpected field 'foo', possible values: \
+                 'command', 'stdout', 'exitcode', 'regex', 'notFound', 'execError'",
+                path_to_string(&test_file)?
+            )
+        );
+        Ok(())
+    }
 }
 
 mod environment {
@@ -697,6 +948,104 @@ mod environment {
         )?;
         Ok(())
     }
+
+    #[test]
+    fn inherits_selected_variables_from_the_parent_env() -> R<()> {
+        std::env::set_var("SCRIPTKEEPER_TEST_VAR", "inherited");
+        std::env::set_var("FOO", "bar");
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |cp $SCRIPTKEEPER_TEST_VAR $FOO
+            ",
+            r"
+                |inheritEnv: [SCRIPTKEEPER_TEST_VAR]
+                |steps:
+                |  - cp inherited
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_inherit_for_all_tests_and_unset_variables() -> R<()> {
+        std::env::set_var("SCRIPTKEEPER_TEST_VAR", "inherited");
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |cp $SCRIPTKEEPER_TEST_VAR
+            ",
+            r"
+                |tests:
+                |  - steps:
+                |      - cp inherited
+                |  - unsetEnv: [SCRIPTKEEPER_TEST_VAR]
+                |    steps:
+                |      - cp
+                |inheritEnv: true
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn shows_the_effective_env_on_failures() -> R<()> {
+        std::env::set_var("SCRIPTKEEPER_TEST_VAR", "inherited");
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |cp $FOO
+            ",
+            r"
+                |inheritEnv: [SCRIPTKEEPER_TEST_VAR]
+                |env:
+                |  FOO: foo
+                |steps:
+                |  - cp bar
+            ",
+            Expect::error_message(
+                r"
+                    |error:
+                    |  expected: cp bar
+                    |  received: cp foo
+                    |  environment:
+                    |    FOO=foo
+                    |    SCRIPTKEEPER_TEST_VAR=inherited
+                ",
+            )?,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_show_variables_inherited_with_inherit_env_true() -> R<()> {
+        std::env::set_var("SCRIPTKEEPER_TEST_SECRET", "secret");
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |cp $FOO
+            ",
+            r"
+                |inheritEnv: true
+                |env:
+                |  FOO: foo
+                |steps:
+                |  - cp bar
+            ",
+            Expect::error_message(
+                r"
+                    |error:
+                    |  expected: cp bar
+                    |  received: cp foo
+                    |  environment:
+                    |    FOO=foo
+                ",
+            )?,
+        )?;
+        Ok(())
+    }
 }
 
 #[test]
@@ -797,6 +1146,156 @@ mod mocked_exitcodes {
     }
 }
 
+mod exec_errors {
+    use super::*;
+
+    #[test]
+    fn allows_to_mock_commands_that_are_not_found() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |grep foo 2> /dev/null
+                |ls $?
+            ",
+            r"
+                |steps:
+                |  - command: grep foo
+                |    notFound: true
+                |  - ls 127
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_commands_that_are_not_found_as_missing_in_lookups() -> R<()> {
+        test_run(
+            r#"
+                |#!/usr/bin/env bash
+                |command -v grep || ls missing
+                |[ -x /bin/grep ] || ls missing
+            "#,
+            r"
+                |steps:
+                |  - command: grep
+                |    notFound: true
+                |  - ls missing
+                |  - command: grep
+                |    notFound: true
+                |  - ls missing
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_commands_as_missing_in_one_lookup_per_step() -> R<()> {
+        test_run(
+            r#"
+                |#!/usr/bin/env bash
+                |command -v grep > /dev/null; first=$?
+                |command -v grep > /dev/null; ls $first $?
+            "#,
+            r"
+                |steps:
+                |  - command: grep
+                |    notFound: true
+                |  - ls 1 0
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_mock_commands_with_arguments_that_are_not_installed() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |does-not-exist foo 2> /dev/null
+                |ls $?
+            ",
+            r"
+                |steps:
+                |  - command: does-not-exist foo
+                |    notFound: true
+                |  - ls 127
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn compares_arguments_of_not_found_commands_that_are_not_installed() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |does-not-exist bar 2> /dev/null
+            ",
+            r"
+                |steps:
+                |  - command: does-not-exist foo
+                |    notFound: true
+                |exitcode: 127
+            ",
+            Expect::error_message(
+                r"
+                    |error:
+                    |  expected: does-not-exist foo
+                    |  received: does-not-exist bar
+                ",
+            )?,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn allows_to_mock_execve_errors() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |grep foo 2> /dev/null
+                |ls $?
+            ",
+            r"
+                |steps:
+                |  - command: grep foo
+                |    execError: EACCES
+                |  - ls 126
+            ",
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_mismatches_for_failing_steps() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |grep bar 2> /dev/null
+            ",
+            r"
+                |steps:
+                |  - command: grep foo
+                |    notFound: true
+                |exitcode: 127
+            ",
+            Expect::error_message(
+                r"
+                    |error:
+                    |  expected: grep foo
+                    |  received: grep bar
+                ",
+            )?,
+        )?;
+        Ok(())
+    }
+}
+
 mod working_directory {
     use super::*;
 
@@ -0,0 +1,307 @@
+#![cfg_attr(
+    feature = "dev",
+    allow(dead_code, unused_variables, unused_imports, unreachable_code)
+)]
+#![cfg_attr(feature = "ci", deny(warnings))]
+#![deny(clippy::all)]
+
+use crate::utils::{test_run, Expect};
+use pretty_assertions::assert_eq;
+use scriptkeeper::utils::path_to_string;
+use scriptkeeper::{context::Context, run_scriptkeeper, ExitCode, R};
+use std::fs;
+use std::os::unix::fs::symlink;
+use tempdir::TempDir;
+use test_utils::{trim_margin, TempFile};
+
+fn test_run_in_tempdir(
+    tempdir: &TempDir,
+    script_code: &str,
+    tests: &str,
+    expected: Expect,
+) -> R<()> {
+    let dir = path_to_string(tempdir.path())?;
+    test_run(
+        &script_code.replace("$DIR", dir),
+        &tests.replace("$DIR", dir),
+        expected,
+    )
+}
+
+#[test]
+fn blocks_writes_in_safe_mode() -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    let stderr = format!(
+        "scriptkeeper: blocked in safe mode: write {}/file\n",
+        path_to_string(tempdir.path())?
+    );
+    test_run_in_tempdir(
+        &tempdir,
+        r"
+            |#!/usr/bin/env bash
+            |(echo foo > $DIR/file) 2> /dev/null || echo blocked
+        ",
+        r#"
+            |tests:
+            |  - steps: []
+            |    stdout: "blocked\n"
+            |safe: true
+        "#,
+        Expect::error_message(&format!(
+            r"
+                |blocked
+                |error:
+                |  blocked in safe mode: write {}/file
+            ",
+            path_to_string(tempdir.path())?
+        ))?
+        .with_stderr(&stderr),
+    )?;
+    assert!(!tempdir.path().join("file").exists());
+    Ok(())
+}
+
+#[test]
+fn blocks_deleting_files_in_unmocked_commands() -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    fs::write(tempdir.path().join("file"), "foo")?;
+    let stderr = format!(
+        "scriptkeeper: blocked in safe mode: delete {}/file\n",
+        path_to_string(tempdir.path())?
+    );
+    test_run_in_tempdir(
+        &tempdir,
+        r"
+            |#!/usr/bin/env bash
+            |rm $DIR/file 2> /dev/null
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    exitcode: 1
+            |unmockedCommands:
+            |  - rm
+            |safe: true
+        ",
+        Expect::error_message(&format!(
+            r"
+                |error:
+                |  blocked in safe mode: delete {}/file
+            ",
+            path_to_string(tempdir.path())?
+        ))?
+        .with_stderr(&stderr),
+    )?;
+    assert_eq!(fs::read_to_string(tempdir.path().join("file"))?, "foo");
+    Ok(())
+}
+
+#[test]
+fn allows_writes_to_allowed_paths() -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    test_run_in_tempdir(
+        &tempdir,
+        r"
+            |#!/usr/bin/env bash
+            |echo foo > $DIR/file
+            |mv $DIR/file $DIR/renamed
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |unmockedCommands:
+            |  - mv
+            |safe: true
+            |allowedWrites:
+            |  - $DIR
+        ",
+        Expect::tests_pass(),
+    )?;
+    assert_eq!(fs::read_to_string(tempdir.path().join("renamed"))?, "foo\n");
+    Ok(())
+}
+
+#[test]
+fn blocks_writes_through_symlinks_in_allowed_paths() -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    fs::create_dir(tempdir.path().join("allowed"))?;
+    fs::create_dir(tempdir.path().join("outside"))?;
+    symlink(
+        tempdir.path().join("outside"),
+        tempdir.path().join("allowed/link"),
+    )?;
+    let stderr = format!(
+        "scriptkeeper: blocked in safe mode: write {}/allowed/link/file\n",
+        path_to_string(tempdir.path())?
+    );
+    test_run_in_tempdir(
+        &tempdir,
+        r"
+            |#!/usr/bin/env bash
+            |(echo foo > $DIR/allowed/link/file) 2> /dev/null || echo blocked
+        ",
+        r#"
+            |tests:
+            |  - steps: []
+            |    stdout: "blocked\n"
+            |safe: true
+            |allowedWrites:
+            |  - $DIR/allowed
+        "#,
+        Expect::error_message(&format!(
+            r"
+                |blocked
+                |error:
+                |  blocked in safe mode: write {}/allowed/link/file
+            ",
+            path_to_string(tempdir.path())?
+        ))?
+        .with_stderr(&stderr),
+    )?;
+    assert!(!tempdir.path().join("outside/file").exists());
+    Ok(())
+}
+
+#[test]
+fn blocks_writes_through_openat2() -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    let dir = path_to_string(tempdir.path())?;
+    test_run_in_tempdir(
+        &tempdir,
+        r#"
+            |#!/usr/bin/env perl
+            |my $path = "$DIR/file";
+            |my $how = pack("QQQ", 0101, 0644, 0);
+            |syscall(437, -100, $path, $how, 24);
+        "#,
+        r"
+            |tests:
+            |  - steps: []
+            |safe: true
+        ",
+        Expect::error_message(&format!(
+            r"
+                |error:
+                |  blocked in safe mode: write {}/file
+            ",
+            dir
+        ))?
+        .with_stderr(&format!(
+            "scriptkeeper: blocked in safe mode: write {}/file\n",
+            dir
+        )),
+    )?;
+    assert!(!tempdir.path().join("file").exists());
+    Ok(())
+}
+
+#[test]
+fn blocks_writes_to_devices_other_than_harmless_ones() -> R<()> {
+    let tempdir = TempDir::new_in("/dev/shm", "test")?;
+    let dir = path_to_string(tempdir.path())?;
+    test_run_in_tempdir(
+        &tempdir,
+        r"
+            |#!/usr/bin/env bash
+            |echo foo > /dev/null
+            |(echo foo > $DIR/file) 2> /dev/null
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    exitcode: 1
+            |safe: true
+        ",
+        Expect::error_message(&format!(
+            r"
+                |error:
+                |  blocked in safe mode: write {}/file
+            ",
+            dir
+        ))?
+        .with_stderr(&format!(
+            "scriptkeeper: blocked in safe mode: write {}/file\n",
+            dir
+        )),
+    )?;
+    assert!(!tempdir.path().join("file").exists());
+    Ok(())
+}
+
+#[test]
+fn ignores_syscalls_with_invalid_directory_file_descriptors() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env perl
+            |my $path = 'file';
+            |syscall(263, -2, $path, 0) == -1 or die;
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |safe: true
+        ",
+        Expect::tests_pass(),
+    )
+}
+
+#[test]
+fn does_not_block_anything_without_safe_mode() -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    test_run_in_tempdir(
+        &tempdir,
+        r"
+            |#!/usr/bin/env bash
+            |echo foo > $DIR/file
+        ",
+        r"
+            |tests:
+            |  - steps: []
+        ",
+        Expect::tests_pass(),
+    )?;
+    assert!(tempdir.path().join("file").exists());
+    Ok(())
+}
+
+#[test]
+fn allows_to_enable_safe_mode_from_the_command_line() -> R<()> {
+    let tempdir = TempDir::new("test")?;
+    let dir = path_to_string(tempdir.path())?;
+    let context = Context::new_mock();
+    let script = TempFile::write_temp_script(
+        trim_margin(
+            r"
+                |#!/usr/bin/env bash
+                |truncate -s 0 $DIR/file 2> /dev/null
+            ",
+        )?
+        .replace("$DIR", dir)
+        .as_bytes(),
+    )?;
+    fs::write(tempdir.path().join("file"), "foo")?;
+    fs::write(
+        script.path().with_extension("test.yaml"),
+        trim_margin(
+            r"
+                |tests:
+                |  - steps: []
+                |    exitcode: 1
+                |unmockedCommands:
+                |  - truncate
+            ",
+        )?,
+    )?;
+    let exitcode = run_scriptkeeper(&context, &script.path(), true)?;
+    assert_eq!(
+        context.get_captured_stderr(),
+        format!("scriptkeeper: blocked in safe mode: write {}/file\n", dir)
+    );
+    assert_eq!(
+        context.get_captured_stdout(),
+        format!("error:\n  blocked in safe mode: write {}/file\n", dir)
+    );
+    assert_eq!(exitcode, ExitCode(1));
+    assert_eq!(fs::read_to_string(tempdir.path().join("file"))?, "foo");
+    Ok(())
+}
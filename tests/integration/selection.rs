@@ -0,0 +1,265 @@
+#![cfg_attr(
+    feature = "dev",
+    allow(dead_code, unused_variables, unused_imports, unreachable_code)
+)]
+#![cfg_attr(feature = "ci", deny(warnings))]
+#![deny(clippy::all)]
+
+use crate::utils::{assert_eq_yaml, prepare_script, test_run, Expect};
+use pretty_assertions::assert_eq;
+use scriptkeeper::context::Context;
+use scriptkeeper::{cli, run_main, ExitCode, R};
+use std::fs;
+use test_utils::{assert_error, trim_margin};
+
+const SCRIPT: &str = r"
+    |#!/usr/bin/env bash
+    |cp $1
+";
+
+const TESTS: &str = r"
+    |tests:
+    |  - name: foo
+    |    arguments: foo
+    |    steps:
+    |      - cp foo
+    |  - name: bar
+    |    arguments: bar
+    |    steps:
+    |      - cp wrong
+    |  - arguments: baz
+    |    steps:
+    |      - cp baz
+";
+
+fn run_with_args(tests: &str, filter: Option<&str>, list: bool) -> R<(ExitCode, String)> {
+    let (script, _) = prepare_script(SCRIPT, tests)?;
+    let context = Context::new_mock();
+    let exitcode = run_main(
+        &context,
+        &cli::Args::Scriptkeeper {
+            script_path: script.path(),
+            record: false,
+            safe: false,
+            filter: filter.map(String::from),
+            list,
+        },
+    )?;
+    Ok((exitcode, context.get_captured_stdout()))
+}
+
+#[test]
+fn shows_test_names_in_results() -> R<()> {
+    test_run(
+        SCRIPT,
+        TESTS,
+        Expect::error_message(
+            "
+                |test 1 (foo):
+                |  Tests passed.
+                |error in test 2 (bar):
+                |  expected: cp wrong
+                |  received: cp bar
+                |test 3:
+                |  Tests passed.
+            ",
+        )?,
+    )
+}
+
+mod filter {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn only_runs_tests_with_matching_names() -> R<()> {
+        assert_eq!(
+            run_with_args(TESTS, Some("^fo"), false)?,
+            (ExitCode(0), "All tests passed.\n".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_the_numbers_of_the_tests() -> R<()> {
+        assert_eq!(
+            run_with_args(TESTS, Some("o|a"), false)?,
+            (
+                ExitCode(1),
+                trim_margin(
+                    "
+                        |test 1 (foo):
+                        |  Tests passed.
+                        |error in test 2 (bar):
+                        |  expected: cp wrong
+                        |  received: cp bar
+                    "
+                )?
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shows_the_test_label_when_only_one_test_is_selected() -> R<()> {
+        assert_eq!(
+            run_with_args(TESTS, Some("^bar$"), false)?,
+            (
+                ExitCode(1),
+                trim_margin(
+                    "
+                        |error in test 2 (bar):
+                        |  expected: cp wrong
+                        |  received: cp bar
+                    "
+                )?
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors_when_no_tests_match() {
+        assert_error!(
+            run_with_args(TESTS, Some("qux"), false),
+            "no tests selected"
+        );
+    }
+
+    #[test]
+    fn errors_on_invalid_patterns() {
+        assert_error!(
+            run_with_args(TESTS, Some("("), false),
+            "invalid filter: regex parse error:\n    (\n    ^\nerror: unclosed group"
+        );
+    }
+}
+
+mod skip_and_only {
+    use super::*;
+
+    #[test]
+    fn does_not_run_skipped_tests() -> R<()> {
+        test_run(
+            SCRIPT,
+            r"
+                |- arguments: foo
+                |  steps:
+                |    - cp foo
+                |- arguments: bar
+                |  skip: true
+                |  steps:
+                |    - cp wrong
+            ",
+            Expect::tests_pass(),
+        )
+    }
+
+    #[test]
+    fn only_runs_tests_marked_with_only() -> R<()> {
+        test_run(
+            SCRIPT,
+            r"
+                |- arguments: foo
+                |  only: true
+                |  steps:
+                |    - cp foo
+                |- arguments: bar
+                |  steps:
+                |    - cp wrong
+            ",
+            Expect::tests_pass(),
+        )
+    }
+
+    #[test]
+    fn keeps_skipped_tests_when_filling_in_holes() -> R<()> {
+        let (script, test_file) = prepare_script(
+            r"
+                |#!/usr/bin/env bash
+                |ls
+            ",
+            r"
+                |tests:
+                |  - arguments: foo
+                |    steps:
+                |      - _
+                |  - arguments: bar
+                |    skip: true
+                |    steps:
+                |      - cp wrong
+                |      - _
+            ",
+        )?;
+        run_main(
+            &Context::new_mock(),
+            &cli::Args::Scriptkeeper {
+                script_path: script.path(),
+                record: false,
+                safe: false,
+                filter: None,
+                list: false,
+            },
+        )?;
+        assert_eq_yaml(
+            &String::from_utf8(fs::read(&test_file)?)?,
+            &trim_margin(
+                r"
+                    |tests:
+                    |  - arguments: foo
+                    |    steps:
+                    |      - ls
+                    |  - skip: true
+                    |    arguments: bar
+                    |    steps:
+                    |      - cp wrong
+                    |      - _
+                ",
+            )?,
+        )
+    }
+}
+
+mod list {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn lists_the_tests_with_their_number_of_steps() -> R<()> {
+        assert_eq!(
+            run_with_args(
+                r"
+                    |tests:
+                    |  - name: foo
+                    |    steps:
+                    |      - cp foo
+                    |      - cp bar
+                    |  - skip: true
+                    |    steps:
+                    |      - cp baz
+                ",
+                None,
+                true
+            )?,
+            (
+                ExitCode(0),
+                trim_margin(
+                    "
+                        |test 1 (foo): 2 steps
+                        |test 2: 1 step, skipped
+                    "
+                )?
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn only_lists_tests_matching_the_filter() -> R<()> {
+        assert_eq!(
+            run_with_args(TESTS, Some("bar"), true)?,
+            (ExitCode(0), "test 2 (bar): 1 step\n".to_string())
+        );
+        Ok(())
+    }
+}
@@ -205,3 +205,580 @@ mod expected_stderr {
         Ok(())
     }
 }
+
+mod stdin {
+    use super::*;
+    use crate::utils::test_run_with_tempfile;
+    use scriptkeeper::context::Context;
+    use scriptkeeper::ExitCode;
+    use std::fs;
+    use test_utils::{trim_margin, TempFile};
+
+    #[test]
+    fn feeds_the_given_string_to_the_tested_script() -> R<()> {
+        test_run(
+            r#"
+                |#!/usr/bin/env bash
+                |read -p "Continue? " answer
+                |echo "answer: $answer"
+            "#,
+            r#"
+                |tests:
+                |  - steps: []
+                |    stdin: "yes\n"
+                |    stdout: "answer: yes\n"
+            "#,
+            Expect::tests_pass().with_stdout("answer: yes\nAll tests passed.\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn connects_stdin_to_dev_null_by_default() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |if read line; then echo read; else echo eof; fi
+            ",
+            r"
+                |tests:
+                |  - steps: []
+            ",
+            Expect::tests_pass().with_stdout("eof\nAll tests passed.\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn feeds_fixture_files_relative_to_the_test_file() -> R<()> {
+        let context = &Context::new_mock();
+        let script = TempFile::write_temp_script(
+            trim_margin(
+                r"
+                    |#!/usr/bin/env bash
+                    |while read line; do echo got $line; done
+                ",
+            )?
+            .as_bytes(),
+        )?;
+        fs::write(script.path().with_file_name("input.txt"), "a\nb\n")?;
+        let exit_code = test_run_with_tempfile(
+            context,
+            &script,
+            r"
+                |tests:
+                |  - steps: []
+                |    stdin:
+                |      file: input.txt
+            ",
+        )?;
+        assert_eq!(
+            (exit_code, context.get_captured_stdout()),
+            (ExitCode(0), "got a\ngot b\nAll tests passed.\n".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_fail_when_the_script_ignores_stdin() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |true
+            ",
+            &format!(
+                r"
+                    |tests:
+                    |  - steps: []
+                    |    stdin: {}
+                ",
+                "x".repeat(200_000)
+            ),
+            Expect::tests_pass(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_missing_fixture_files() -> R<()> {
+        let context = &Context::new_mock();
+        let script = TempFile::write_temp_script(b"#!/usr/bin/env bash\ntrue\n")?;
+        let result = test_run_with_tempfile(
+            context,
+            &script,
+            r"
+                |tests:
+                |  - steps: []
+                |    stdin:
+                |      file: missing.txt
+            ",
+        );
+        assert_eq!(
+            result.map_err(|error| error.to_string()),
+            Err(format!(
+                "cannot open stdin file {}: ENOENT: No such file or directory",
+                script
+                    .path()
+                    .with_file_name("missing.txt")
+                    .to_string_lossy()
+            ))
+        );
+        Ok(())
+    }
+}
+
+mod interaction {
+    use super::*;
+
+    #[test]
+    fn answers_prompts_when_they_appear_on_stdout() -> R<()> {
+        test_run(
+            r#"
+                |#!/usr/bin/env bash
+                |printf "Deploy to prod? "
+                |read answer
+                |printf "Are you sure? "
+                |read confirmation
+                |if [ "$answer$confirmation" == "yy" ]; then
+                |  echo deploying
+                |fi
+            "#,
+            r#"
+                |tests:
+                |  - steps: []
+                |    interaction:
+                |      - expect: "Deploy to prod? "
+                |        send: "y\n"
+                |      - expect: "Are you sure? "
+                |        send: "y\n"
+                |    stdout: "Deploy to prod? Are you sure? deploying\n"
+            "#,
+            Expect::tests_pass()
+                .with_stdout("Deploy to prod? Are you sure? deploying\nAll tests passed.\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn fails_when_a_prompt_does_not_appear_before_the_timeout() -> R<()> {
+        test_run(
+            r#"
+                |#!/usr/bin/env bash
+                |printf "Continue? "
+                |read answer
+            "#,
+            r#"
+                |tests:
+                |  - steps: []
+                |    interaction:
+                |      - expect: "Deploy to prod? "
+                |        send: "y\n"
+                |        timeout: 1
+                |    exitcode: 1
+            "#,
+            Expect::error_message(
+                r#"
+                    |Continue? error:
+                    |  expected: output to stdout: "Deploy to prod? "
+                    |  received: <timeout after 1s>
+                "#,
+            )?,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn fails_when_the_script_terminates_before_a_prompt_appears() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |echo done
+            ",
+            r#"
+                |tests:
+                |  - steps: []
+                |    interaction:
+                |      - expect: "Continue? "
+                |        send: "y\n"
+            "#,
+            Expect::error_message(
+                r#"
+                    |done
+                    |error:
+                    |  expected: output to stdout: "Continue? "
+                    |  received: <script terminated>
+                "#,
+            )?,
+        )?;
+        Ok(())
+    }
+}
+
+mod output_matchers {
+    use super::*;
+
+    #[test]
+    fn passes_when_all_matchers_match() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |echo 'started at 12:03:55  '
+                |echo 'took 42ms'
+            ",
+            r#"
+                |tests:
+                |  - steps: []
+                |    stdout:
+                |      contains: started at
+                |      notContains: error
+                |      regex: 'took \d+ms'
+                |      lines: ["took 42ms", "started at 12:03:55"]
+                |      trimTrailingWhitespace: true
+            "#,
+            Expect::tests_pass()
+                .with_stdout("started at 12:03:55  \ntook 42ms\nAll tests passed.\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_mismatches() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |echo 'fatal error' 1>&2
+            ",
+            r"
+                |tests:
+                |  - steps: []
+                |    stderr:
+                |      notContains: error
+            ",
+            Expect::error_message(
+                r#"
+                    |error:
+                    |  expected output to stderr not to contain: "error"
+                    |  received output to stderr: "fatal error\n"
+                "#,
+            )?
+            .with_stderr("fatal error\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn reports_normalized_output() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |printf 'foo  \n' 1>&2
+            ",
+            r#"
+                |tests:
+                |  - steps: []
+                |    stderr:
+                |      equals: "bar\n"
+                |      trimTrailingWhitespace: true
+            "#,
+            Expect::error_message(
+                r#"
+                    |error:
+                    |  expected output to stderr: "bar\n"
+                    |  received output to stderr: "foo\n"
+                "#,
+            )?
+            .with_stderr("foo  \n"),
+        )?;
+        Ok(())
+    }
+}
+
+mod output_diffs {
+    use super::*;
+
+    #[test]
+    fn shows_line_based_diffs_for_multi_line_outputs() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |printf 'a\nb\nc\n' 1>&2
+            ",
+            r#"
+                |tests:
+                |  - steps: []
+                |    stderr: "a\nx\nc\n"
+            "#,
+            Expect::error_message(
+                r"
+                    |error:
+                    |  unexpected output to stderr (- expected, + received):
+                    |    @@ -1,3 +1,3 @@
+                    |     a
+                    |    -x
+                    |    +b
+                    |     c
+                ",
+            )?
+            .with_stderr("a\nb\nc\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn only_shows_the_context_around_differences() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |for i in {1..20}; do
+                |  if [ $i = 10 ]; then echo ten; else echo $i; fi
+                |done 1>&2
+            ",
+            r#"
+                |tests:
+                |  - steps: []
+                |    stderr: "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n17\n18\n19\n20\n"
+            "#,
+            Expect::error_message(
+                r"
+                    |error:
+                    |  unexpected output to stderr (- expected, + received):
+                    |    @@ -7,7 +7,7 @@
+                    |     7
+                    |     8
+                    |     9
+                    |    -10
+                    |    +ten
+                    |     11
+                    |     12
+                    |     13
+                ",
+            )?
+            .with_stderr(
+                "1\n2\n3\n4\n5\n6\n7\n8\n9\nten\n11\n12\n13\n14\n15\n16\n17\n18\n19\n20\n",
+            ),
+        )?;
+        Ok(())
+    }
+}
+
+mod output_fixtures {
+    use super::*;
+    use crate::utils::test_run_with_tempfile;
+    use pretty_assertions::assert_eq;
+    use scriptkeeper::context::Context;
+    use scriptkeeper::ExitCode;
+    use std::fs;
+    use test_utils::{trim_margin, TempFile};
+
+    fn run_with_fixtures(
+        script_code: &str,
+        tests: &str,
+        fixtures: &[(&str, &str)],
+    ) -> R<(ExitCode, String)> {
+        let context = &Context::new_mock();
+        let script = TempFile::write_temp_script(trim_margin(script_code)?.as_bytes())?;
+        for (path, content) in fixtures {
+            let path = script.path().with_file_name(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+        let exit_code = test_run_with_tempfile(context, &script, tests)?;
+        Ok((exit_code, context.get_captured_stdout()))
+    }
+
+    #[test]
+    fn compares_stdout_against_fixture_files() -> R<()> {
+        assert_eq!(
+            run_with_fixtures(
+                r"
+                    |#!/usr/bin/env bash
+                    |echo foo
+                    |echo bar
+                ",
+                r"
+                    |tests:
+                    |  - steps: []
+                    |    stdout:
+                    |      file: fixtures/output.txt
+                ",
+                &[("fixtures/output.txt", "foo\nbar\n")],
+            )?,
+            (ExitCode(0), "foo\nbar\nAll tests passed.\n".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_mismatches_against_fixture_files() -> R<()> {
+        assert_eq!(
+            run_with_fixtures(
+                r"
+                    |#!/usr/bin/env bash
+                    |echo foo 1>&2
+                    |echo baz 1>&2
+                ",
+                r"
+                    |tests:
+                    |  - steps: []
+                    |    stderr:
+                    |      file: output.txt
+                ",
+                &[("output.txt", "foo\nbar\n")],
+            )?,
+            (
+                ExitCode(1),
+                trim_margin(
+                    r"
+                        |error:
+                        |  unexpected output to stderr (- expected, + received):
+                        |    @@ -1,2 +1,2 @@
+                        |     foo
+                        |    -bar
+                        |    +baz
+                    "
+                )?
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn uses_fixture_files_as_mocked_stdout() -> R<()> {
+        assert_eq!(
+            run_with_fixtures(
+                r"
+                    |#!/usr/bin/env bash
+                    |output=$(cp)
+                    |cp $output
+                ",
+                r"
+                    |steps:
+                    |  - command: cp
+                    |    stdout:
+                    |      file: fixtures/cp-output.txt
+                    |  - cp from-fixture
+                ",
+                &[("fixtures/cp-output.txt", "from-fixture\n")],
+            )?,
+            (ExitCode(0), "All tests passed.\n".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_missing_fixture_files() -> R<()> {
+        let context = &Context::new_mock();
+        let script = TempFile::write_temp_script(b"#!/usr/bin/env bash\ntrue\n")?;
+        let result = test_run_with_tempfile(
+            context,
+            &script,
+            r"
+                |tests:
+                |  - steps: []
+                |    stdout:
+                |      file: missing.txt
+            ",
+        );
+        assert_eq!(
+            result.map_err(|error| error.to_string()),
+            Err(format!(
+                "error in {}: cannot read fixture file {}: No such file or directory (os error 2)",
+                script.path().with_extension("test.yaml").to_string_lossy(),
+                script
+                    .path()
+                    .with_file_name("missing.txt")
+                    .to_string_lossy()
+            ))
+        );
+        Ok(())
+    }
+}
+
+mod binary_data {
+    use super::*;
+    use crate::utils::test_run_with_tempfile;
+    use scriptkeeper::context::Context;
+    use scriptkeeper::ExitCode;
+    use test_utils::{trim_margin, TempFile};
+
+    #[test]
+    fn allows_binary_mocked_stdout() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |if [[ $(cp) == $'\xff\xfe' ]]; then echo match; fi
+            ",
+            r"
+                |steps:
+                |  - command: cp
+                |    stdout: !!binary //4=
+            ",
+            Expect::tests_pass().with_stdout("match\nAll tests passed.\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn allows_binary_stdin() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |read -r line
+                |if [[ $line == $'\xff\xfe' ]]; then echo match; fi
+            ",
+            r"
+                |steps: []
+                |stdin: !!binary //4K
+            ",
+            Expect::tests_pass().with_stdout("match\nAll tests passed.\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn allows_non_utf8_arguments() -> R<()> {
+        test_run(
+            r"
+                |#!/usr/bin/env bash
+                |if [[ $1 == $'caf\xe9' ]]; then echo match; fi
+                |cp $'x\xff'
+            ",
+            r"
+                |arguments: caf\xe9
+                |steps:
+                |  - cp x\xff
+            ",
+            Expect::tests_pass().with_stdout("match\nAll tests passed.\n"),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn compares_binary_output_byte_by_byte() -> R<()> {
+        let script = TempFile::write_temp_script(
+            trim_margin(
+                r"
+                    |#!/usr/bin/env bash
+                    |printf '\x1f\x8b\x08\x00' 1>&2
+                ",
+            )?
+            .as_bytes(),
+        )?;
+        let run = |expected: &str| -> R<ExitCode> {
+            test_run_with_tempfile(
+                &Context::new_mock(),
+                &script,
+                &format!(
+                    r"
+                        |steps: []
+                        |stderr: !!binary {}
+                    ",
+                    expected
+                ),
+            )
+        };
+        assert_eq!(run("H4sIAA==")?, ExitCode(0));
+        assert_eq!(run("H4sIAQ==")?, ExitCode(1));
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+#![cfg_attr(
+    feature = "dev",
+    allow(dead_code, unused_variables, unused_imports, unreachable_code)
+)]
+#![cfg_attr(feature = "ci", deny(warnings))]
+#![deny(clippy::all)]
+
+use crate::utils::{test_run, Expect};
+use scriptkeeper::R;
+
+#[test]
+fn uses_pipes_by_default() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |if [ -t 0 ] || [ -t 1 ]; then echo tty; else echo pipe; fi
+        ",
+        r#"
+            |tests:
+            |  - steps: []
+            |    stdout: "pipe\n"
+        "#,
+        Expect::tests_pass().with_stdout("pipe\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn runs_the_script_in_a_terminal() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |if [ -t 0 ] && [ -t 1 ] && [ -t 2 ]; then echo tty; else echo pipe; fi
+        ",
+        r#"
+            |tests:
+            |  - steps: []
+            |    tty: true
+            |    stdout: "tty\n"
+        "#,
+        Expect::tests_pass().with_stdout("tty\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn allows_to_specify_the_window_size() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |stty size
+        ",
+        r#"
+            |unmockedCommands: [stty]
+            |tests:
+            |  - steps: []
+            |    tty:
+            |      rows: 50
+            |      columns: 200
+            |    stdout: "50 200\n"
+        "#,
+        Expect::tests_pass().with_stdout("50 200\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn writes_stderr_to_the_terminal() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |echo foo
+            |echo bar >&2
+        ",
+        r#"
+            |tests:
+            |  - steps: []
+            |    tty: true
+            |    stdout: "foo\nbar\n"
+        "#,
+        Expect::tests_pass().with_stdout("foo\nbar\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn feeds_stdin_through_the_terminal() -> R<()> {
+    test_run(
+        r#"
+            |#!/usr/bin/env bash
+            |while read line; do echo "got $line"; done
+            |echo done
+        "#,
+        r#"
+            |tests:
+            |  - steps: []
+            |    tty: true
+            |    stdin: "foo\nbar\n"
+        "#,
+        Expect::tests_pass().with_stdout("got foo\ngot bar\ndone\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn answers_prompts_in_the_terminal() -> R<()> {
+    test_run(
+        r#"
+            |#!/usr/bin/env bash
+            |read -p "Continue? " answer
+            |echo "answer: $answer"
+        "#,
+        r#"
+            |tests:
+            |  - steps: []
+            |    tty: true
+            |    interaction:
+            |      - expect: "Continue? "
+            |        send: "y\n"
+        "#,
+        Expect::tests_pass().with_stdout("Continue? answer: y\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn allows_to_strip_ansi_escape_sequences() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |if [ -t 1 ]; then
+            |  printf '\e[31mred\e[0m\n'
+            |fi
+        ",
+        r#"
+            |tests:
+            |  - steps: []
+            |    tty: true
+            |    stdout: "red\n"
+            |    stripAnsi: true
+        "#,
+        Expect::tests_pass().with_stdout("\u{1b}[31mred\u{1b}[0m\nAll tests passed.\n"),
+    )
+}
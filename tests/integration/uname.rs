@@ -0,0 +1,64 @@
+#![cfg_attr(
+    feature = "dev",
+    allow(dead_code, unused_variables, unused_imports, unreachable_code)
+)]
+#![cfg_attr(feature = "ci", deny(warnings))]
+#![deny(clippy::all)]
+
+use crate::utils::{test_run, Expect};
+use scriptkeeper::R;
+
+#[test]
+fn allows_to_mock_the_host_name() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |echo $HOSTNAME
+        ",
+        r"
+            |tests:
+            |  - steps: []
+            |    uname:
+            |      nodename: prod-1
+        ",
+        Expect::tests_pass().with_stdout("prod-1\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn allows_to_mock_fields_for_unmocked_commands() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |uname -s -r -m
+        ",
+        r"
+            |unmockedCommands: [uname]
+            |tests:
+            |  - steps: []
+            |    uname:
+            |      sysname: Darwin
+            |      release: 18.7.0
+            |      machine: arm64
+        ",
+        Expect::tests_pass().with_stdout("Darwin 18.7.0 arm64\nAll tests passed.\n"),
+    )
+}
+
+#[test]
+fn keeps_the_real_values_of_fields_that_are_not_mocked() -> R<()> {
+    test_run(
+        r"
+            |#!/usr/bin/env bash
+            |uname -s
+        ",
+        r"
+            |unmockedCommands: [uname]
+            |tests:
+            |  - steps: []
+            |    uname:
+            |      nodename: prod-1
+        ",
+        Expect::tests_pass().with_stdout("Linux\nAll tests passed.\n"),
+    )
+}
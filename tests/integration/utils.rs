@@ -65,7 +65,7 @@ pub fn test_run_with_tempfile(context: &Context, script: &TempFile, tests: &str)
         script.path().with_extension("test.yaml"),
         trim_margin(tests)?,
     )?;
-    run_scriptkeeper(context, &script.path())
+    run_scriptkeeper(context, &script.path(), false)
 }
 
 pub fn test_run(script_code: &str, tests: &str, expected: Expect) -> R<()> {